@@ -33,11 +33,3 @@ app.listen(PORT, async () => {
   await treasuryService.initializeTreasury();
 });
 
-
-app.post("/submit_block", (req, res) => {
-  const { window_id, ordered_hash } = req.body;
-  console.log(`🧩 Validator received block: window=${window_id} hash=${ordered_hash.slice(0, 16)}`);
-  res.json({ status: "accepted", slot: window_id });
-});
-
-app.listen(4000, () => console.log("🪄 Mock validator running on port 4000"));
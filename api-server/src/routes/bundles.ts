@@ -6,14 +6,58 @@ import { metricsService } from "../services/metrics";
 const router = express.Router();
 const redis = new Redis(process.env.REDIS_URL || "redis://localhost:6379");
 
+const ENVELOPE_KEY_KEY = "bundle_envelope:public_key";
+
+// Set when the engine runs with source = "stream"; otherwise bundles go to per-window lists
+const BUNDLE_STREAM_KEY = process.env.BUNDLE_STREAM_KEY;
+
+// Queue a bundle or envelope where the block engine's configured source reads it
+async function queueBundle(window_id: number, payload: object) {
+  if (BUNDLE_STREAM_KEY) {
+    await redis.xadd(BUNDLE_STREAM_KEY, "*", "bundle", JSON.stringify(payload));
+  } else {
+    await redis.rpush(`bundle_window:${window_id}`, JSON.stringify(payload));
+  }
+}
+
+// --- GET /api/bundles/envelope_key ---
+// Key searchers seal bundles to, as published by the block engine
+router.get("/envelope_key", async (req, res) => {
+  const key = await redis.get(ENVELOPE_KEY_KEY);
+  if (!key) {
+    return res.status(404).json({ error: "Encrypted bundles are not enabled" });
+  }
+  return res.status(200).json(JSON.parse(key));
+});
+
 // --- POST /api/bundles ---
 router.post("/", async (req, res) => {
   try {
-    const { transactions, tip, searcher_pubkey } = req.body;
+    // Encrypted bundles are queued untouched for the window they were sealed for;
+    // only the engine can open them, and only once that window has closed
+    const { envelope } = req.body;
+    if (envelope) {
+      const { version, key_id, window_id, ephemeral_key, nonce, ciphertext } = envelope;
+      if (!version || !key_id || !Number.isInteger(window_id) || !ephemeral_key || !nonce || !ciphertext) {
+        return res.status(400).json({ error: "Invalid envelope format" });
+      }
+      const current_window = Math.floor(Date.now() / 200);
+      if (window_id < current_window) {
+        return res.status(400).json({ error: "Envelope targets a window that has already closed" });
+      }
+      await queueBundle(window_id, { version, key_id, window_id, ephemeral_key, nonce, ciphertext });
+      return res.status(200).json({ status: "queued", window_id, encrypted: true });
+    }
+
+    const { transactions, tip, searcher_pubkey, post_conditions } = req.body;
 
     if (!transactions || !Array.isArray(transactions) || !tip || !searcher_pubkey) {
       return res.status(400).json({ error: "Invalid bundle format" });
     }
+    // Checked by the engine's simulator; passed through as submitted
+    if (post_conditions !== undefined && !Array.isArray(post_conditions)) {
+      return res.status(400).json({ error: "post_conditions must be an array" });
+    }
 
     // Generate bundle ID
     const bundle_id = crypto.randomBytes(8).toString("hex");
@@ -24,11 +68,12 @@ router.post("/", async (req, res) => {
       tip,
       searcher_pubkey,
       timestamp: Date.now(),
+      ...(post_conditions ? { post_conditions } : {}),
     };
 
     // Push to Redis queue for the current time window
     const window_id = Math.floor(Date.now() / 200); // 200ms "slot window"
-    await redis.rpush(`bundle_window:${window_id}`, JSON.stringify(bundle));
+    await queueBundle(window_id, bundle);
 
     // Record bundle metrics
     await metricsService.recordBundle(bundle);
@@ -12,7 +12,6 @@ use solana_sdk::{
     system_instruction,
     transaction::Transaction,
 };
-use std::time::Duration;
 use tokio::runtime::Runtime;
 
 fn create_test_bundle(tip: u64, tx_count: usize) -> Bundle {
@@ -30,8 +29,6 @@ fn create_test_bundle(tip: u64, tx_count: usize) -> Bundle {
 }
 
 fn benchmark_transaction_pool(c: &mut Criterion) {
-    let rt = Runtime::new().unwrap();
-    
     c.bench_function("pool_add_bundle", |b| {
         b.iter(|| {
             let pool = TransactionPool::new(10000);
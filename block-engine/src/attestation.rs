@@ -0,0 +1,264 @@
+use crate::ordered_block::{OrderedBlock, WireBundle};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Domain separator for block digests, so an attestation signature can't be replayed as anything else
+const ATTESTATION_DOMAIN: &[u8] = b"block-engine-attestation-v1";
+
+/// Engine signature over a block's canonical digest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockAttestation {
+    /// Engine identity that built the block
+    pub engine: String,
+    /// `block_digest` of the block, hex encoded
+    pub digest: String,
+    pub signature: String,
+}
+
+impl BlockAttestation {
+    pub fn sign(keypair: &Keypair, block: &OrderedBlock) -> Self {
+        let digest = block_digest(block);
+        Self {
+            engine: keypair.pubkey().to_string(),
+            digest: digest.to_string(),
+            signature: keypair.sign_message(digest.as_ref()).to_string(),
+        }
+    }
+}
+
+/// Why a block's attestation does not hold up
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AttestationError {
+    #[error("block is not attested")]
+    Missing,
+    #[error("attestation is malformed: {0}")]
+    Malformed(String),
+    #[error("attested digest {advertised} does not match the block ({computed})")]
+    DigestMismatch { advertised: String, computed: String },
+    #[error("attestation signature does not verify for engine {0}")]
+    InvalidSignature(String),
+    #[error("block was attested by {found}, expected {expected}")]
+    UnexpectedEngine { expected: String, found: String },
+    #[error("ordered bundle {0} is not among the window inputs")]
+    UnknownInput(String),
+    #[error("ordering hash does not match the ordered bundles")]
+    OrderedHashMismatch,
+    #[error("block bundles do not match the ordered bundles")]
+    BundlesMismatch,
+    #[error("blockhash {advertised} does not match the block contents ({computed})")]
+    BlockhashMismatch { advertised: String, computed: String },
+}
+
+impl AttestationError {
+    /// Stable variant name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AttestationError::Missing => "missing",
+            AttestationError::Malformed(_) => "malformed",
+            AttestationError::DigestMismatch { .. } => "digest_mismatch",
+            AttestationError::InvalidSignature(_) => "invalid_signature",
+            AttestationError::UnexpectedEngine { .. } => "unexpected_engine",
+            AttestationError::UnknownInput(_) => "unknown_input",
+            AttestationError::OrderedHashMismatch => "ordered_hash_mismatch",
+            AttestationError::BundlesMismatch => "bundles_mismatch",
+            AttestationError::BlockhashMismatch { .. } => "blockhash_mismatch",
+        }
+    }
+}
+
+/// Canonical digest the engine signs: window, slot, parent hash, blockhash, fee and tip totals,
+/// ordering hash and the window's input bundle IDs (sorted, so the order they arrived in does not matter)
+pub fn block_digest(block: &OrderedBlock) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(ATTESTATION_DOMAIN);
+    hasher.update(block.window_id.to_le_bytes());
+    hasher.update(block.block.slot.to_le_bytes());
+    hasher.update(block.block.parent_hash.as_ref());
+    hasher.update(block.block.blockhash.as_ref());
+    hasher.update(block.block.total_fees.to_le_bytes());
+    hasher.update(block.block.total_tips.to_le_bytes());
+    hasher.update(block.ordered_hash.as_bytes());
+
+    let mut inputs: Vec<&String> = block.window_inputs.iter().collect();
+    inputs.sort();
+    hasher.update((inputs.len() as u64).to_le_bytes());
+    for id in inputs {
+        hasher.update((id.len() as u64).to_le_bytes());
+        hasher.update(id.as_bytes());
+    }
+    Hash::new_from_array(hasher.finalize().into())
+}
+
+/// Check a block's attestation: the digest is recomputed from the block, the signature must verify
+/// for the named engine (and be that of `expected_engine`, when given), the signed ordering hash and
+/// blockhash must match the bundles and transactions actually carried, the block's own bundles must
+/// be the ordered bundles, and every ordered bundle must be one of the window inputs. Returns the engine that signed.
+pub fn verify_block_attestation(block: &OrderedBlock, expected_engine: Option<&Pubkey>) -> Result<Pubkey, AttestationError> {
+    let attestation = block.attestation.as_ref().ok_or(AttestationError::Missing)?;
+    let engine = Pubkey::from_str(&attestation.engine)
+        .map_err(|e| AttestationError::Malformed(format!("engine key: {}", e)))?;
+    let signature = Signature::from_str(&attestation.signature)
+        .map_err(|e| AttestationError::Malformed(format!("signature: {}", e)))?;
+
+    if let Some(expected) = expected_engine {
+        if *expected != engine {
+            return Err(AttestationError::UnexpectedEngine { expected: expected.to_string(), found: engine.to_string() });
+        }
+    }
+
+    let digest = block_digest(block);
+    if attestation.digest != digest.to_string() {
+        return Err(AttestationError::DigestMismatch {
+            advertised: attestation.digest.clone(),
+            computed: digest.to_string(),
+        });
+    }
+    if !signature.verify(engine.as_ref(), digest.as_ref()) {
+        return Err(AttestationError::InvalidSignature(engine.to_string()));
+    }
+
+    // The digest covers only the hashes, so they must be checked against the contents
+    if !block.verify_ordered_hash() {
+        return Err(AttestationError::OrderedHashMismatch);
+    }
+    let blockhash = block.block.compute_blockhash();
+    if blockhash != block.block.blockhash {
+        return Err(AttestationError::BlockhashMismatch {
+            advertised: block.block.blockhash.to_string(),
+            computed: blockhash.to_string(),
+        });
+    }
+    let carried = block.block.bundles.iter().map(WireBundle::from_bundle).collect::<Result<Vec<_>>>();
+    if carried.ok().as_ref() != Some(&block.ordered_bundles) {
+        return Err(AttestationError::BundlesMismatch);
+    }
+
+    if let Some(unknown) = block.ordered_bundles.iter().find(|b| !block.window_inputs.contains(&b.id)) {
+        return Err(AttestationError::UnknownInput(unknown.id.clone()));
+    }
+    Ok(engine)
+}
+
+/// Load the engine identity from a Solana JSON keypair file
+pub fn load_engine_keypair(path: &Path) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|e| anyhow!("failed to read engine keypair {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_assembler::BlockAssembler;
+    use crate::bundle::Bundle;
+
+    async fn attested_block(keypair: &Keypair) -> OrderedBlock {
+        let assembler = BlockAssembler::new(Pubkey::new_unique(), 100, 1_000_000);
+        let template = assembler.create_block_template(4, Hash::new_unique());
+        let winner = Bundle::new(vec![], 2000, "a".to_string());
+        let loser = Bundle::new(vec![], 1000, "b".to_string());
+        let inputs = vec![loser.id.to_string(), winner.id.to_string()];
+        let block = assembler.assemble_block(template, vec![winner]).await.unwrap();
+        OrderedBlock::new(4, block).unwrap().with_window_inputs(inputs).signed(keypair)
+    }
+
+    #[tokio::test]
+    async fn test_attestation_verifies_for_signing_engine() {
+        let keypair = Keypair::new();
+        let block = attested_block(&keypair).await;
+        assert_eq!(verify_block_attestation(&block, None), Ok(keypair.pubkey()));
+        assert_eq!(verify_block_attestation(&block, Some(&keypair.pubkey())), Ok(keypair.pubkey()));
+
+        let other = Keypair::new().pubkey();
+        assert!(matches!(
+            verify_block_attestation(&block, Some(&other)),
+            Err(AttestationError::UnexpectedEngine { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_digest_covers_header_ordering_and_inputs() {
+        let keypair = Keypair::new();
+        let original = attested_block(&keypair).await;
+
+        let mut block = original.clone();
+        block.block.parent_hash = Hash::new_unique();
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "digest_mismatch");
+
+        let mut block = original.clone();
+        block.ordered_hash = "0".repeat(64);
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "digest_mismatch");
+
+        let mut block = original.clone();
+        block.window_inputs.pop();
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "digest_mismatch");
+
+        // Input order is not part of the digest
+        let mut block = original.clone();
+        block.window_inputs.reverse();
+        assert!(verify_block_attestation(&block, None).is_ok());
+
+        // Re-signing the digest with another key does not pass as the original engine
+        let mut block = original.clone();
+        let forged = Keypair::new().sign_message(block_digest(&block).as_ref());
+        block.attestation.as_mut().unwrap().signature = forged.to_string();
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "invalid_signature");
+
+        let mut block = original;
+        block.attestation = None;
+        assert_eq!(verify_block_attestation(&block, None), Err(AttestationError::Missing));
+    }
+
+    #[tokio::test]
+    async fn test_contents_must_match_signed_hashes() {
+        let keypair = Keypair::new();
+        let original = attested_block(&keypair).await;
+
+        // Swapping the ordered bundles or the block contents keeps the signed digest intact
+        let mut block = original.clone();
+        block.ordered_bundles[0].tip += 1;
+        assert_eq!(verify_block_attestation(&block, None), Err(AttestationError::OrderedHashMismatch));
+
+        let mut block = original.clone();
+        block.block.timestamp += 1;
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "blockhash_mismatch");
+
+        // Neither the block's own bundles nor its totals can drift from what was signed
+        let mut block = original.clone();
+        block.block.bundles[0].tip_lamports += 1;
+        assert_eq!(verify_block_attestation(&block, None), Err(AttestationError::BundlesMismatch));
+
+        let mut block = original.clone();
+        block.block.bundles.clear();
+        assert_eq!(verify_block_attestation(&block, None), Err(AttestationError::BundlesMismatch));
+
+        let mut block = original.clone();
+        block.block.total_tips += 1;
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "digest_mismatch");
+
+        let mut block = original;
+        block.block.total_fees += 1;
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "digest_mismatch");
+    }
+
+    #[tokio::test]
+    async fn test_winners_must_be_window_inputs() {
+        let keypair = Keypair::new();
+        let block = attested_block(&keypair).await;
+        let block = block.with_window_inputs(vec!["someone-else".to_string()]).signed(&keypair);
+        assert_eq!(verify_block_attestation(&block, None).unwrap_err().kind(), "unknown_input");
+    }
+
+    #[test]
+    fn test_engine_keypair_loads_from_file() {
+        let keypair = Keypair::new();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        solana_sdk::signature::write_keypair_file(&keypair, file.path()).unwrap();
+        assert_eq!(load_engine_keypair(file.path()).unwrap().pubkey(), keypair.pubkey());
+        assert!(load_engine_keypair(Path::new("/nonexistent/engine.json")).is_err());
+    }
+}
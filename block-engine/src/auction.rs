@@ -3,12 +3,12 @@ use crate::simulator::TransactionSimulator;
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use tokio::time::{sleep, Duration, Instant};
 use tracing::{info, warn, debug};
-use uuid::Uuid;
 
 pub struct BundleAuction {
-    pub bundles: BinaryHeap<AuctionBundle>,
+    bundles: BinaryHeap<AuctionBundle>,
     pub slot: u64,
     pub simulator: Option<TransactionSimulator>,
 }
@@ -278,7 +278,7 @@ impl AuctionWindow {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuctionWindowStats {
     pub window_id: u64,
     pub total_bundles: usize,
@@ -371,6 +371,36 @@ pub fn simulate_auction_with_bundles(
     Ok(winners)
 }
 
+/// Strategy for choosing a window's winning bundles, in block order
+pub trait AuctionPolicy: Send + Sync {
+    /// Order every candidate from best to worst
+    fn rank(&self, window_id: u64, bundles: Vec<Bundle>) -> Vec<Bundle>;
+
+    fn select_winners(&self, window_id: u64, bundles: Vec<Bundle>, max_bundles: usize) -> Vec<Bundle> {
+        let mut ranked = self.rank(window_id, bundles);
+        ranked.truncate(max_bundles);
+        ranked
+    }
+}
+
+/// Highest tip first, ties broken by bundle ID
+#[derive(Debug, Clone, Default)]
+pub struct PriorityFeePolicy;
+
+impl AuctionPolicy for PriorityFeePolicy {
+    fn rank(&self, window_id: u64, bundles: Vec<Bundle>) -> Vec<Bundle> {
+        let mut auction_window = AuctionWindow::new(window_id, 0, bundles.len());
+        auction_window.bundles = bundles;
+        auction_window.rank_bundles_by_priority()
+    }
+
+    fn select_winners(&self, window_id: u64, bundles: Vec<Bundle>, max_bundles: usize) -> Vec<Bundle> {
+        let mut auction_window = AuctionWindow::new(window_id, 0, max_bundles);
+        auction_window.bundles = bundles;
+        auction_window.select_and_log_winners()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
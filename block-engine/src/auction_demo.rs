@@ -1,20 +1,12 @@
-// Import our auction modules
-mod auction;
-mod bundle;
-mod simulator;
-mod block_assembler;
-mod validator;
-
-use auction::{simulate_auction_with_bundles, simulate_auction_window};
-use bundle::Bundle;
-use block_assembler::{assemble_block_with_params};
-use validator::{MockValidator, ValidatorNetwork, BlockSubmissionResult};
+use block_engine::{
+    simulate_auction_with_bundles, simulate_auction_window,
+    Bundle, assemble_block_with_params,
+    MockValidator, ValidatorNetwork, BlockSubmissionResult, ValidatorResponse,
+};
 use solana_sdk::{hash::Hash, pubkey::Pubkey, transaction::Transaction, instruction::Instruction, message::Message, signature::Signature};
 use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 use tracing::{info, Level};
-use tracing_subscriber;
-use serde_json;
 
 /// Example demonstrating the 200ms auction window functionality
 #[tokio::main]
@@ -56,7 +48,9 @@ async fn demo_auction_with_block_assembly_and_validation() -> anyhow::Result<()>
     // Assemble block with the winning bundles
     let slot = 12345;
     let parent_hash = Hash::default();
-    let leader_pubkey = Pubkey::default();
+    // A lone validator leads every slot
+    let validator = MockValidator::new();
+    let leader_pubkey = validator.identity;
 
     let (block, summary) = assemble_block_with_params(
         winners,
@@ -72,7 +66,6 @@ async fn demo_auction_with_block_assembly_and_validation() -> anyhow::Result<()>
     info!("⛓️ Block assembled with {} transactions", block.transactions.len());
 
     // Submit to mock validator
-    let validator = MockValidator::new();
     info!("🔍 Submitting block to validator for verification...");
     
     match validator.submit_block(block).await? {
@@ -119,7 +112,11 @@ async fn demo_real_time_auction_with_validator_network() -> anyhow::Result<()> {
     // Assemble block with the winning bundles
     let slot = 12346;
     let parent_hash = Hash::default();
-    let leader_pubkey = Pubkey::default();
+    // Stake-weighted network; the slot's leader comes from its schedule
+    let network = ValidatorNetwork::with_stakes(&[40, 25, 15, 10, 10]);
+    let leader = network.leader_for_slot(slot).expect("network has stake");
+    info!("{} leads slot {}", leader.validator_id, slot);
+    let leader_pubkey = leader.identity;
 
     let (block, summary) = assemble_block_with_params(
         winners,
@@ -135,29 +132,25 @@ async fn demo_real_time_auction_with_validator_network() -> anyhow::Result<()> {
     info!("⛓️ Block assembled with {} transactions", block.transactions.len());
 
     // Submit to validator network
-    let network = ValidatorNetwork::new(5); // 5 validators
     info!("🌐 Submitting block to validator network (5 validators)...");
     
-    let results = network.submit_block_to_network(block).await;
-    
-    // Analyze results
-    let accepted_count = results.iter().filter(|(_, result)| {
-        matches!(result, BlockSubmissionResult::Accepted { .. })
-    }).count();
-    
-    let rejected_count = results.len() - accepted_count;
-    
-    info!("📊 Network results: {} accepted, {} rejected", accepted_count, rejected_count);
-    
+    let outcome = network.submit_block_to_network(block).await;
+
+    info!(
+        "Network outcome: {:?} with {}/{} stake accepting",
+        outcome.status, outcome.accepted_stake, outcome.total_stake
+    );
+
     // Show individual validator results
-    for (validator_id, result) in results {
-        match result {
-            BlockSubmissionResult::Accepted { signature } => {
-                info!("✅ {} ACCEPTED block with signature {}", validator_id, signature);
+    for vote in &outcome.votes {
+        match &vote.response {
+            ValidatorResponse::Accepted { signature } => {
+                info!("{} ACCEPTED block with signature {}", vote.validator_id, signature);
             }
-            BlockSubmissionResult::Rejected { reason } => {
-                info!("❌ {} REJECTED block: {}", validator_id, reason);
+            ValidatorResponse::Rejected { reason } => {
+                info!("{} REJECTED block: {}", vote.validator_id, reason);
             }
+            other => info!("{} did not vote: {:?}", vote.validator_id, other),
         }
     }
 
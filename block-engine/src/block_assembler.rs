@@ -1,12 +1,13 @@
 use crate::bundle::Bundle;
+use crate::leader_schedule::LeaderSchedule;
 use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
-    signature::{Signature, Keypair, Signer},
+    signature::Signature,
     transaction::Transaction,
-    system_instruction,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use anyhow::Result;
 use sha2::{Digest, Sha256};
@@ -25,6 +26,24 @@ pub struct Block {
     pub total_tips: u64,
 }
 
+impl Block {
+    /// Hash over the block header and every transaction, so any receiver can recompute and check `blockhash`
+    pub fn compute_blockhash(&self) -> Hash {
+        let mut hasher = Sha256::new();
+        hasher.update(self.slot.to_le_bytes());
+        hasher.update(self.parent_hash.as_ref());
+        hasher.update(self.leader_pubkey.as_ref());
+        hasher.update(self.timestamp.to_le_bytes());
+        for tx in &self.transactions {
+            for signature in &tx.signatures {
+                hasher.update(signature.as_ref());
+            }
+            hasher.update(tx.message_data());
+        }
+        Hash::new_from_array(hasher.finalize().into())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BlockTemplate {
     pub slot: u64,
@@ -36,9 +55,11 @@ pub struct BlockTemplate {
 
 pub struct BlockAssembler {
     pub current_slot: u64,
+    /// Leader for every slot when no schedule is set
     pub leader_pubkey: Pubkey,
     pub max_transactions_per_block: usize,
     pub max_compute_units_per_block: u64,
+    pub leader_schedule: Option<LeaderSchedule>,
 }
 
 impl BlockAssembler {
@@ -52,14 +73,29 @@ impl BlockAssembler {
             leader_pubkey,
             max_transactions_per_block,
             max_compute_units_per_block,
+            leader_schedule: None,
         }
     }
 
+    /// Take each slot's leader from `schedule`
+    pub fn with_leader_schedule(mut self, schedule: LeaderSchedule) -> Self {
+        self.leader_schedule = Some(schedule);
+        self
+    }
+
+    /// Scheduled leader for `slot`, falling back to `leader_pubkey`
+    pub fn leader_for_slot(&self, slot: u64) -> Pubkey {
+        self.leader_schedule
+            .as_ref()
+            .and_then(|schedule| schedule.leader_for_slot(slot))
+            .unwrap_or(self.leader_pubkey)
+    }
+
     pub fn create_block_template(&self, slot: u64, parent_hash: Hash) -> BlockTemplate {
         BlockTemplate {
             slot,
             parent_hash,
-            leader_pubkey: self.leader_pubkey,
+            leader_pubkey: self.leader_for_slot(slot),
             max_transactions: self.max_transactions_per_block,
             max_compute_units: self.max_compute_units_per_block,
         }
@@ -109,17 +145,19 @@ impl BlockAssembler {
             .unwrap()
             .as_secs();
 
-        Ok(Block {
+        let mut block = Block {
             slot: template.slot,
             parent_hash: template.parent_hash,
-            blockhash: Hash::new_unique(), // In reality, this would be computed
+            blockhash: Hash::default(),
             transactions: all_transactions,
             bundles: included_bundles,
             timestamp,
             leader_pubkey: template.leader_pubkey,
             total_fees,
             total_tips,
-        })
+        };
+        block.blockhash = block.compute_blockhash();
+        Ok(block)
     }
 
     fn estimate_bundle_compute_units(&self, bundle: &Bundle) -> u64 {
@@ -193,6 +231,70 @@ pub enum BlockValidationError {
     MissingBundleTransaction,
     #[error("Invalid block structure: {0}")]
     InvalidStructure(String),
+    #[error("Parent block {0} is unknown")]
+    UnknownParent(Hash),
+    #[error("Block {0} was already accepted")]
+    DuplicateBlock(Hash),
+    #[error("Transaction at index {duplicate} repeats the one at index {first}")]
+    DuplicateTransaction { first: usize, duplicate: usize },
+    #[error("Transactions of bundle {0} are not contiguous")]
+    NonContiguousBundle(Uuid),
+    #[error("Transactions of bundle {0} are out of order")]
+    BundleOutOfOrder(Uuid),
+    #[error("Block advertises {advertised} lamports of tips but its bundles pay {computed}")]
+    TipMismatch { advertised: u64, computed: u64 },
+    #[error("Bundle at position {position} outranks the bundle before it")]
+    RankingViolation { position: usize },
+}
+
+/// Check how a block lays out its bundles: each bundle's transactions appear once, back to back
+/// and in bundle order, no transaction appears twice, tips add up to `total_tips`, and bundles
+/// follow the `PriorityFeePolicy` ranking (highest tip first, ties broken by bundle ID).
+pub fn validate_bundle_layout(block: &Block) -> Result<(), BlockValidationError> {
+    // Transactions are identified by their message, so unsigned copies still count as repeats
+    let mut positions: HashMap<Vec<u8>, usize> = HashMap::with_capacity(block.transactions.len());
+    for (index, tx) in block.transactions.iter().enumerate() {
+        if let Some(first) = positions.insert(tx.message_data(), index) {
+            return Err(BlockValidationError::DuplicateTransaction { first, duplicate: index });
+        }
+    }
+
+    let mut claimed = vec![false; block.transactions.len()];
+    for bundle in &block.bundles {
+        let mut previous: Option<usize> = None;
+        let mut contiguous = true;
+        for tx in &bundle.transactions {
+            let index = *positions
+                .get(&tx.message_data())
+                .ok_or(BlockValidationError::MissingBundleTransaction)?;
+            if std::mem::replace(&mut claimed[index], true) {
+                // Another bundle already placed this transaction
+                return Err(BlockValidationError::DuplicateTransaction { first: index, duplicate: index });
+            }
+            match previous {
+                Some(prev) if index < prev => return Err(BlockValidationError::BundleOutOfOrder(bundle.id)),
+                Some(prev) if index != prev + 1 => contiguous = false,
+                _ => {}
+            }
+            previous = Some(index);
+        }
+        if !contiguous {
+            return Err(BlockValidationError::NonContiguousBundle(bundle.id));
+        }
+    }
+
+    let computed = block.bundles.iter().fold(0u64, |sum, b| sum.saturating_add(b.tip_lamports));
+    if computed != block.total_tips {
+        return Err(BlockValidationError::TipMismatch { advertised: block.total_tips, computed });
+    }
+
+    for (position, pair) in block.bundles.windows(2).enumerate() {
+        let (ahead, behind) = (&pair[0], &pair[1]);
+        if (behind.tip_lamports, ahead.id) > (ahead.tip_lamports, behind.id) {
+            return Err(BlockValidationError::RankingViolation { position: position + 1 });
+        }
+    }
+    Ok(())
 }
 
 /// JSON summary of the assembled block
@@ -208,14 +310,19 @@ pub struct BlockSummary {
 
 /// Assembles a block from winning bundles, aggregates transactions, computes block hash, and outputs JSON summary
 pub fn assemble_block(winning_bundles: Vec<Bundle>) -> Result<(Block, BlockSummary)> {
-    tracing::info!("🔨 Assembling block from {} winning bundles", winning_bundles.len());
-
-    // Generate unique block ID
-    let block_id = Uuid::new_v4().to_string();
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
+    assemble_block_at(winning_bundles, timestamp)
+}
+
+/// `assemble_block` with the block timestamp (Unix seconds) supplied by the caller
+pub fn assemble_block_at(winning_bundles: Vec<Bundle>, timestamp: u64) -> Result<(Block, BlockSummary)> {
+    tracing::info!("Assembling block from {} winning bundles", winning_bundles.len());
+
+    // Generate unique block ID
+    let block_id = Uuid::new_v4().to_string();
 
     // Aggregate all transactions from winning bundles
     let mut all_transactions = Vec::new();
@@ -335,6 +442,12 @@ pub struct MockValidatorClient {
     pub should_fail: bool,
 }
 
+impl Default for MockValidatorClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MockValidatorClient {
     pub fn new() -> Self {
         Self {
@@ -372,9 +485,12 @@ impl MockValidatorClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::leader_schedule::StakedValidator;
     use crate::bundle::Bundle;
     use solana_sdk::{
         instruction::Instruction,
+        signature::{Keypair, Signer},
+        system_instruction,
         message::Message,
         signature::Signature,
         transaction::Transaction,
@@ -413,6 +529,37 @@ mod tests {
         assert_eq!(block.total_tips, 3000);
     }
 
+    #[tokio::test]
+    async fn test_blockhash_covers_header_and_transactions() {
+        let assembler = BlockAssembler::new(Pubkey::new_unique(), 100, 1_000_000);
+        let template = assembler.create_block_template(7, Hash::new_unique());
+        let mut block = assembler
+            .assemble_block(template, vec![create_test_bundle(1000, 2)])
+            .await
+            .unwrap();
+        assert_eq!(block.blockhash, block.compute_blockhash());
+
+        block.transactions.pop();
+        assert_ne!(block.blockhash, block.compute_blockhash());
+    }
+
+    #[test]
+    fn test_template_leader_comes_from_schedule() {
+        let fallback = Pubkey::new_unique();
+        let schedule = LeaderSchedule::new(vec![
+            StakedValidator::new(Pubkey::new_unique(), 1),
+            StakedValidator::new(Pubkey::new_unique(), 1),
+        ]);
+        let assembler = BlockAssembler::new(fallback, 100, 1_000_000);
+        assert_eq!(assembler.create_block_template(9, Hash::default()).leader_pubkey, fallback);
+
+        let assembler = assembler.with_leader_schedule(schedule.clone());
+        for slot in 0..20 {
+            let template = assembler.create_block_template(slot, Hash::default());
+            assert_eq!(Some(template.leader_pubkey), schedule.leader_for_slot(slot));
+        }
+    }
+
     #[tokio::test]
     async fn test_transaction_limit() {
         let leader = Keypair::new();
@@ -452,6 +599,81 @@ mod tests {
         assert!(assembler.validate_block(&block).is_ok());
     }
 
+    /// Bundle whose transactions all differ from each other and from every other bundle
+    fn distinct_bundle(tip: u64, tx_count: usize) -> Bundle {
+        let payer = Keypair::new();
+        let transactions = (0..tx_count)
+            .map(|_| {
+                let instruction = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 100);
+                Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()))
+            })
+            .collect();
+        Bundle::new(transactions, tip, payer.pubkey().to_string())
+    }
+
+    fn laid_out(bundles: Vec<Bundle>) -> Block {
+        Block {
+            slot: 1,
+            parent_hash: Hash::default(),
+            blockhash: Hash::new_unique(),
+            transactions: bundles.iter().flat_map(|b| b.transactions.clone()).collect(),
+            total_tips: bundles.iter().map(|b| b.tip_lamports).sum(),
+            bundles,
+            timestamp: 1000,
+            leader_pubkey: Pubkey::new_unique(),
+            total_fees: 0,
+        }
+    }
+
+    #[test]
+    fn test_bundle_layout_violations() {
+        let (high, low) = (distinct_bundle(2000, 2), distinct_bundle(1000, 3));
+        let block = laid_out(vec![high.clone(), low.clone()]);
+        assert!(validate_bundle_layout(&block).is_ok());
+
+        let mut repeated = block.clone();
+        repeated.transactions.push(high.transactions[0].clone());
+        assert!(matches!(
+            validate_bundle_layout(&repeated),
+            Err(BlockValidationError::DuplicateTransaction { first: 0, duplicate: 5 })
+        ));
+
+        let mut interleaved = block.clone();
+        interleaved.transactions.swap(1, 2);
+        assert!(matches!(
+            validate_bundle_layout(&interleaved),
+            Err(BlockValidationError::NonContiguousBundle(id)) if id == high.id
+        ));
+
+        let mut reversed = block.clone();
+        reversed.transactions.swap(2, 4);
+        assert!(matches!(
+            validate_bundle_layout(&reversed),
+            Err(BlockValidationError::BundleOutOfOrder(id)) if id == low.id
+        ));
+
+        let mut inflated = block.clone();
+        inflated.total_tips += 1;
+        assert!(matches!(
+            validate_bundle_layout(&inflated),
+            Err(BlockValidationError::TipMismatch { advertised: 3001, computed: 3000 })
+        ));
+
+        let misranked = laid_out(vec![low.clone(), high.clone()]);
+        assert!(matches!(
+            validate_bundle_layout(&misranked),
+            Err(BlockValidationError::RankingViolation { position: 1 })
+        ));
+
+        // Equal tips rank by bundle ID
+        let (mut first, mut second) = (distinct_bundle(500, 1), distinct_bundle(500, 1));
+        if first.id > second.id {
+            std::mem::swap(&mut first, &mut second);
+        }
+        assert!(validate_bundle_layout(&laid_out(vec![first.clone(), second.clone()])).is_ok());
+        assert!(validate_bundle_layout(&laid_out(vec![second, first])).is_err());
+    }
+
     #[tokio::test]
     async fn test_mock_validator_client() {
         let client = MockValidatorClient::new();
@@ -548,10 +770,14 @@ mod tests {
         assert!(!summary.block_hash.is_empty());
         assert!(!summary.block_id.is_empty());
         
-        // Verify deterministic hashing - same bundles should produce same hash
-        let (_, summary2) = assemble_block(bundles).unwrap();
-        assert_ne!(summary.block_hash, summary2.block_hash); // Different because timestamp differs
-        assert_ne!(summary.block_id, summary2.block_id); // Different block IDs
+        // Verify deterministic hashing - same bundles and timestamp should produce same hash
+        let (_, first) = assemble_block_at(bundles.clone(), 1000).unwrap();
+        let (_, again) = assemble_block_at(bundles.clone(), 1000).unwrap();
+        assert_eq!(first.block_hash, again.block_hash);
+        assert_ne!(first.block_id, again.block_id); // Different block IDs
+
+        let (_, later) = assemble_block_at(bundles, 1001).unwrap();
+        assert_ne!(first.block_hash, later.block_hash); // Different because timestamp differs
     }
 
     #[test]
@@ -0,0 +1,232 @@
+use crate::block_assembler::Block;
+use solana_sdk::hash::Hash;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BlockTreeError {
+    #[error("parent {parent} of block {blockhash} is not in the tree")]
+    UnknownParent { blockhash: Hash, parent: Hash },
+    #[error("block {0} is already in the tree")]
+    Duplicate(Hash),
+}
+
+impl BlockTreeError {
+    /// Stable variant name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BlockTreeError::UnknownParent { .. } => "unknown_parent",
+            BlockTreeError::Duplicate(_) => "duplicate",
+        }
+    }
+}
+
+/// Switch of the canonical chain to a longer fork
+#[derive(Debug, Clone)]
+pub struct Reorg {
+    pub old_head: Hash,
+    pub new_head: Hash,
+    /// Last block both chains share
+    pub common_ancestor: Hash,
+    /// Blocks that were canonical and no longer are, newest first
+    pub orphaned: Vec<Block>,
+    /// Blocks that became canonical, oldest first
+    pub adopted: Vec<Block>,
+}
+
+/// How inserting a block changed the tree
+#[derive(Debug, Clone)]
+pub enum TreeUpdate {
+    /// The block extends the canonical chain
+    Extended,
+    /// The block starts or grows a fork that is not longer than the canonical chain
+    SideFork,
+    Reorg(Reorg),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    block: Block,
+    height: u64,
+}
+
+/// Blocks linked by `parent_hash`, with the longest chain as canonical. On a tie the chain seen
+/// first stays canonical.
+#[derive(Debug, Clone)]
+pub struct BlockTree {
+    /// Virtual genesis every chain starts from
+    root: Hash,
+    nodes: HashMap<Hash, Node>,
+    head: Hash,
+}
+
+impl BlockTree {
+    pub fn new(root: Hash) -> Self {
+        Self {
+            root,
+            nodes: HashMap::new(),
+            head: root,
+        }
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    /// Tip of the canonical chain
+    pub fn head(&self) -> Hash {
+        self.head
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Whether blocks can build on `hash`
+    pub fn contains(&self, hash: &Hash) -> bool {
+        *hash == self.root || self.nodes.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<&Block> {
+        self.nodes.get(hash).map(|n| &n.block)
+    }
+
+    /// Blocks between `hash` and the root; 0 for the root itself
+    pub fn height(&self, hash: &Hash) -> Option<u64> {
+        if *hash == self.root {
+            return Some(0);
+        }
+        self.nodes.get(hash).map(|n| n.height)
+    }
+
+    pub fn insert(&mut self, block: Block) -> Result<TreeUpdate, BlockTreeError> {
+        let (blockhash, parent) = (block.blockhash, block.parent_hash);
+        if self.contains(&blockhash) {
+            return Err(BlockTreeError::Duplicate(blockhash));
+        }
+        let parent_height = self
+            .height(&parent)
+            .ok_or(BlockTreeError::UnknownParent { blockhash, parent })?;
+
+        let height = parent_height + 1;
+        let head_height = self.height(&self.head).unwrap_or(0);
+        self.nodes.insert(blockhash, Node { block, height });
+
+        if parent == self.head {
+            self.head = blockhash;
+            return Ok(TreeUpdate::Extended);
+        }
+        if height <= head_height {
+            return Ok(TreeUpdate::SideFork);
+        }
+
+        let old_head = self.head;
+        self.head = blockhash;
+        let new_chain = self.path_to_root(blockhash);
+        let on_new_chain: HashSet<Hash> = new_chain.iter().copied().collect();
+        let old_chain = self.path_to_root(old_head);
+        let common_ancestor = old_chain
+            .iter()
+            .copied()
+            .find(|hash| on_new_chain.contains(hash))
+            .unwrap_or(self.root);
+
+        let blocks = |chain: &[Hash]| -> Vec<Block> {
+            chain
+                .iter()
+                .take_while(|hash| **hash != common_ancestor)
+                .filter_map(|hash| self.get(hash).cloned())
+                .collect()
+        };
+        let orphaned = blocks(&old_chain);
+        let mut adopted = blocks(&new_chain);
+        adopted.reverse();
+        Ok(TreeUpdate::Reorg(Reorg { old_head, new_head: blockhash, common_ancestor, orphaned, adopted }))
+    }
+
+    /// Canonical blocks, oldest first
+    pub fn canonical_chain(&self) -> Vec<&Block> {
+        let mut chain: Vec<&Block> = self.path_to_root(self.head).iter().filter_map(|h| self.get(h)).collect();
+        chain.reverse();
+        chain
+    }
+
+    pub fn is_canonical(&self, hash: &Hash) -> bool {
+        self.path_to_root(self.head).contains(hash)
+    }
+
+    /// `hash` and its ancestors, newest first, ending at the root
+    fn path_to_root(&self, mut hash: Hash) -> Vec<Hash> {
+        let mut path = vec![hash];
+        while let Some(node) = self.nodes.get(&hash) {
+            hash = node.block.parent_hash;
+            path.push(hash);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn child(parent: Hash, slot: u64) -> Block {
+        let mut block = Block {
+            slot,
+            parent_hash: parent,
+            blockhash: Hash::default(),
+            transactions: vec![],
+            bundles: vec![],
+            timestamp: 1,
+            leader_pubkey: Pubkey::new_unique(),
+            total_fees: 0,
+            total_tips: 0,
+        };
+        block.blockhash = block.compute_blockhash();
+        block
+    }
+
+    #[test]
+    fn test_rejects_unknown_parents_and_duplicates() {
+        let mut tree = BlockTree::new(Hash::default());
+        let a = child(Hash::default(), 1);
+        assert!(matches!(tree.insert(a.clone()), Ok(TreeUpdate::Extended)));
+        assert_eq!(tree.insert(a.clone()).unwrap_err(), BlockTreeError::Duplicate(a.blockhash));
+
+        let orphan = child(Hash::new_unique(), 2);
+        assert_eq!(tree.insert(orphan).unwrap_err().kind(), "unknown_parent");
+        assert_eq!(tree.head(), a.blockhash);
+    }
+
+    #[test]
+    fn test_longer_fork_reorgs_canonical_chain() {
+        let root = Hash::default();
+        let mut tree = BlockTree::new(root);
+        let a1 = child(root, 1);
+        let a2 = child(a1.blockhash, 2);
+        let b2 = child(a1.blockhash, 2);
+        let b3 = child(b2.blockhash, 3);
+        tree.insert(a1.clone()).unwrap();
+        tree.insert(a2.clone()).unwrap();
+
+        // Equal height keeps the chain seen first
+        assert!(matches!(tree.insert(b2.clone()), Ok(TreeUpdate::SideFork)));
+        assert_eq!(tree.head(), a2.blockhash);
+
+        let TreeUpdate::Reorg(reorg) = tree.insert(b3.clone()).unwrap() else {
+            panic!("expected a reorg");
+        };
+        assert_eq!((reorg.old_head, reorg.new_head, reorg.common_ancestor), (a2.blockhash, b3.blockhash, a1.blockhash));
+        assert_eq!(reorg.orphaned.iter().map(|b| b.blockhash).collect::<Vec<_>>(), vec![a2.blockhash]);
+        assert_eq!(reorg.adopted.iter().map(|b| b.blockhash).collect::<Vec<_>>(), vec![b2.blockhash, b3.blockhash]);
+
+        let canonical: Vec<Hash> = tree.canonical_chain().iter().map(|b| b.blockhash).collect();
+        assert_eq!(canonical, vec![a1.blockhash, b2.blockhash, b3.blockhash]);
+        assert!(!tree.is_canonical(&a2.blockhash));
+        assert_eq!(tree.height(&b3.blockhash), Some(3));
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::{pubkey::Pubkey, transaction::Transaction};
+use std::collections::HashMap;
 use std::time::SystemTime;
 use uuid::Uuid;
 
@@ -10,6 +11,78 @@ pub struct Bundle {
     pub tip_lamports: u64,
     pub created_at: SystemTime,
     pub searcher_pubkey: String,
+    #[serde(default)]
+    pub post_conditions: Vec<PostCondition>,
+}
+
+/// Balance check that must hold once every transaction in the bundle has executed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostCondition {
+    /// The account must end the bundle at least `lamports` richer
+    MinBalanceIncrease {
+        #[serde(with = "base58_pubkey")]
+        account: Pubkey,
+        lamports: u64,
+    },
+    /// The account may lose at most `lamports` over the bundle
+    MaxBalanceDecrease {
+        #[serde(with = "base58_pubkey")]
+        account: Pubkey,
+        lamports: u64,
+    },
+    /// The tip account must receive at least the tip the bundle bid in the auction
+    TipPaid {
+        #[serde(with = "base58_pubkey")]
+        tip_account: Pubkey,
+    },
+}
+
+impl PostCondition {
+    /// Check the condition against the net lamport change of each account, for a bundle bidding `tip_lamports`
+    pub fn check(&self, tip_lamports: u64, balance_deltas: &HashMap<Pubkey, i64>) -> Result<(), BundleError> {
+        let delta_of = |account: &Pubkey| i128::from(balance_deltas.get(account).copied().unwrap_or(0));
+
+        let (account, delta, holds) = match self {
+            PostCondition::MinBalanceIncrease { account, lamports } => {
+                let delta = delta_of(account);
+                (account, delta, delta >= i128::from(*lamports))
+            }
+            PostCondition::MaxBalanceDecrease { account, lamports } => {
+                let delta = delta_of(account);
+                (account, delta, delta >= -i128::from(*lamports))
+            }
+            PostCondition::TipPaid { tip_account } => {
+                let delta = delta_of(tip_account);
+                (tip_account, delta, delta >= i128::from(tip_lamports))
+            }
+        };
+
+        if holds {
+            Ok(())
+        } else {
+            Err(BundleError::PostConditionViolated(format!(
+                "{:?} not met: account {} changed by {} lamports",
+                self, account, delta
+            )))
+        }
+    }
+}
+
+/// Pubkeys as base58 strings, the form searchers submit them in
+mod base58_pubkey {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Pubkey::from_str(&encoded).map_err(D::Error::custom)
+    }
 }
 
 impl Bundle {
@@ -20,8 +93,15 @@ impl Bundle {
             tip_lamports,
             created_at: SystemTime::now(),
             searcher_pubkey,
+            post_conditions: Vec::new(),
         }
     }
+
+    /// Attach balance post-conditions the simulator must enforce
+    pub fn with_post_conditions(mut self, post_conditions: Vec<PostCondition>) -> Self {
+        self.post_conditions = post_conditions;
+        self
+    }
     
     pub fn validate(&self) -> Result<(), BundleError> {
         if self.transactions.is_empty() {
@@ -44,21 +124,21 @@ pub enum BundleError {
     TooManyTransactions,
     #[error("Simulation failed: {0}")]
     SimulationFailed(String),
+    #[error("Post-condition violated: {0}")]
+    PostConditionViolated(String),
+    #[error("Encrypted bundle could not be opened: {0}")]
+    Decryption(#[from] crate::envelope::EnvelopeError),
 }
 
-pub struct BundleEngine {
-    rpc_url: String,
-}
-
-impl BundleEngine {
-    pub async fn new(rpc_url: String) -> anyhow::Result<Self> {
-        Ok(Self { rpc_url })
-    }
-    
-    pub async fn start_auction_loop(&mut self) -> anyhow::Result<()> {
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
-            // Auction logic will go here
+impl BundleError {
+    /// Stable variant name, used as a metrics label
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BundleError::EmptyBundle => "empty_bundle",
+            BundleError::TooManyTransactions => "too_many_transactions",
+            BundleError::SimulationFailed(_) => "simulation_failed",
+            BundleError::PostConditionViolated(_) => "post_condition_violated",
+            BundleError::Decryption(_) => "decryption_failed",
         }
     }
-}
\ No newline at end of file
+}
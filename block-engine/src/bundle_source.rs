@@ -0,0 +1,1327 @@
+use crate::bundle::{Bundle, BundleError};
+use crate::envelope::{BundleEnvelope, EnvelopeError, EnvelopeKeyring};
+use crate::ordered_block::WireBundle;
+use crate::redis_connection::RedisConnection;
+use async_trait::async_trait;
+use redis::streams::{StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use anyhow::Result;
+
+/// Where the engine gets each closed window's bundles from
+#[async_trait]
+pub trait BundleSource: Send {
+    /// Return the bundles belonging to a window that has just closed
+    async fn take_window(&mut self, window_id: u64) -> Result<Vec<Bundle>>;
+
+    /// Confirm a window returned by `take_window` has been fully processed.
+    /// Sources that can redeliver after a crash keep the window until this is called.
+    async fn ack_window(&mut self, _window_id: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Hand back a window whose bundles could not be processed so they are delivered again with a
+    /// later window. Returns `false` when the source cannot redeliver, leaving the caller to keep them.
+    async fn requeue_window(&mut self, _window_id: u64) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// The engine fell too far behind and will never take the `skipped` windows. Sources that key
+    /// bundles by window deliver theirs with `into_window` or dead-letter them.
+    async fn skip_windows(&mut self, _skipped: Range<u64>, _into_window: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Whether windows are read over the network, so reads count toward Redis latency and health
+    fn is_remote(&self) -> bool {
+        false
+    }
+}
+
+/// Parse a bundle as the API server serializes it
+pub fn parse_wire_bundle(json: &str) -> Result<Bundle> {
+    serde_json::from_str::<WireBundle>(json)?.to_bundle()
+}
+
+/// Parse a window entry, which is either a plain bundle or a `BundleEnvelope`. Envelopes are opened
+/// with `keyring` and only for windows up to `window_id`, the window that has just closed.
+pub fn parse_bundle_entry(json: &str, window_id: u64, keyring: Option<&EnvelopeKeyring>) -> Result<Bundle> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    if value.get("ciphertext").is_none() {
+        return serde_json::from_value::<WireBundle>(value)?.to_bundle();
+    }
+
+    let envelope: BundleEnvelope = serde_json::from_value(value)?;
+    let wire = match keyring {
+        Some(keyring) => keyring.open(&envelope, window_id),
+        None => Err(EnvelopeError::UnknownKey(envelope.key_id.clone())),
+    }
+    .map_err(BundleError::Decryption)?;
+    wire.to_bundle()
+}
+
+/// List operations the window source needs, so the claim logic can run against Redis or memory
+#[async_trait]
+pub trait ListStore: Send {
+    /// Atomically move every entry of `key` onto the end of `claim_key` and return the moved entries
+    async fn claim_all(&mut self, key: &str, claim_key: &str) -> Result<Vec<String>>;
+    async fn read_all(&mut self, key: &str) -> Result<Vec<String>>;
+    async fn push(&mut self, key: &str, value: String) -> Result<()>;
+    /// Keep only the newest `max_len` entries of `key`
+    async fn trim(&mut self, key: &str, max_len: usize) -> Result<()>;
+    async fn last(&mut self, key: &str) -> Result<Option<String>>;
+    async fn delete(&mut self, key: &str) -> Result<()>;
+    async fn keys_matching(&mut self, pattern: &str) -> Result<Vec<String>>;
+}
+
+/// Redis-backed list store; claims run as a single Lua script so no push can slip between read and delete
+pub struct RedisListStore {
+    connection: RedisConnection,
+    claim_script: redis::Script,
+}
+
+impl RedisListStore {
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        let connection = RedisConnection::connect(redis_url).await?;
+
+        Ok(Self {
+            connection,
+            claim_script: redis::Script::new(CLAIM_ALL_SCRIPT),
+        })
+    }
+}
+
+const CLAIM_ALL_SCRIPT: &str = r#"
+local items = redis.call('LRANGE', KEYS[1], 0, -1)
+if #items == 0 then return items end
+redis.call('DEL', KEYS[1])
+for i = 1, #items, 1000 do
+  redis.call('RPUSH', KEYS[2], unpack(items, i, math.min(i + 999, #items)))
+end
+return items
+"#;
+
+#[async_trait]
+impl ListStore for RedisListStore {
+    async fn claim_all(&mut self, key: &str, claim_key: &str) -> Result<Vec<String>> {
+        let mut connection = self.connection.get().await?;
+        let result = self
+            .claim_script
+            .key(key)
+            .key(claim_key)
+            .invoke_async(&mut connection)
+            .await;
+        self.connection.check(result)
+    }
+
+    async fn read_all(&mut self, key: &str) -> Result<Vec<String>> {
+        let mut connection = self.connection.get().await?;
+        let result = connection.lrange(key, 0, -1).await;
+        self.connection.check(result)
+    }
+
+    async fn push(&mut self, key: &str, value: String) -> Result<()> {
+        let mut connection = self.connection.get().await?;
+        let result = connection.rpush(key, value).await;
+        self.connection.check(result)
+    }
+
+    async fn trim(&mut self, key: &str, max_len: usize) -> Result<()> {
+        let mut connection = self.connection.get().await?;
+        let start = -(max_len.max(1) as isize);
+        let result = connection.ltrim(key, start, -1).await;
+        self.connection.check(result)
+    }
+
+    async fn last(&mut self, key: &str) -> Result<Option<String>> {
+        let mut connection = self.connection.get().await?;
+        let result = connection.lindex(key, -1).await;
+        self.connection.check(result)
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<()> {
+        let mut connection = self.connection.get().await?;
+        let result = connection.del(key).await;
+        self.connection.check(result)
+    }
+
+    async fn keys_matching(&mut self, pattern: &str) -> Result<Vec<String>> {
+        let mut connection = self.connection.get().await?;
+        let mut keys = Vec::new();
+        let iter: redis::RedisResult<redis::AsyncIter<String>> = connection.scan_match(pattern).await;
+        let mut iter = self.connection.check(iter)?;
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+}
+
+/// In-memory list store, a stand-in for Redis in tests. Clones share the same lists.
+#[derive(Clone, Default)]
+pub struct MemoryListStore {
+    lists: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+impl MemoryListStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self, key: &str) -> Vec<String> {
+        self.lists.lock().unwrap().get(key).cloned().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl ListStore for MemoryListStore {
+    async fn claim_all(&mut self, key: &str, claim_key: &str) -> Result<Vec<String>> {
+        let mut lists = self.lists.lock().unwrap();
+        let items = lists.remove(key).unwrap_or_default();
+        if !items.is_empty() {
+            lists.entry(claim_key.to_string()).or_default().extend(items.iter().cloned());
+        }
+        Ok(items)
+    }
+
+    async fn read_all(&mut self, key: &str) -> Result<Vec<String>> {
+        Ok(self.list(key))
+    }
+
+    async fn push(&mut self, key: &str, value: String) -> Result<()> {
+        let mut lists = self.lists.lock().unwrap();
+        lists.entry(key.to_string()).or_default().push(value);
+        Ok(())
+    }
+
+    async fn trim(&mut self, key: &str, max_len: usize) -> Result<()> {
+        if let Some(list) = self.lists.lock().unwrap().get_mut(key) {
+            let excess = list.len().saturating_sub(max_len.max(1));
+            list.drain(..excess);
+        }
+        Ok(())
+    }
+
+    async fn last(&mut self, key: &str) -> Result<Option<String>> {
+        Ok(self.list(key).pop())
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<()> {
+        self.lists.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn keys_matching(&mut self, pattern: &str) -> Result<Vec<String>> {
+        // Only the `prefix*suffix` shape used by the window source is supported
+        let (prefix, suffix) = pattern.split_once('*').unwrap_or((pattern, ""));
+        let lists = self.lists.lock().unwrap();
+        Ok(lists
+            .keys()
+            .filter(|key| key.starts_with(prefix) && key.ends_with(suffix) && key.len() >= prefix.len() + suffix.len())
+            .cloned()
+            .collect())
+    }
+}
+
+/// What to do with bundles pushed to a window after the engine already claimed it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LateBundlePolicy {
+    /// Include them in the window currently being claimed
+    RollForward,
+    /// Move them to the dead-letter list with a "late" reason
+    Reject,
+}
+
+/// Dead-letter record for a bundle the engine could not use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub window_id: u64,
+    pub reason: String,
+    pub payload: String,
+}
+
+/// How many already-claimed windows are re-checked for late arrivals
+const LATE_LOOKBACK_WINDOWS: usize = 5;
+
+/// Consumes the lists the API server pushes to `bundle_window:{id}`.
+///
+/// Each window is claimed atomically onto `bundle_window:{id}:processing` and only deleted
+/// once the engine acknowledges it, so a crash mid-window leaves the bundles to be recovered
+/// into the next window on restart. A requeued window's claim is read again with the next window.
+pub struct RedisListSource<S: ListStore = RedisListStore> {
+    store: S,
+    key_prefix: String,
+    late_policy: LateBundlePolicy,
+    recent_windows: VecDeque<u64>,
+    recovered: bool,
+    // Claim keys holding each taken window's entries, deleted when that window is acknowledged
+    unacked_keys: HashMap<u64, Vec<String>>,
+    requeued_keys: Vec<String>,
+    keyring: Option<Arc<EnvelopeKeyring>>,
+}
+
+impl RedisListSource<RedisListStore> {
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        Ok(Self::with_store(RedisListStore::connect(redis_url).await?))
+    }
+}
+
+impl<S: ListStore> RedisListSource<S> {
+    pub fn with_store(store: S) -> Self {
+        Self {
+            store,
+            key_prefix: "bundle_window".to_string(),
+            late_policy: LateBundlePolicy::RollForward,
+            recent_windows: VecDeque::new(),
+            recovered: false,
+            unacked_keys: HashMap::new(),
+            requeued_keys: Vec::new(),
+            keyring: None,
+        }
+    }
+
+    pub fn with_late_policy(mut self, late_policy: LateBundlePolicy) -> Self {
+        self.late_policy = late_policy;
+        self
+    }
+
+    /// Open encrypted bundles with these keys; without a keyring they are dead-lettered
+    pub fn with_keyring(mut self, keyring: Arc<EnvelopeKeyring>) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    pub fn window_key(&self, window_id: u64) -> String {
+        format!("{}:{}", self.key_prefix, window_id)
+    }
+
+    pub fn processing_key(&self, window_id: u64) -> String {
+        format!("{}:{}:processing", self.key_prefix, window_id)
+    }
+
+    /// Where late arrivals are claimed before being dead-lettered, kept apart from the window's own
+    /// claim so deleting them cannot touch entries still waiting to be acknowledged
+    pub fn late_key(&self, window_id: u64) -> String {
+        format!("{}:{}:late:processing", self.key_prefix, window_id)
+    }
+
+    pub fn dead_letter_key(&self) -> String {
+        format!("{}:dead_letter", self.key_prefix)
+    }
+
+    async fn dead_letter(&mut self, window_id: u64, reason: String, payload: String) -> Result<()> {
+        warn!("Dead-lettering bundle from window {}: {}", window_id, reason);
+        let record = serde_json::to_string(&DeadLetter { window_id, reason, payload })?;
+        let key = self.dead_letter_key();
+        self.store.push(&key, record).await
+    }
+
+    /// Claimed-but-unacknowledged windows left behind by a previous run
+    async fn recover(&mut self, window_id: u64) -> Result<Vec<String>> {
+        let pattern = format!("{}:*:processing", self.key_prefix);
+        let keys = self.store.keys_matching(&pattern).await?;
+        let mut entries = Vec::new();
+        for key in keys {
+            let items = self.store.read_all(&key).await?;
+            warn!("Recovering {} unacknowledged bundles from {}", items.len(), key);
+            entries.extend(items);
+            self.claimed(window_id, key);
+        }
+        Ok(entries)
+    }
+
+    /// Note that `key` holds entries delivered with `window_id`
+    fn claimed(&mut self, window_id: u64, key: String) {
+        let keys = self.unacked_keys.entry(window_id).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+}
+
+#[async_trait]
+impl<S: ListStore> BundleSource for RedisListSource<S> {
+    async fn take_window(&mut self, window_id: u64) -> Result<Vec<Bundle>> {
+        let mut entries = Vec::new();
+
+        if !self.recovered {
+            entries.extend(self.recover(window_id).await?);
+            self.recovered = true;
+        }
+
+        // Windows that failed processing, delivered again
+        while let Some(key) = self.requeued_keys.pop() {
+            self.claimed(window_id, key.clone());
+            entries.extend(self.store.read_all(&key).await?);
+        }
+
+        // Late arrivals in windows that were already claimed
+        let recent: Vec<u64> = self.recent_windows.iter().copied().filter(|w| *w < window_id).collect();
+        for late_window in recent {
+            let key = self.window_key(late_window);
+            let claim_key = match self.late_policy {
+                LateBundlePolicy::RollForward => self.processing_key(late_window),
+                LateBundlePolicy::Reject => self.late_key(late_window),
+            };
+            let late = self.store.claim_all(&key, &claim_key).await?;
+            if late.is_empty() {
+                continue;
+            }
+
+            match self.late_policy {
+                LateBundlePolicy::RollForward => {
+                    info!(
+                        "Rolling {} late bundles from window {} into window {}",
+                        late.len(), late_window, window_id
+                    );
+                    entries.extend(late);
+                    self.claimed(window_id, claim_key);
+                }
+                LateBundlePolicy::Reject => {
+                    for payload in late {
+                        let reason = format!("arrived after window {} was claimed", late_window);
+                        self.dead_letter(late_window, reason, payload).await?;
+                    }
+                    self.store.delete(&claim_key).await?;
+                }
+            }
+        }
+
+        let key = self.window_key(window_id);
+        let claim_key = self.processing_key(window_id);
+        self.claimed(window_id, claim_key.clone());
+        entries.extend(self.store.claim_all(&key, &claim_key).await?);
+
+        self.recent_windows.push_back(window_id);
+        while self.recent_windows.len() > LATE_LOOKBACK_WINDOWS {
+            self.recent_windows.pop_front();
+        }
+
+        let mut bundles = Vec::new();
+        for payload in entries {
+            match parse_bundle_entry(&payload, window_id, self.keyring.as_deref()) {
+                Ok(bundle) => bundles.push(bundle),
+                Err(e) => self.dead_letter(window_id, e.to_string(), payload).await?,
+            }
+        }
+
+        Ok(bundles)
+    }
+
+    async fn ack_window(&mut self, window_id: u64) -> Result<()> {
+        let keys = self.unacked_keys.remove(&window_id).unwrap_or_default();
+        for (i, key) in keys.iter().enumerate() {
+            if let Err(e) = self.store.delete(key).await {
+                // Keep what is left so a retried ack can finish the job
+                self.unacked_keys.insert(window_id, keys[i..].to_vec());
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn requeue_window(&mut self, window_id: u64) -> Result<bool> {
+        let keys = self.unacked_keys.remove(&window_id).unwrap_or_default();
+        for key in keys {
+            if !self.requeued_keys.contains(&key) {
+                self.requeued_keys.push(key);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Skipped windows are handled like late arrivals: rolled into `into_window` or dead-lettered
+    async fn skip_windows(&mut self, skipped: Range<u64>, into_window: u64) -> Result<()> {
+        // Scanning finds the few windows that hold bundles without visiting every skipped one
+        let pattern = format!("{}:*", self.key_prefix);
+        let mut windows: Vec<u64> = self
+            .store
+            .keys_matching(&pattern)
+            .await?
+            .iter()
+            .filter_map(|key| key.strip_prefix(&format!("{}:", self.key_prefix))?.parse().ok())
+            .filter(|window_id| skipped.contains(window_id))
+            .collect();
+        windows.sort();
+
+        for skipped_window in windows {
+            let key = self.window_key(skipped_window);
+            match self.late_policy {
+                LateBundlePolicy::RollForward => {
+                    let claim_key = self.processing_key(skipped_window);
+                    let entries = self.store.claim_all(&key, &claim_key).await?;
+                    info!(
+                        "Rolling {} bundles from skipped window {} into window {}",
+                        entries.len(), skipped_window, into_window
+                    );
+                    // Before the first window, recovery picks up every claim key anyway
+                    if self.recovered && !self.requeued_keys.contains(&claim_key) {
+                        self.requeued_keys.push(claim_key);
+                    }
+                }
+                LateBundlePolicy::Reject => {
+                    let claim_key = self.late_key(skipped_window);
+                    for payload in self.store.claim_all(&key, &claim_key).await? {
+                        let reason = format!("window {} was skipped", skipped_window);
+                        self.dead_letter(skipped_window, reason, payload).await?;
+                    }
+                    self.store.delete(&claim_key).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "redis-list"
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+}
+
+/// An entry read from a stream, with its fields as strings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: HashMap<String, String>,
+}
+
+/// Consumer-group stream operations the stream source needs, so it can run against Redis or memory
+#[async_trait]
+pub trait StreamStore: Send {
+    /// Create `group` on `stream`, reading from the beginning, unless it already exists
+    async fn ensure_group(&mut self, stream: &str, group: &str) -> Result<()>;
+    /// Read up to `count` entries for `consumer`: `"0"` re-reads its pending entries, `">"` reads new ones
+    async fn read_group(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        start_id: &str,
+        count: usize,
+    ) -> Result<Vec<StreamEntry>>;
+    async fn ack(&mut self, stream: &str, group: &str, ids: &[String]) -> Result<()>;
+    /// Append to a plain list, used for dead letters
+    async fn push(&mut self, key: &str, value: String) -> Result<()>;
+}
+
+/// Redis-backed stream store
+pub struct RedisStreamStore {
+    connection: RedisConnection,
+    // Groups created on this connection; forgotten after reconnecting in case Redis came back without its data
+    ready_groups: HashSet<(String, String)>,
+}
+
+impl RedisStreamStore {
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        Ok(Self {
+            connection: RedisConnection::connect(redis_url).await?,
+            ready_groups: HashSet::new(),
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_connected()
+    }
+
+    /// Pass a result through, forgetting the groups if the connection was lost
+    fn check<T>(&mut self, result: redis::RedisResult<T>) -> Result<T> {
+        let checked = self.connection.check(result);
+        if !self.connection.is_connected() {
+            self.ready_groups.clear();
+        }
+        checked
+    }
+}
+
+#[async_trait]
+impl StreamStore for RedisStreamStore {
+    async fn ensure_group(&mut self, stream: &str, group: &str) -> Result<()> {
+        let mut connection = self.connection.get().await?;
+        let name = (stream.to_string(), group.to_string());
+        if self.ready_groups.contains(&name) {
+            return Ok(());
+        }
+
+        // Creating a group that already exists fails with BUSYGROUP, which is fine
+        let created: redis::RedisResult<()> = connection.xgroup_create_mkstream(stream, group, "0").await;
+        match created {
+            Err(e) if e.code() != Some("BUSYGROUP") => self.check(Err(e)),
+            _ => {
+                self.ready_groups.insert(name);
+                Ok(())
+            }
+        }
+    }
+
+    async fn read_group(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        start_id: &str,
+        count: usize,
+    ) -> Result<Vec<StreamEntry>> {
+        let mut connection = self.connection.get().await?;
+        let options = StreamReadOptions::default().group(group, consumer).count(count);
+        let result = connection.xread_options(&[stream], &[start_id], &options).await;
+        let reply: StreamReadReply = self.check(result)?;
+        Ok(reply
+            .keys
+            .into_iter()
+            .flat_map(|key| key.ids)
+            .map(|entry| {
+                let fields = entry
+                    .map
+                    .keys()
+                    .filter_map(|field| entry.get::<String>(field).map(|value| (field.clone(), value)))
+                    .collect();
+                StreamEntry { id: entry.id, fields }
+            })
+            .collect())
+    }
+
+    async fn ack(&mut self, stream: &str, group: &str, ids: &[String]) -> Result<()> {
+        let mut connection = self.connection.get().await?;
+        let result: redis::RedisResult<()> = connection.xack(stream, group, ids).await;
+        self.check(result)
+    }
+
+    async fn push(&mut self, key: &str, value: String) -> Result<()> {
+        let mut connection = self.connection.get().await?;
+        let result: redis::RedisResult<()> = connection.rpush(key, value).await;
+        self.check(result)
+    }
+}
+
+/// In-memory stream store, a stand-in for Redis in tests. Clones share the same streams.
+#[derive(Clone, Default)]
+pub struct MemoryStreamStore {
+    inner: Arc<Mutex<MemoryStreams>>,
+}
+
+#[derive(Default)]
+struct MemoryStreams {
+    streams: HashMap<String, Vec<StreamEntry>>,
+    groups: HashMap<(String, String), MemoryGroup>,
+    lists: HashMap<String, Vec<String>>,
+    next_id: u64,
+}
+
+#[derive(Default)]
+struct MemoryGroup {
+    delivered: usize,
+    // (consumer, entry ID) pairs delivered and not yet acknowledged
+    pending: Vec<(String, String)>,
+}
+
+impl MemoryStreamStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an entry, as XADD would, and return its ID
+    pub fn add(&self, stream: &str, fields: &[(&str, &str)]) -> String {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let id = format!("{}-0", inner.next_id);
+        let fields = fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        inner
+            .streams
+            .entry(stream.to_string())
+            .or_default()
+            .push(StreamEntry { id: id.clone(), fields });
+        id
+    }
+
+    /// IDs delivered to `group` and not yet acknowledged
+    pub fn pending(&self, stream: &str, group: &str) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .groups
+            .get(&(stream.to_string(), group.to_string()))
+            .map(|group| group.pending.iter().map(|(_, id)| id.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn list(&self, key: &str) -> Vec<String> {
+        self.inner.lock().unwrap().lists.get(key).cloned().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl StreamStore for MemoryStreamStore {
+    async fn ensure_group(&mut self, stream: &str, group: &str) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.streams.entry(stream.to_string()).or_default();
+        inner.groups.entry((stream.to_string(), group.to_string())).or_default();
+        Ok(())
+    }
+
+    async fn read_group(
+        &mut self,
+        stream: &str,
+        group: &str,
+        consumer: &str,
+        start_id: &str,
+        count: usize,
+    ) -> Result<Vec<StreamEntry>> {
+        let mut inner = self.inner.lock().unwrap();
+        let MemoryStreams { streams, groups, .. } = &mut *inner;
+        let entries = streams.get(stream).map(Vec::as_slice).unwrap_or_default();
+        let Some(MemoryGroup { delivered, pending }) = groups.get_mut(&(stream.to_string(), group.to_string())) else {
+            anyhow::bail!("NOGROUP no such consumer group {} for stream {}", group, stream);
+        };
+
+        if start_id == ">" {
+            let new: Vec<StreamEntry> = entries.iter().skip(*delivered).take(count).cloned().collect();
+            *delivered += new.len();
+            pending.extend(new.iter().map(|entry| (consumer.to_string(), entry.id.clone())));
+            return Ok(new);
+        }
+        Ok(pending
+            .iter()
+            .filter(|(owner, _)| owner == consumer)
+            .filter_map(|(_, id)| entries.iter().find(|entry| &entry.id == id).cloned())
+            .take(count)
+            .collect())
+    }
+
+    async fn ack(&mut self, stream: &str, group: &str, ids: &[String]) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(group) = inner.groups.get_mut(&(stream.to_string(), group.to_string())) {
+            group.pending.retain(|(_, id)| !ids.contains(id));
+        }
+        Ok(())
+    }
+
+    async fn push(&mut self, key: &str, value: String) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.lists.entry(key.to_string()).or_default().push(value);
+        Ok(())
+    }
+}
+
+/// Envelopes sealed further ahead than this many windows are dead-lettered rather than held
+const MAX_SEALED_AHEAD_WINDOWS: u64 = 25;
+
+/// Consumes a Redis Stream through a consumer group, acknowledging entries once read.
+/// Every entry delivered since the previous window closed belongs to the closing window, except
+/// envelopes sealed for a later window, which stay pending until that window closes.
+pub struct RedisStreamSource<S: StreamStore = RedisStreamStore> {
+    store: S,
+    stream_key: String,
+    group: String,
+    consumer: String,
+    batch_size: usize,
+    // Start from our own pending entries so a crash before acknowledgment redelivers them
+    read_pending: bool,
+    // Entry IDs delivered with each taken window, acknowledged with that window
+    unacked_ids: HashMap<u64, Vec<String>>,
+    // Envelopes read before their window closed, by the window they were sealed for
+    deferred: BTreeMap<u64, Vec<(String, String)>>,
+    keyring: Option<Arc<EnvelopeKeyring>>,
+}
+
+impl RedisStreamSource<RedisStreamStore> {
+    pub async fn connect(redis_url: &str, stream_key: &str, group: &str, consumer: &str) -> Result<Self> {
+        let mut source = Self::with_store(RedisStreamStore::connect(redis_url).await?, stream_key, group, consumer);
+        if source.store.is_connected() {
+            source.store.ensure_group(stream_key, group).await?;
+        }
+        Ok(source)
+    }
+}
+
+impl<S: StreamStore> RedisStreamSource<S> {
+    /// Field of each stream entry that holds the bundle JSON
+    pub const BUNDLE_FIELD: &'static str = "bundle";
+
+    pub fn with_store(store: S, stream_key: &str, group: &str, consumer: &str) -> Self {
+        Self {
+            store,
+            stream_key: stream_key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            batch_size: 1000,
+            read_pending: true,
+            unacked_ids: HashMap::new(),
+            deferred: BTreeMap::new(),
+            keyring: None,
+        }
+    }
+
+    /// Open encrypted bundles with these keys; without a keyring they are dead-lettered
+    pub fn with_keyring(mut self, keyring: Arc<EnvelopeKeyring>) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    /// List that entries which cannot be used are moved to before they are acknowledged
+    pub fn dead_letter_key(&self) -> String {
+        format!("{}:dead_letter", self.stream_key)
+    }
+
+    async fn dead_letter(&mut self, window_id: u64, reason: String, payload: String) -> Result<()> {
+        warn!("Dead-lettering stream entry from window {}: {}", window_id, reason);
+        let record = serde_json::to_string(&DeadLetter { window_id, reason, payload })?;
+        let key = self.dead_letter_key();
+        self.store.push(&key, record).await
+    }
+
+    /// Whether `id` was already handed out with a window or is being held for a later one
+    fn is_tracked(&self, id: &str) -> bool {
+        self.unacked_ids.values().flatten().any(|tracked| tracked == id)
+            || self.deferred.values().flatten().any(|(deferred, _)| deferred == id)
+    }
+}
+
+#[async_trait]
+impl<S: StreamStore> BundleSource for RedisStreamSource<S> {
+    async fn take_window(&mut self, window_id: u64) -> Result<Vec<Bundle>> {
+        self.store.ensure_group(&self.stream_key, &self.group).await?;
+        // Our pending entries (after a restart or requeue) come first, then new ones
+        let start_ids: &[&str] = if self.read_pending { &["0", ">"] } else { &[">"] };
+
+        let mut entries = Vec::new();
+        for start_id in start_ids {
+            let read = self
+                .store
+                .read_group(&self.stream_key, &self.group, &self.consumer, start_id, self.batch_size)
+                .await?;
+            entries.extend(read.into_iter().filter(|entry| !self.is_tracked(&entry.id)));
+        }
+        self.read_pending = false;
+
+        let mut payloads = Vec::new();
+        // Envelopes held back until this window closed
+        let later = self.deferred.split_off(&(window_id + 1));
+        for (_, held) in std::mem::replace(&mut self.deferred, later) {
+            payloads.extend(held);
+        }
+        for entry in entries {
+            match entry.fields.get(Self::BUNDLE_FIELD) {
+                Some(payload) => payloads.push((entry.id, payload.clone())),
+                None => {
+                    let reason = format!("entry {} has no `{}` field", entry.id, Self::BUNDLE_FIELD);
+                    self.unacked_ids.entry(window_id).or_default().push(entry.id);
+                    self.dead_letter(window_id, reason, format!("{:?}", entry.fields)).await?;
+                }
+            }
+        }
+
+        let mut bundles = Vec::new();
+        for (id, payload) in payloads {
+            let parsed = parse_bundle_entry(&payload, window_id, self.keyring.as_deref());
+            if let Err(e) = &parsed {
+                if let Some(BundleError::Decryption(EnvelopeError::WindowNotClosed { sealed_for, .. })) = e.downcast_ref() {
+                    if *sealed_for <= window_id + MAX_SEALED_AHEAD_WINDOWS {
+                        // Left pending in the group and handed out once its own window closes
+                        debug!(entry_id = %id, sealed_for, window_id, "Holding envelope until its window closes");
+                        self.deferred.entry(*sealed_for).or_default().push((id, payload));
+                        continue;
+                    }
+                }
+            }
+
+            self.unacked_ids.entry(window_id).or_default().push(id);
+            match parsed {
+                Ok(bundle) => {
+                    debug!(bundle_id = %bundle.id, searcher = %bundle.searcher_pubkey, window_id, "Bundle ingested");
+                    bundles.push(bundle)
+                }
+                Err(e) => self.dead_letter(window_id, e.to_string(), payload).await?,
+            }
+        }
+        Ok(bundles)
+    }
+
+    async fn ack_window(&mut self, window_id: u64) -> Result<()> {
+        let Some(ids) = self.unacked_ids.remove(&window_id).filter(|ids| !ids.is_empty()) else {
+            return Ok(());
+        };
+        let result = self.store.ack(&self.stream_key, &self.group, &ids).await;
+        if result.is_err() {
+            self.unacked_ids.insert(window_id, ids);
+        }
+        result
+    }
+
+    /// The window's entries stay pending in the group and are read again with the next window
+    async fn requeue_window(&mut self, window_id: u64) -> Result<bool> {
+        self.unacked_ids.remove(&window_id);
+        self.read_pending = true;
+        Ok(true)
+    }
+
+    fn name(&self) -> &'static str {
+        "redis-stream"
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+}
+
+/// In-process source fed through a `tokio::sync::mpsc` channel
+pub struct ChannelSource {
+    receiver: mpsc::Receiver<Bundle>,
+}
+
+impl ChannelSource {
+    pub fn new(receiver: mpsc::Receiver<Bundle>) -> Self {
+        Self { receiver }
+    }
+
+    /// Create a source together with the sender that feeds it
+    pub fn channel(capacity: usize) -> (mpsc::Sender<Bundle>, Self) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (sender, Self::new(receiver))
+    }
+}
+
+#[async_trait]
+impl BundleSource for ChannelSource {
+    async fn take_window(&mut self, _window_id: u64) -> Result<Vec<Bundle>> {
+        let mut bundles = Vec::new();
+        while let Ok(bundle) = self.receiver.try_recv() {
+            bundles.push(bundle);
+        }
+        Ok(bundles)
+    }
+
+    fn name(&self) -> &'static str {
+        "channel"
+    }
+}
+
+/// Replays a JSONL capture of API bundles, one recorded window per live window.
+/// Bundles are grouped into recorded windows by their submission timestamp.
+pub struct JsonlReplaySource {
+    windows: VecDeque<Vec<Bundle>>,
+}
+
+impl JsonlReplaySource {
+    pub fn from_file(path: impl AsRef<Path>, window_duration_ms: u64) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::from_jsonl(&contents, window_duration_ms))
+    }
+
+    pub fn from_jsonl(contents: &str, window_duration_ms: u64) -> Self {
+        let mut windows: VecDeque<Vec<Bundle>> = VecDeque::new();
+        let mut current_window = None;
+
+        for (line_number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let wire = match serde_json::from_str::<WireBundle>(line) {
+                Ok(wire) => wire,
+                Err(e) => {
+                    warn!("Skipping replay line {}: {}", line_number + 1, e);
+                    continue;
+                }
+            };
+            let recorded_window = wire.timestamp / window_duration_ms.max(1);
+            let bundle = match wire.to_bundle() {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    warn!("Skipping replay line {}: {}", line_number + 1, e);
+                    continue;
+                }
+            };
+
+            if current_window != Some(recorded_window) {
+                windows.push_back(Vec::new());
+                current_window = Some(recorded_window);
+            }
+            windows.back_mut().unwrap().push(bundle);
+        }
+
+        Self { windows }
+    }
+
+    pub fn remaining_windows(&self) -> usize {
+        self.windows.len()
+    }
+}
+
+#[async_trait]
+impl BundleSource for JsonlReplaySource {
+    async fn take_window(&mut self, _window_id: u64) -> Result<Vec<Bundle>> {
+        Ok(self.windows.pop_front().unwrap_or_default())
+    }
+
+    fn name(&self) -> &'static str {
+        "jsonl-replay"
+    }
+}
+
+/// Window-keyed in-memory source, a stand-in for Redis in tests.
+/// Clones share the same storage, so a test can keep one to push bundles.
+#[derive(Clone, Default)]
+pub struct InMemorySource {
+    windows: Arc<Mutex<HashMap<u64, Vec<Bundle>>>>,
+}
+
+impl InMemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, window_id: u64, bundle: Bundle) {
+        let mut windows = self.windows.lock().unwrap();
+        windows.entry(window_id).or_default().push(bundle);
+    }
+
+    pub fn pending_windows(&self) -> usize {
+        self.windows.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl BundleSource for InMemorySource {
+    async fn take_window(&mut self, window_id: u64) -> Result<Vec<Bundle>> {
+        let mut windows = self.windows.lock().unwrap();
+        Ok(windows.remove(&window_id).unwrap_or_default())
+    }
+
+    async fn skip_windows(&mut self, skipped: Range<u64>, into_window: u64) -> Result<()> {
+        let mut windows = self.windows.lock().unwrap();
+        let skipped_ids: Vec<u64> = windows.keys().copied().filter(|id| skipped.contains(id)).collect();
+        for window_id in skipped_ids {
+            let bundles = windows.remove(&window_id).unwrap_or_default();
+            windows.entry(into_window).or_default().extend(bundles);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "in-memory"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+
+    fn create_wire_bundle(tip: u64, timestamp: u64) -> WireBundle {
+        let keypair = Keypair::new();
+        let mut tx = Transaction::new_with_payer(
+            &[system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 100)],
+            Some(&keypair.pubkey()),
+        );
+        tx.sign(&[&keypair], Hash::new_unique());
+        let mut wire = WireBundle::from_bundle(&Bundle::new(vec![tx], tip, keypair.pubkey().to_string())).unwrap();
+        wire.timestamp = timestamp;
+        wire
+    }
+
+    #[tokio::test]
+    async fn test_channel_source_drains_available_bundles() {
+        let (sender, mut source) = ChannelSource::channel(10);
+        sender.send(Bundle::new(vec![], 1000, "a".to_string())).await.unwrap();
+        sender.send(Bundle::new(vec![], 2000, "b".to_string())).await.unwrap();
+
+        assert_eq!(source.take_window(1).await.unwrap().len(), 2);
+        assert!(source.take_window(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_replay_groups_by_recorded_window() {
+        let lines: Vec<String> = [(1000, 0), (2000, 150), (3000, 250), (4000, 610)]
+            .iter()
+            .map(|(tip, ts)| serde_json::to_string(&create_wire_bundle(*tip, *ts)).unwrap())
+            .chain(std::iter::once("{not json".to_string()))
+            .collect();
+        let mut source = JsonlReplaySource::from_jsonl(&lines.join("\n"), 200);
+
+        assert_eq!(source.remaining_windows(), 3);
+        let first: Vec<u64> = source.take_window(100).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        assert_eq!(first, vec![1000, 2000]);
+        assert_eq!(source.take_window(101).await.unwrap().len(), 1);
+        assert_eq!(source.take_window(102).await.unwrap().len(), 1);
+        assert!(source.take_window(103).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_source_is_window_keyed() {
+        let handle = InMemorySource::new();
+        let mut source: Box<dyn BundleSource> = Box::new(handle.clone());
+        handle.push(5, Bundle::new(vec![], 1000, "a".to_string()));
+        handle.push(6, Bundle::new(vec![], 2000, "b".to_string()));
+
+        assert!(source.take_window(4).await.unwrap().is_empty());
+        assert_eq!(source.take_window(5).await.unwrap()[0].tip_lamports, 1000);
+        assert_eq!(handle.pending_windows(), 1);
+    }
+
+    fn wire_json(tip: u64) -> String {
+        serde_json::to_string(&create_wire_bundle(tip, 0)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_source_keeps_window_until_acked() {
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone());
+
+        assert_eq!(source.take_window(10).await.unwrap().len(), 1);
+        assert!(store.list("bundle_window:10").is_empty());
+        assert_eq!(store.list("bundle_window:10:processing").len(), 1);
+
+        source.ack_window(10).await.unwrap();
+        assert!(store.list("bundle_window:10:processing").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_source_acks_only_the_given_window() {
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        store.push("bundle_window:11", wire_json(2000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone());
+
+        source.take_window(10).await.unwrap();
+        source.take_window(11).await.unwrap();
+        source.ack_window(11).await.unwrap();
+
+        assert_eq!(store.list("bundle_window:10:processing").len(), 1);
+        assert!(store.list("bundle_window:11:processing").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_requeued_window_is_redelivered_with_the_next_one() {
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        store.push("bundle_window:11", wire_json(2000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone());
+
+        source.take_window(10).await.unwrap();
+        assert!(source.requeue_window(10).await.unwrap());
+
+        let mut tips: Vec<u64> = source.take_window(11).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        tips.sort();
+        assert_eq!(tips, vec![1000, 2000]);
+
+        source.ack_window(11).await.unwrap();
+        assert!(store.list("bundle_window:10:processing").is_empty());
+        assert!(store.list("bundle_window:11:processing").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_source_recovers_unacked_windows_after_crash() {
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        let mut crashed = RedisListSource::with_store(store.clone());
+        crashed.take_window(10).await.unwrap();
+        drop(crashed);
+
+        let mut restarted = RedisListSource::with_store(store.clone());
+        let bundles = restarted.take_window(11).await.unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].tip_lamports, 1000);
+
+        restarted.ack_window(11).await.unwrap();
+        assert!(store.list("bundle_window:10:processing").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_late_bundles_roll_forward() {
+        let mut store = MemoryListStore::new();
+        let mut source = RedisListSource::with_store(store.clone());
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        source.take_window(10).await.unwrap();
+        source.ack_window(10).await.unwrap();
+
+        store.push("bundle_window:10", wire_json(2000)).await.unwrap();
+        store.push("bundle_window:11", wire_json(3000)).await.unwrap();
+
+        let mut tips: Vec<u64> = source.take_window(11).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        tips.sort();
+        assert_eq!(tips, vec![2000, 3000]);
+    }
+
+    #[tokio::test]
+    async fn test_late_bundles_rejected_to_dead_letter() {
+        let mut store = MemoryListStore::new();
+        let mut source = RedisListSource::with_store(store.clone()).with_late_policy(LateBundlePolicy::Reject);
+        source.take_window(10).await.unwrap();
+        source.ack_window(10).await.unwrap();
+
+        store.push("bundle_window:10", wire_json(2000)).await.unwrap();
+        assert!(source.take_window(11).await.unwrap().is_empty());
+
+        let dead = store.list("bundle_window:dead_letter");
+        assert_eq!(dead.len(), 1);
+        let record: DeadLetter = serde_json::from_str(&dead[0]).unwrap();
+        assert_eq!(record.window_id, 10);
+        assert!(record.reason.contains("after window 10"));
+    }
+
+    #[tokio::test]
+    async fn test_rejecting_late_bundles_keeps_requeued_entries() {
+        let mut store = MemoryListStore::new();
+        let mut source = RedisListSource::with_store(store.clone()).with_late_policy(LateBundlePolicy::Reject);
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        source.take_window(10).await.unwrap();
+        source.requeue_window(10).await.unwrap();
+
+        store.push("bundle_window:10", wire_json(2000)).await.unwrap();
+        let tips: Vec<u64> = source.take_window(11).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        assert_eq!(tips, vec![1000]);
+        assert_eq!(store.list("bundle_window:dead_letter").len(), 1);
+        // The requeued bundle stays claimed until window 11 is acknowledged
+        assert_eq!(store.list("bundle_window:10:processing").len(), 1);
+        assert!(store.list("bundle_window:10:late:processing").is_empty());
+
+        source.requeue_window(11).await.unwrap();
+        let tips: Vec<u64> = source.take_window(12).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        assert_eq!(tips, vec![1000]);
+    }
+
+    #[tokio::test]
+    async fn test_skipped_windows_roll_forward_or_dead_letter() {
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:3", wire_json(1000)).await.unwrap();
+        store.push("bundle_window:7", wire_json(2000)).await.unwrap();
+        store.push("bundle_window:12", wire_json(3000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone());
+
+        source.skip_windows(2..12, 12).await.unwrap();
+        let mut tips: Vec<u64> = source.take_window(12).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        tips.sort();
+        assert_eq!(tips, vec![1000, 2000, 3000]);
+        source.ack_window(12).await.unwrap();
+        assert!(store.list("bundle_window:3:processing").is_empty());
+
+        // Once running, skipped windows are delivered with the window after them
+        store.push("bundle_window:14", wire_json(4000)).await.unwrap();
+        source.skip_windows(13..20, 20).await.unwrap();
+        let tips: Vec<u64> = source.take_window(20).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        assert_eq!(tips, vec![4000]);
+
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:3", wire_json(1000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone()).with_late_policy(LateBundlePolicy::Reject);
+        source.skip_windows(2..12, 12).await.unwrap();
+        assert!(source.take_window(12).await.unwrap().is_empty());
+        let dead = store.list("bundle_window:dead_letter");
+        let record: DeadLetter = serde_json::from_str(&dead[0]).unwrap();
+        assert_eq!((record.window_id, record.reason.as_str()), (3, "window 3 was skipped"));
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_bundles_are_dead_lettered() {
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:10", "{\"id\": \"x\"}".to_string()).await.unwrap();
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone());
+
+        assert_eq!(source.take_window(10).await.unwrap().len(), 1);
+        let dead = store.list("bundle_window:dead_letter");
+        assert_eq!(dead.len(), 1);
+        let record: DeadLetter = serde_json::from_str(&dead[0]).unwrap();
+        assert_eq!(record.payload, "{\"id\": \"x\"}");
+    }
+
+    #[tokio::test]
+    async fn test_list_source_opens_envelopes_after_window_closes() {
+        use crate::envelope::EnvelopeKey;
+
+        let key = EnvelopeKey::generate();
+        let sealed = |window_id: u64, tip: u64| {
+            serde_json::to_string(
+                &BundleEnvelope::seal(&key.public_key(), window_id, &create_wire_bundle(tip, 0)).unwrap(),
+            )
+            .unwrap()
+        };
+        let mut store = MemoryListStore::new();
+        store.push("bundle_window:10", sealed(10, 3000)).await.unwrap();
+        store.push("bundle_window:10", wire_json(1000)).await.unwrap();
+        // Filed under the wrong window, so it would be opened before its own window closed
+        store.push("bundle_window:10", sealed(11, 5000)).await.unwrap();
+        let mut source = RedisListSource::with_store(store.clone())
+            .with_keyring(Arc::new(EnvelopeKeyring::new(key.clone())));
+
+        let mut tips: Vec<_> = source.take_window(10).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        tips.sort();
+        assert_eq!(tips, vec![1000, 3000]);
+        let dead = store.list("bundle_window:dead_letter");
+        assert_eq!(dead.len(), 1);
+        let record: DeadLetter = serde_json::from_str(&dead[0]).unwrap();
+        assert!(record.reason.contains("before it closes"));
+        source.ack_window(10).await.unwrap();
+
+        // Without the key the envelope is dead-lettered rather than dropped
+        store.push("bundle_window:12", sealed(12, 3000)).await.unwrap();
+        let mut keyless = RedisListSource::with_store(store.clone());
+        assert!(keyless.take_window(12).await.unwrap().is_empty());
+        let err = parse_bundle_entry(&sealed(12, 1), 12, None).unwrap_err();
+        assert_eq!(err.downcast_ref::<BundleError>().map(BundleError::kind), Some("decryption_failed"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_source_holds_envelopes_until_their_window() {
+        use crate::envelope::EnvelopeKey;
+
+        let key = EnvelopeKey::generate();
+        let envelope = serde_json::to_string(
+            &BundleEnvelope::seal(&key.public_key(), 11, &create_wire_bundle(5000, 0)).unwrap(),
+        )
+        .unwrap();
+        let store = MemoryStreamStore::new();
+        let held_id = store.add("bundles", &[("bundle", &envelope)]);
+        store.add("bundles", &[("bundle", &wire_json(1000))]);
+        let mut source = RedisStreamSource::with_store(store.clone(), "bundles", "engine", "engine-1")
+            .with_keyring(Arc::new(EnvelopeKeyring::new(key)));
+
+        let tips: Vec<u64> = source.take_window(10).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        assert_eq!(tips, vec![1000]);
+        source.ack_window(10).await.unwrap();
+        // Neither dead-lettered nor acknowledged while window 11 is still open
+        assert!(store.list("bundles:dead_letter").is_empty());
+        assert_eq!(store.pending("bundles", "engine"), vec![held_id.clone()]);
+
+        let tips: Vec<u64> = source.take_window(11).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        assert_eq!(tips, vec![5000]);
+        source.ack_window(11).await.unwrap();
+        assert!(store.pending("bundles", "engine").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_source_redelivers_requeued_and_dead_letters_bad_entries() {
+        let store = MemoryStreamStore::new();
+        store.add("bundles", &[("bundle", &wire_json(1000))]);
+        store.add("bundles", &[("other", "x")]);
+        let mut source = RedisStreamSource::with_store(store.clone(), "bundles", "engine", "engine-1");
+
+        assert_eq!(source.take_window(10).await.unwrap().len(), 1);
+        assert_eq!(store.list("bundles:dead_letter").len(), 1);
+        assert!(source.requeue_window(10).await.unwrap());
+
+        store.add("bundles", &[("bundle", &wire_json(2000))]);
+        let mut tips: Vec<u64> = source.take_window(11).await.unwrap().iter().map(|b| b.tip_lamports).collect();
+        tips.sort();
+        assert_eq!(tips, vec![1000, 2000]);
+        source.ack_window(11).await.unwrap();
+        assert!(store.pending("bundles", "engine").is_empty());
+    }
+}
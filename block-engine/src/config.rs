@@ -0,0 +1,776 @@
+use crate::auction::{AuctionPolicy, PriorityFeePolicy};
+use crate::engine::EngineConfig;
+use crate::envelope::{EnvelopeKey, EnvelopeKeyring};
+use crate::leader_schedule::{LeaderSchedule, StakedValidator, DEFAULT_SLOTS_PER_LEADER};
+use crate::publisher::DEFAULT_STREAM_MAX_LEN;
+use crate::simulator::{DEFAULT_SIMULATION_CACHE_TTL, DEFAULT_SIMULATION_TIMEOUT};
+use crate::telemetry::LogFormat;
+use crate::window_store::DEFAULT_WINDOW_LOG_MAX_LEN;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::Level;
+
+/// Window length the API server derives bundle window ids from (`Date.now() / 200`)
+pub const API_WINDOW_MS: u64 = 200;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse config file {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error("invalid value for `{field}`: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+/// Auction ranking rule used to pick winners in each window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum BundlePolicy {
+    /// Highest tip wins, ties broken by bundle ID
+    PriorityFee,
+}
+
+impl BundlePolicy {
+    pub fn build(&self) -> Box<dyn AuctionPolicy> {
+        match self {
+            BundlePolicy::PriorityFee => Box::new(PriorityFeePolicy),
+        }
+    }
+}
+
+/// Where processed windows are persisted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowStoreKind {
+    /// Append-only JSON-lines file at `window_log_path`
+    File,
+    /// Redis list `engine:window_log` on `redis_url`
+    Redis,
+    /// Windows are not persisted
+    None,
+}
+
+/// Where the engine reads each window's bundles from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleSourceKind {
+    /// Redis lists `bundle_window:{id}` that the API server pushes to
+    List,
+    /// Redis stream `bundle_stream_key`, read through consumer group `bundle_stream_group`
+    Stream,
+    /// Replay of a JSON-lines bundle capture at `replay_path`
+    Jsonl,
+    /// JSON-lines bundles piped to the engine's stdin
+    Channel,
+}
+
+/// A validator in the leader schedule and the endpoint that receives its blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeaderConfig {
+    /// Base58 validator identity
+    pub identity: String,
+    pub stake: u64,
+    /// submit_block URL
+    pub endpoint: String,
+}
+
+/// Settings for the block-engine binary, loaded from TOML and overridden from the command line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockEngineConfig {
+    pub redis_url: String,
+    /// Must match the API server's window length, `API_WINDOW_MS`, whenever window ids come from it:
+    /// with `source = "list"` or when opening envelopes
+    pub window_duration_ms: u64,
+    /// Windows picked up more than this long after closing count as late
+    pub late_tolerance_ms: u64,
+    /// Backlog beyond this many windows is skipped rather than replayed
+    pub max_catch_up_windows: u64,
+    /// Bundles from failed windows older than this are dropped instead of requeued
+    pub max_requeue_age_ms: u64,
+    pub max_bundles_per_block: usize,
+    pub max_transactions_per_block: usize,
+    pub max_compute_units_per_block: u64,
+    pub max_pool_size: usize,
+    pub source: BundleSourceKind,
+    pub bundle_stream_key: String,
+    pub bundle_stream_group: String,
+    /// Consumer name within the group; keep it stable so a restart picks up its own pending entries
+    pub bundle_stream_consumer: String,
+    /// Capture replayed when `source = "jsonl"`
+    pub replay_path: Option<PathBuf>,
+    /// Solana JSON-RPC endpoint bundles are simulated against; bundles go unsimulated when unset
+    pub simulator_rpc_url: Option<String>,
+    /// Per-transaction simulation timeout; bundles that exceed it are rejected
+    pub simulation_timeout_ms: u64,
+    /// How long a simulation result is reused; the only bound on state changed outside this engine
+    pub simulation_cache_ttl_ms: u64,
+    pub validator_endpoints: Vec<String>,
+    /// Staked validators to schedule leaders from; when set, each block goes to all of them instead
+    /// of the `validator_endpoints`, and only its slot's leader and the next one decide acceptance
+    pub leaders: Vec<LeaderConfig>,
+    pub slots_per_leader: u64,
+    /// Per-attempt timeout for block submission
+    pub submit_timeout_ms: u64,
+    pub submit_max_attempts: u32,
+    /// Where blocks that could not be delivered are saved
+    pub failed_blocks_dir: PathBuf,
+    /// Bundles still queued at shutdown are saved here and requeued on the next start
+    pub pending_bundles_path: PathBuf,
+    pub window_store: WindowStoreKind,
+    pub window_log_path: PathBuf,
+    /// Records kept in the Redis window log when `window_store = "redis"`
+    pub window_log_max_len: usize,
+    /// Publish each window's outcome to Redis Pub/Sub and a capped stream
+    pub publish_outcomes: bool,
+    pub outcome_stream_max_len: usize,
+    /// Address serving `GET /metrics`
+    pub metrics_addr: SocketAddr,
+    pub log_level: String,
+    pub log_format: LogFormat,
+    /// OTLP/HTTP collector base URL; spans are exported only when set
+    pub otlp_endpoint: Option<String>,
+    pub bundle_policy: BundlePolicy,
+    /// Secret that opens encrypted bundles; without one, envelopes are dead-lettered
+    pub envelope_key_path: Option<PathBuf>,
+    /// Earlier envelope keys still accepted after a rotation
+    pub retired_envelope_key_paths: Vec<PathBuf>,
+    /// Solana JSON keypair that signs block attestations; a throwaway key is used when unset
+    pub engine_keypair_path: Option<PathBuf>,
+}
+
+impl Default for BlockEngineConfig {
+    fn default() -> Self {
+        let engine = EngineConfig::default();
+        Self {
+            redis_url: "redis://127.0.0.1/".to_string(),
+            window_duration_ms: engine.window_duration_ms,
+            late_tolerance_ms: engine.late_tolerance_ms,
+            max_catch_up_windows: engine.max_catch_up_windows,
+            max_requeue_age_ms: engine.max_requeue_age_ms,
+            max_bundles_per_block: engine.max_bundles_per_block,
+            max_transactions_per_block: engine.max_transactions_per_block,
+            max_compute_units_per_block: engine.max_compute_units_per_block,
+            max_pool_size: engine.max_pool_size,
+            source: BundleSourceKind::List,
+            bundle_stream_key: "bundle_stream".to_string(),
+            bundle_stream_group: "block-engine".to_string(),
+            bundle_stream_consumer: "block-engine-1".to_string(),
+            replay_path: None,
+            simulator_rpc_url: None,
+            simulation_timeout_ms: DEFAULT_SIMULATION_TIMEOUT.as_millis() as u64,
+            simulation_cache_ttl_ms: DEFAULT_SIMULATION_CACHE_TTL.as_millis() as u64,
+            validator_endpoints: vec!["http://localhost:4000/submit_block".to_string()],
+            leaders: Vec::new(),
+            slots_per_leader: DEFAULT_SLOTS_PER_LEADER,
+            submit_timeout_ms: 2_000,
+            submit_max_attempts: 3,
+            failed_blocks_dir: PathBuf::from("failed_blocks"),
+            pending_bundles_path: PathBuf::from("pending_bundles.jsonl"),
+            window_store: WindowStoreKind::File,
+            window_log_path: PathBuf::from("window_log.jsonl"),
+            window_log_max_len: DEFAULT_WINDOW_LOG_MAX_LEN,
+            publish_outcomes: true,
+            outcome_stream_max_len: DEFAULT_STREAM_MAX_LEN,
+            metrics_addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            log_level: "info".to_string(),
+            log_format: LogFormat::Text,
+            otlp_endpoint: None,
+            bundle_policy: BundlePolicy::PriorityFee,
+            envelope_key_path: None,
+            retired_envelope_key_paths: Vec::new(),
+            engine_keypair_path: None,
+        }
+    }
+}
+
+impl BlockEngineConfig {
+    pub fn from_toml_str(contents: &str, path: &Path) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml_str(&contents, path)
+    }
+
+    /// Check every setting, reporting the first invalid one
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: &str| {
+            Err(ConfigError::Invalid {
+                field,
+                reason: reason.to_string(),
+            })
+        };
+
+        if !self.redis_url.starts_with("redis://") && !self.redis_url.starts_with("rediss://") {
+            return invalid("redis_url", "must start with redis:// or rediss://");
+        }
+        if redis::Client::open(self.redis_url.as_str()).is_err() {
+            return invalid("redis_url", "is not a valid Redis connection URL");
+        }
+        if self.window_duration_ms == 0 {
+            return invalid("window_duration_ms", "must be greater than zero");
+        }
+        if self.envelope_key_path.is_some() && !matches!(self.source, BundleSourceKind::List | BundleSourceKind::Stream) {
+            return invalid("envelope_key_path", "sealed bundles are only read from source = \"list\" or \"stream\"");
+        }
+        // List keys and envelope window ids are API window ids; streams and replays carry none
+        let api_windows = self.source == BundleSourceKind::List || self.envelope_key_path.is_some();
+        if api_windows && self.window_duration_ms != API_WINDOW_MS {
+            return Err(ConfigError::Invalid {
+                field: "window_duration_ms",
+                reason: format!("must be {} to match the API server's window ids", API_WINDOW_MS),
+            });
+        }
+        if self.max_catch_up_windows == 0 {
+            return invalid("max_catch_up_windows", "must be greater than zero");
+        }
+        if self.max_requeue_age_ms == 0 {
+            return invalid("max_requeue_age_ms", "must be greater than zero");
+        }
+        if self.max_bundles_per_block == 0 {
+            return invalid("max_bundles_per_block", "must be greater than zero");
+        }
+        if self.max_transactions_per_block == 0 {
+            return invalid("max_transactions_per_block", "must be greater than zero");
+        }
+        if self.max_compute_units_per_block == 0 {
+            return invalid("max_compute_units_per_block", "must be greater than zero");
+        }
+        if self.max_pool_size < self.max_bundles_per_block {
+            return invalid("max_pool_size", "must be at least max_bundles_per_block");
+        }
+        if self.source == BundleSourceKind::Stream {
+            if self.bundle_stream_key.is_empty() {
+                return invalid("bundle_stream_key", "is required when source = \"stream\"");
+            }
+            if self.bundle_stream_group.is_empty() {
+                return invalid("bundle_stream_group", "is required when source = \"stream\"");
+            }
+            if self.bundle_stream_consumer.is_empty() {
+                return invalid("bundle_stream_consumer", "is required when source = \"stream\"");
+            }
+        }
+        if self.source == BundleSourceKind::Jsonl && self.replay_path.is_none() {
+            return invalid("replay_path", "is required when source = \"jsonl\"");
+        }
+        if let Some(url) = &self.simulator_rpc_url {
+            if !matches!(reqwest::Url::parse(url), Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https") {
+                return Err(ConfigError::Invalid {
+                    field: "simulator_rpc_url",
+                    reason: format!("`{}` is not an http(s) URL", url),
+                });
+            }
+        }
+        if self.simulation_timeout_ms == 0 {
+            return invalid("simulation_timeout_ms", "must be greater than zero");
+        }
+        if self.validator_endpoints.is_empty() {
+            return invalid("validator_endpoints", "at least one endpoint is required");
+        }
+        for endpoint in &self.validator_endpoints {
+            match reqwest::Url::parse(endpoint) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                _ => {
+                    return Err(ConfigError::Invalid {
+                        field: "validator_endpoints",
+                        reason: format!("`{}` is not an http(s) URL", endpoint),
+                    })
+                }
+            }
+        }
+        for leader in &self.leaders {
+            if leader.identity.parse::<Pubkey>().is_err() {
+                return Err(ConfigError::Invalid {
+                    field: "leaders",
+                    reason: format!("`{}` is not a base58 pubkey", leader.identity),
+                });
+            }
+            if !matches!(reqwest::Url::parse(&leader.endpoint), Ok(url) if url.scheme() == "http" || url.scheme() == "https") {
+                return Err(ConfigError::Invalid {
+                    field: "leaders",
+                    reason: format!("`{}` is not an http(s) URL", leader.endpoint),
+                });
+            }
+        }
+        if !self.leaders.is_empty() && self.leaders.iter().all(|l| l.stake == 0) {
+            return invalid("leaders", "at least one leader needs stake");
+        }
+        if self.slots_per_leader == 0 {
+            return invalid("slots_per_leader", "must be greater than zero");
+        }
+        if self.submit_timeout_ms == 0 {
+            return invalid("submit_timeout_ms", "must be greater than zero");
+        }
+        if self.submit_max_attempts == 0 {
+            return invalid("submit_max_attempts", "must be at least 1");
+        }
+        if self.pending_bundles_path.as_os_str().is_empty() {
+            return invalid("pending_bundles_path", "must not be empty");
+        }
+        if self.window_store == WindowStoreKind::File && self.window_log_path.as_os_str().is_empty() {
+            return invalid("window_log_path", "is required when window_store = \"file\"");
+        }
+        if self.window_store == WindowStoreKind::Redis && self.window_log_max_len == 0 {
+            return invalid("window_log_max_len", "must be greater than zero");
+        }
+        if self.publish_outcomes && self.outcome_stream_max_len == 0 {
+            return invalid("outcome_stream_max_len", "must be greater than zero");
+        }
+        if self.log_level.parse::<Level>().is_err() {
+            return invalid("log_level", "must be one of trace, debug, info, warn, error");
+        }
+        if let Some(endpoint) = &self.otlp_endpoint {
+            match reqwest::Url::parse(endpoint) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                _ => {
+                    return Err(ConfigError::Invalid {
+                        field: "otlp_endpoint",
+                        reason: format!("`{}` is not an http(s) URL", endpoint),
+                    })
+                }
+            }
+        }
+        if self.envelope_key_path.is_none() && !self.retired_envelope_key_paths.is_empty() {
+            return invalid("retired_envelope_key_paths", "requires envelope_key_path");
+        }
+        Ok(())
+    }
+
+    /// Load the envelope keys, if encrypted bundles are enabled
+    pub fn envelope_keyring(&self) -> anyhow::Result<Option<EnvelopeKeyring>> {
+        let Some(path) = &self.envelope_key_path else {
+            return Ok(None);
+        };
+        let load = |path: &PathBuf| {
+            EnvelopeKey::from_file(path).map_err(|e| anyhow::anyhow!("envelope key {}: {}", path.display(), e))
+        };
+        let mut keyring = EnvelopeKeyring::new(load(path)?).with_max_retired(self.retired_envelope_key_paths.len().max(2));
+        for retired in &self.retired_envelope_key_paths {
+            keyring = keyring.with_retired(load(retired)?);
+        }
+        Ok(Some(keyring))
+    }
+
+    /// Whether the bundle source, window store, outcome publisher or envelope key publishing talks to Redis
+    pub fn uses_redis(&self) -> bool {
+        matches!(self.source, BundleSourceKind::List | BundleSourceKind::Stream)
+            || self.window_store == WindowStoreKind::Redis
+            || self.publish_outcomes
+            || self.envelope_key_path.is_some()
+    }
+
+    /// Leader schedule over `leaders`, if any are configured; only call after `validate`
+    pub fn leader_schedule(&self) -> Option<LeaderSchedule> {
+        if self.leaders.is_empty() {
+            return None;
+        }
+        let validators = self
+            .leaders
+            .iter()
+            .filter_map(|l| Some(StakedValidator::new(l.identity.parse().ok()?, l.stake)));
+        Some(LeaderSchedule::new(validators).with_slots_per_leader(self.slots_per_leader))
+    }
+
+    /// Log level to install in the subscriber; only call after `validate`
+    pub fn tracing_level(&self) -> Level {
+        self.log_level.parse().unwrap_or(Level::INFO)
+    }
+
+    pub fn engine_config(&self) -> EngineConfig {
+        EngineConfig {
+            window_duration_ms: self.window_duration_ms,
+            max_bundles_per_block: self.max_bundles_per_block,
+            max_pool_size: self.max_pool_size,
+            max_transactions_per_block: self.max_transactions_per_block,
+            max_compute_units_per_block: self.max_compute_units_per_block,
+            late_tolerance_ms: self.late_tolerance_ms,
+            max_catch_up_windows: self.max_catch_up_windows,
+            max_requeue_age_ms: self.max_requeue_age_ms,
+        }
+    }
+}
+
+/// Command-line options for the block-engine binary. Flags take precedence over the config file.
+#[derive(Debug, Default, Parser)]
+#[command(name = "block-engine", about = "Runs bundle auctions and submits ordered blocks to validators")]
+pub struct CliArgs {
+    /// Path to a TOML config file
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    #[arg(long)]
+    pub redis_url: Option<String>,
+    /// Auction window length in milliseconds; must match the API server's
+    #[arg(long)]
+    pub window_ms: Option<u64>,
+    #[arg(long)]
+    pub max_bundles: Option<usize>,
+    #[arg(long)]
+    pub max_transactions: Option<usize>,
+    #[arg(long)]
+    pub max_compute_units: Option<u64>,
+    #[arg(long)]
+    pub max_pool_size: Option<usize>,
+    #[arg(long, value_enum)]
+    pub source: Option<BundleSourceKind>,
+    /// JSON-lines bundle capture to replay; implies --source jsonl
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+    /// Simulate bundles against this Solana JSON-RPC endpoint
+    #[arg(long)]
+    pub simulator_rpc_url: Option<String>,
+    /// Validator submit_block URL; repeat for several validators
+    #[arg(long = "validator")]
+    pub validator_endpoints: Vec<String>,
+    #[arg(long)]
+    pub failed_blocks_dir: Option<PathBuf>,
+    #[arg(long)]
+    pub pending_bundles_path: Option<PathBuf>,
+    #[arg(long, value_enum)]
+    pub window_store: Option<WindowStoreKind>,
+    #[arg(long)]
+    pub window_log_path: Option<PathBuf>,
+    #[arg(long)]
+    pub metrics_addr: Option<SocketAddr>,
+    /// Do not publish window outcomes to Redis
+    #[arg(long)]
+    pub no_publish: bool,
+    #[arg(long)]
+    pub log_level: Option<String>,
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+    /// Export spans to this OTLP/HTTP collector, e.g. http://localhost:4318
+    #[arg(long)]
+    pub otlp_endpoint: Option<String>,
+    #[arg(long, value_enum)]
+    pub bundle_policy: Option<BundlePolicy>,
+    /// File holding the base64 X25519 secret for encrypted bundles
+    #[arg(long)]
+    pub envelope_key: Option<PathBuf>,
+    /// Solana JSON keypair that signs block attestations
+    #[arg(long)]
+    pub keypair: Option<PathBuf>,
+}
+
+impl CliArgs {
+    /// Load the config file if given, apply flag overrides, then validate the result
+    pub fn resolve(self) -> Result<BlockEngineConfig, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => BlockEngineConfig::from_file(path)?,
+            None => BlockEngineConfig::default(),
+        };
+
+        if let Some(redis_url) = self.redis_url {
+            config.redis_url = redis_url;
+        }
+        if let Some(window_ms) = self.window_ms {
+            config.window_duration_ms = window_ms;
+        }
+        if let Some(max_bundles) = self.max_bundles {
+            config.max_bundles_per_block = max_bundles;
+        }
+        if let Some(max_transactions) = self.max_transactions {
+            config.max_transactions_per_block = max_transactions;
+        }
+        if let Some(max_compute_units) = self.max_compute_units {
+            config.max_compute_units_per_block = max_compute_units;
+        }
+        if let Some(max_pool_size) = self.max_pool_size {
+            config.max_pool_size = max_pool_size;
+        }
+        if let Some(replay) = self.replay {
+            config.replay_path = Some(replay);
+            config.source = BundleSourceKind::Jsonl;
+        }
+        if let Some(source) = self.source {
+            config.source = source;
+        }
+        if let Some(simulator_rpc_url) = self.simulator_rpc_url {
+            config.simulator_rpc_url = Some(simulator_rpc_url);
+        }
+        if !self.validator_endpoints.is_empty() {
+            config.validator_endpoints = self.validator_endpoints;
+        }
+        if let Some(failed_blocks_dir) = self.failed_blocks_dir {
+            config.failed_blocks_dir = failed_blocks_dir;
+        }
+        if let Some(pending_bundles_path) = self.pending_bundles_path {
+            config.pending_bundles_path = pending_bundles_path;
+        }
+        if let Some(window_store) = self.window_store {
+            config.window_store = window_store;
+        }
+        if let Some(window_log_path) = self.window_log_path {
+            config.window_log_path = window_log_path;
+        }
+        if let Some(metrics_addr) = self.metrics_addr {
+            config.metrics_addr = metrics_addr;
+        }
+        if self.no_publish {
+            config.publish_outcomes = false;
+        }
+        if let Some(log_level) = self.log_level {
+            config.log_level = log_level;
+        }
+        if let Some(log_format) = self.log_format {
+            config.log_format = log_format;
+        }
+        if let Some(otlp_endpoint) = self.otlp_endpoint {
+            config.otlp_endpoint = Some(otlp_endpoint);
+        }
+        if let Some(bundle_policy) = self.bundle_policy {
+            config.bundle_policy = bundle_policy;
+        }
+        if let Some(envelope_key) = self.envelope_key {
+            config.envelope_key_path = Some(envelope_key);
+        }
+        if let Some(keypair) = self.keypair {
+            config.engine_keypair_path = Some(keypair);
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = CliArgs::try_parse_from(["block-engine"]).unwrap().resolve().unwrap();
+        assert_eq!(config, BlockEngineConfig::default());
+        assert_eq!(config.engine_config().window_duration_ms, 200);
+    }
+
+    #[test]
+    fn test_file_values_overridden_by_flags() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+redis_url = "redis://redis.internal:6380/"
+window_duration_ms = 200
+late_tolerance_ms = 80
+max_bundles_per_block = 8
+validator_endpoints = ["http://validator-a:4000/submit_block", "http://validator-b:4000/submit_block"]
+log_level = "debug"
+log_format = "json"
+bundle_policy = "priority_fee"
+simulator_rpc_url = "http://rpc-a:8899"
+simulation_timeout_ms = 500
+simulation_cache_ttl_ms = 800
+"#
+        )
+        .unwrap();
+
+        let args = CliArgs::try_parse_from([
+            "block-engine",
+            "--config",
+            file.path().to_str().unwrap(),
+            "--max-bundles",
+            "6",
+            "--simulator-rpc-url",
+            "http://rpc-b:8899",
+        ])
+        .unwrap();
+        let config = args.resolve().unwrap();
+
+        assert_eq!(config.redis_url, "redis://redis.internal:6380/");
+        assert_eq!(config.window_duration_ms, 200);
+        assert_eq!(config.engine_config().late_tolerance_ms, 80);
+        assert_eq!(config.max_bundles_per_block, 6);
+        assert_eq!(config.validator_endpoints.len(), 2);
+        assert_eq!(config.tracing_level(), Level::DEBUG);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.simulator_rpc_url.as_deref(), Some("http://rpc-b:8899"));
+        assert_eq!(config.simulation_timeout_ms, 500);
+        assert_eq!(config.simulation_cache_ttl_ms, 800);
+    }
+
+    #[test]
+    fn test_invalid_settings_are_reported_by_field() {
+        let cases: Vec<(Vec<&str>, &str)> = vec![
+            (vec!["--window-ms", "0"], "window_duration_ms"),
+            (vec!["--window-ms", "250"], "window_duration_ms"),
+            (vec!["--max-bundles", "0"], "max_bundles_per_block"),
+            (vec!["--redis-url", "http://localhost"], "redis_url"),
+            (vec!["--validator", "localhost:4000"], "validator_endpoints"),
+            (vec!["--log-level", "loud"], "log_level"),
+            (vec!["--otlp-endpoint", "collector:4318"], "otlp_endpoint"),
+            (vec!["--max-pool-size", "2"], "max_pool_size"),
+            (vec!["--simulator-rpc-url", "localhost:8899"], "simulator_rpc_url"),
+            (vec!["--source", "jsonl"], "replay_path"),
+        ];
+
+        for (flags, expected_field) in cases {
+            let args = CliArgs::try_parse_from(std::iter::once("block-engine").chain(flags)).unwrap();
+            match args.resolve() {
+                Err(ConfigError::Invalid { field, .. }) => assert_eq!(field, expected_field),
+                other => panic!("expected invalid {}, got {:?}", expected_field, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_length_is_free_without_api_window_ids() {
+        let replay = BlockEngineConfig {
+            source: BundleSourceKind::Jsonl,
+            replay_path: Some(PathBuf::from("capture.jsonl")),
+            window_duration_ms: 400,
+            ..BlockEngineConfig::default()
+        };
+        assert!(replay.validate().is_ok());
+
+        let sealed = BlockEngineConfig {
+            source: BundleSourceKind::Stream,
+            envelope_key_path: Some(PathBuf::from("envelope.key")),
+            ..replay
+        };
+        assert!(matches!(sealed.validate(), Err(ConfigError::Invalid { field: "window_duration_ms", .. })));
+    }
+
+    #[test]
+    fn test_envelope_key_needs_a_redis_source() {
+        let sealed = |source| BlockEngineConfig {
+            source,
+            replay_path: Some(PathBuf::from("capture.jsonl")),
+            envelope_key_path: Some(PathBuf::from("envelope.key")),
+            ..BlockEngineConfig::default()
+        };
+        assert!(sealed(BundleSourceKind::List).validate().is_ok());
+        assert!(sealed(BundleSourceKind::Stream).validate().is_ok());
+        for source in [BundleSourceKind::Jsonl, BundleSourceKind::Channel] {
+            assert!(matches!(sealed(source).validate(), Err(ConfigError::Invalid { field: "envelope_key_path", .. })));
+        }
+    }
+
+    #[test]
+    fn test_bundle_source_selection() {
+        assert_eq!(BlockEngineConfig::default().source, BundleSourceKind::List);
+
+        let config = BlockEngineConfig::from_toml_str(
+            "source = \"stream\"\nbundle_stream_key = \"bundles\"",
+            Path::new("engine.toml"),
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!((config.source, config.bundle_stream_key.as_str()), (BundleSourceKind::Stream, "bundles"));
+
+        let unnamed = BlockEngineConfig { bundle_stream_consumer: String::new(), ..config };
+        assert!(matches!(unnamed.validate(), Err(ConfigError::Invalid { field: "bundle_stream_consumer", .. })));
+
+        let args = CliArgs::try_parse_from(["block-engine", "--replay", "capture.jsonl"]).unwrap();
+        let config = args.resolve().unwrap();
+        assert_eq!(config.source, BundleSourceKind::Jsonl);
+        assert_eq!(config.replay_path, Some(PathBuf::from("capture.jsonl")));
+    }
+
+    #[test]
+    fn test_redis_use_follows_source_store_and_publisher() {
+        assert!(BlockEngineConfig::default().uses_redis());
+
+        let replay = BlockEngineConfig {
+            source: BundleSourceKind::Jsonl,
+            replay_path: Some(PathBuf::from("capture.jsonl")),
+            publish_outcomes: false,
+            ..BlockEngineConfig::default()
+        };
+        assert!(!replay.uses_redis());
+        assert!(BlockEngineConfig { window_store: WindowStoreKind::Redis, ..replay.clone() }.uses_redis());
+        assert!(BlockEngineConfig { publish_outcomes: true, ..replay }.uses_redis());
+    }
+
+    #[test]
+    fn test_leaders_build_a_schedule() {
+        assert!(BlockEngineConfig::default().leader_schedule().is_none());
+
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let contents = format!(
+            r#"
+slots_per_leader = 2
+
+[[leaders]]
+identity = "{}"
+stake = 30
+endpoint = "http://validator-a:4000/submit_block"
+
+[[leaders]]
+identity = "{}"
+stake = 10
+endpoint = "http://validator-b:4000/submit_block"
+"#,
+            a, b
+        );
+        let config = BlockEngineConfig::from_toml_str(&contents, Path::new("engine.toml")).unwrap();
+        config.validate().unwrap();
+
+        let schedule = config.leader_schedule().unwrap();
+        assert_eq!(schedule.total_stake(), 40);
+        assert_eq!(schedule.slots_per_leader(), 2);
+
+        let mut bad = config.clone();
+        bad.leaders[1].identity = "not-a-key".to_string();
+        assert!(matches!(bad.validate(), Err(ConfigError::Invalid { field: "leaders", .. })));
+        let unstaked = BlockEngineConfig {
+            leaders: config.leaders.iter().map(|l| LeaderConfig { stake: 0, ..l.clone() }).collect(),
+            ..config
+        };
+        assert!(matches!(unstaked.validate(), Err(ConfigError::Invalid { field: "leaders", .. })));
+    }
+
+    #[test]
+    fn test_unknown_keys_and_missing_file_rejected() {
+        let err = BlockEngineConfig::from_toml_str("window_ms = 100", Path::new("engine.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+
+        let err = BlockEngineConfig::from_file(Path::new("/nonexistent/engine.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { .. }));
+    }
+
+    #[test]
+    fn test_envelope_keyring_loads_current_and_retired_keys() {
+        assert!(BlockEngineConfig::default().envelope_keyring().unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let (current, retired) = (EnvelopeKey::generate(), EnvelopeKey::generate());
+        current.save(&dir.path().join("current.key")).unwrap();
+        retired.save(&dir.path().join("retired.key")).unwrap();
+        let contents = format!(
+            "envelope_key_path = {:?}\nretired_envelope_key_paths = [{:?}]",
+            dir.path().join("current.key"),
+            dir.path().join("retired.key")
+        );
+        let config = BlockEngineConfig::from_toml_str(&contents, Path::new("engine.toml")).unwrap();
+        config.validate().unwrap();
+
+        let keyring = config.envelope_keyring().unwrap().unwrap();
+        assert_eq!(keyring.current().key_id, current.key_id());
+        assert_eq!(keyring.key_ids(), vec![current.key_id(), retired.key_id()]);
+
+        let orphaned = BlockEngineConfig { envelope_key_path: None, ..config };
+        assert!(matches!(
+            orphaned.validate(),
+            Err(ConfigError::Invalid { field: "retired_envelope_key_paths", .. })
+        ));
+    }
+}
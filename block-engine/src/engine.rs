@@ -0,0 +1,1377 @@
+use crate::auction::{AuctionPolicy, AuctionWindow, AuctionWindowStats, PriorityFeePolicy};
+use crate::block_assembler::BlockAssembler;
+use crate::bundle::Bundle;
+use crate::block_tree::Reorg;
+use crate::bundle_source::{parse_wire_bundle, BundleSource};
+use crate::health::EngineHealth;
+use crate::leader_schedule::LeaderSchedule;
+use crate::metrics::EngineMetrics;
+use crate::ordered_block::{now_millis, OrderedBlock, WireBundle};
+use crate::publisher::{
+    AuctionOutcomeEvent, BlockHashes, LosingBundle, OutcomePublisher, WinningBundle, OUTCOME_SCHEMA_VERSION,
+};
+use crate::scheduler::{SchedulerStats, WindowScheduler, WindowTiming};
+use crate::simulator::TransactionSimulator;
+use crate::sink::{BlockSink, SubmissionOutcome};
+use crate::transaction_pool::{PoolError, TransactionPool};
+use crate::window_store::{RankedBundle, RejectedBundle, WindowRecord, WindowStore};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::{Keypair, Signature, Signer}};
+use std::collections::HashSet;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{debug, info, info_span, instrument, warn, Instrument, Span};
+use anyhow::Result;
+use uuid::Uuid;
+
+/// Tunables for the auction loop
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub window_duration_ms: u64,
+    pub max_bundles_per_block: usize,
+    pub max_pool_size: usize,
+    pub max_transactions_per_block: usize,
+    pub max_compute_units_per_block: u64,
+    /// Windows picked up more than this long after closing count as late
+    pub late_tolerance_ms: u64,
+    /// Backlog beyond this many windows is skipped rather than replayed
+    pub max_catch_up_windows: u64,
+    /// Bundles from orphaned blocks older than this are dropped instead of re-queued
+    pub max_requeue_age_ms: u64,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            window_duration_ms: 200,
+            max_bundles_per_block: 5,
+            max_pool_size: 10_000,
+            max_transactions_per_block: 100,
+            max_compute_units_per_block: 1_000_000,
+            late_tolerance_ms: 50,
+            max_catch_up_windows: 50,
+            max_requeue_age_ms: 30_000,
+        }
+    }
+}
+
+/// What happened to one auction window
+#[derive(Debug, Clone)]
+pub struct WindowOutcome {
+    pub window_id: u64,
+    pub stats: AuctionWindowStats,
+    pub rejected: Vec<(Uuid, String)>,
+    pub block: Option<OrderedBlock>,
+    /// Delivery result for the block, if one was built
+    pub submission: Option<SubmissionOutcome>,
+}
+
+/// What happened to the bundles of blocks a reorg orphaned
+#[derive(Debug, Clone)]
+pub struct ReorgOutcome {
+    pub new_head: Hash,
+    /// Back in the pool for the next window
+    pub requeued: Vec<Uuid>,
+    pub dropped: Vec<(Uuid, String)>,
+}
+
+const SIMULATOR_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Drives bundles from ingestion through simulation, auction, assembly and submission
+pub struct BundleEngine {
+    config: EngineConfig,
+    pool: Arc<TransactionPool>,
+    simulator: Option<TransactionSimulator>,
+    policy: Box<dyn AuctionPolicy>,
+    assembler: BlockAssembler,
+    sink: Box<dyn BlockSink>,
+    source: Option<Box<dyn BundleSource>>,
+    scheduler: WindowScheduler,
+    window_store: Option<Box<dyn WindowStore>>,
+    publisher: Option<Box<dyn OutcomePublisher>>,
+    metrics: Arc<EngineMetrics>,
+    health: Arc<EngineHealth>,
+    /// When the simulator's RPC endpoint was last checked
+    simulator_checked_at: Option<Instant>,
+    /// Where bundles still queued in the pool are saved on shutdown and reloaded on start
+    pending_path: Option<PathBuf>,
+    /// Identity that signs every block
+    keypair: Keypair,
+    /// Chain switches reported by validators
+    reorgs: Option<broadcast::Receiver<Reorg>>,
+    parent_hash: Hash,
+}
+
+impl BundleEngine {
+    pub fn new(config: EngineConfig, sink: Box<dyn BlockSink>) -> Self {
+        let assembler = BlockAssembler::new(
+            Pubkey::default(),
+            config.max_transactions_per_block,
+            config.max_compute_units_per_block,
+        );
+
+        let scheduler = WindowScheduler::new(
+            config.window_duration_ms,
+            config.late_tolerance_ms,
+            config.max_catch_up_windows,
+        );
+
+        Self {
+            pool: Arc::new(TransactionPool::new(config.max_pool_size)),
+            simulator: None,
+            policy: Box::new(PriorityFeePolicy),
+            assembler,
+            sink,
+            source: None,
+            scheduler,
+            window_store: None,
+            publisher: None,
+            metrics: Arc::new(EngineMetrics::new()),
+            health: Arc::new(EngineHealth::new(config.window_duration_ms)),
+            simulator_checked_at: None,
+            pending_path: None,
+            keypair: Keypair::new(),
+            reorgs: None,
+            parent_hash: Hash::default(),
+            config,
+        }
+    }
+
+    /// Simulate every bundle before it enters the auction
+    pub fn with_simulator(mut self, simulator: TransactionSimulator) -> Self {
+        self.simulator = Some(simulator);
+        self
+    }
+
+    /// Sign blocks with this identity instead of a throwaway key
+    pub fn with_keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = keypair;
+        self
+    }
+
+    /// Re-queue or drop the bundles of orphaned blocks as reorgs arrive
+    pub fn with_reorg_feed(mut self, reorgs: broadcast::Receiver<Reorg>) -> Self {
+        self.reorgs = Some(reorgs);
+        self
+    }
+
+    /// Stamp each block with its slot's scheduled leader
+    pub fn with_leader_schedule(mut self, schedule: LeaderSchedule) -> Self {
+        self.assembler.leader_schedule = Some(schedule);
+        self
+    }
+
+    /// Key block attestations are signed with
+    pub fn engine_pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn with_policy(mut self, policy: Box<dyn AuctionPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Pull each closed window's bundles from the given source
+    pub fn with_source(mut self, source: Box<dyn BundleSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Append every processed window's inputs, ranking and block to `store`
+    pub fn with_window_store(mut self, store: Box<dyn WindowStore>) -> Self {
+        self.window_store = Some(store);
+        self
+    }
+
+    /// Publish every processed window's outcome, e.g. to Redis for the API server
+    pub fn with_publisher(mut self, publisher: Box<dyn OutcomePublisher>) -> Self {
+        self.publisher = Some(publisher);
+        self
+    }
+
+    /// Record into a shared metrics registry, e.g. one served on `/metrics`
+    pub fn with_metrics(mut self, metrics: Arc<EngineMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<EngineMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Share health state with the `/healthz` and `/readyz` handlers
+    pub fn with_health(mut self, health: Arc<EngineHealth>) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn health(&self) -> Arc<EngineHealth> {
+        Arc::clone(&self.health)
+    }
+
+    pub fn with_pending_bundles_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pending_path = Some(path.into());
+        self
+    }
+
+    /// Replace the window scheduler, e.g. with one driven by a test clock
+    pub fn with_scheduler(mut self, scheduler: WindowScheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn scheduler_stats(&self) -> SchedulerStats {
+        self.scheduler.stats()
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    pub fn pool(&self) -> Arc<TransactionPool> {
+        Arc::clone(&self.pool)
+    }
+
+    /// Queue a bundle for the next window to close
+    pub fn submit_bundle(&self, bundle: Bundle) -> Result<(), PoolError> {
+        self.metrics.bundles_received.inc();
+        self.pool.add_bundle(bundle).inspect_err(|e| self.metrics.record_rejection(e.kind()))
+    }
+
+    /// Run windows until Ctrl-C or SIGTERM
+    pub async fn start_auction_loop(&mut self) -> Result<()> {
+        self.run(shutdown_signal()).await
+    }
+
+    /// Process every window in order as its boundary passes until `shutdown` resolves.
+    /// A window that is already being processed always runs to completion, including its
+    /// submission and persistence; bundles still queued in the pool are then saved for the next run.
+    pub async fn run<F: Future<Output = ()>>(&mut self, shutdown: F) -> Result<()> {
+        tokio::pin!(shutdown);
+        self.restore_pending()?;
+        self.restore_last_window()?;
+
+        loop {
+            let scheduled = tokio::select! {
+                _ = &mut shutdown => {
+                    info!("Shutdown requested, stopping auction loop");
+                    let saved = self.persist_pending()?;
+                    if saved > 0 {
+                        info!("Saved {} queued bundles for the next run", saved);
+                    }
+                    self.persist_last_window()?;
+                    return Ok(());
+                }
+                scheduled = self.scheduler.next_window() => scheduled,
+            };
+
+            let window_id = scheduled.window_id;
+            if let WindowTiming::Late { lag_ms } = scheduled.timing {
+                warn!("Processing window {} {}ms after it closed", window_id, lag_ms);
+            }
+
+            self.apply_reorgs();
+
+            if let (Some(skipped), Some(source)) = (self.scheduler.take_skipped(), self.source.as_mut()) {
+                if let Err(e) = source.skip_windows(skipped.clone(), window_id).await {
+                    warn!("Failed to hand skipped windows {:?} on {}: {}", skipped, source.name(), e);
+                }
+            }
+
+            let mut taken_ok = true;
+            let incoming = match self.source.as_mut() {
+                Some(source) => {
+                    let span = info_span!("take_window", window_id, source = source.name());
+                    let started = Instant::now();
+                    let taken = source.take_window(window_id).instrument(span).await;
+                    if source.is_remote() {
+                        self.metrics.observe_redis_rtt("take_window", started.elapsed());
+                        self.health.record_redis(taken.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+                    }
+                    taken.unwrap_or_else(|e| {
+                        warn!("Failed to read bundles for window {} from {}: {}", window_id, source.name(), e);
+                        taken_ok = false;
+                        Vec::new()
+                    })
+                }
+                None => Vec::new(),
+            };
+            let incoming_ids: Vec<Uuid> = incoming.iter().map(|b| b.id).collect();
+
+            let processed = self.process_window(window_id, incoming).await;
+            if let Err(e) = &processed {
+                warn!("Auction processing failed for window {}: {}", window_id, e);
+            }
+            if let Some(source) = self.source.as_mut() {
+                if processed.is_ok() && taken_ok {
+                    if let Err(e) = source.ack_window(window_id).await {
+                        warn!("Failed to acknowledge window {} on {}: {}", window_id, source.name(), e);
+                    }
+                } else {
+                    // Whatever the source delivered comes back from it; the pool keeps only its own bundles
+                    match source.requeue_window(window_id).await {
+                        Ok(true) => {
+                            for id in &incoming_ids {
+                                self.pool.remove_bundle(id);
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(e) => warn!("Failed to requeue window {} on {}: {}", window_id, source.name(), e),
+                    }
+                }
+            }
+
+            self.scheduler.complete(scheduled);
+            self.metrics.observe_scheduler(&self.scheduler.stats());
+            if let Some(simulator) = &self.simulator {
+                self.metrics.observe_simulation_cache(&simulator.get_cache_stats());
+            }
+            self.health.record_window(window_id);
+            self.check_simulator().await;
+        }
+    }
+
+    /// Handle every reorg reported since the last call
+    pub fn apply_reorgs(&mut self) -> Vec<ReorgOutcome> {
+        let mut outcomes = Vec::new();
+        while let Some(feed) = self.reorgs.as_mut() {
+            match feed.try_recv() {
+                Ok(reorg) => outcomes.push(self.handle_reorg(&reorg)),
+                Err(broadcast::error::TryRecvError::Lagged(missed)) => {
+                    warn!("Missed {} reorg notifications; their bundles stay orphaned", missed)
+                }
+                Err(_) => break,
+            }
+        }
+        outcomes
+    }
+
+    /// Move the chain tip to the reorg's new head and give the orphaned blocks' bundles another
+    /// chance: each is re-queued unless it already landed on the adopted fork, is older than
+    /// `max_requeue_age_ms` or no longer fits in the pool
+    pub fn handle_reorg(&mut self, reorg: &Reorg) -> ReorgOutcome {
+        let landed: HashSet<Signature> = reorg
+            .adopted
+            .iter()
+            .flat_map(|b| b.transactions.iter().flat_map(|tx| tx.signatures.iter().copied()))
+            .collect();
+        let max_age = Duration::from_millis(self.config.max_requeue_age_ms);
+
+        let mut outcome = ReorgOutcome { new_head: reorg.new_head, requeued: Vec::new(), dropped: Vec::new() };
+        for bundle in reorg.orphaned.iter().flat_map(|b| b.bundles.iter()) {
+            let id = bundle.id;
+            let age = bundle.created_at.elapsed().unwrap_or_default();
+            let result = if bundle.transactions.iter().any(|tx| tx.signatures.iter().any(|s| landed.contains(s))) {
+                Err("already included on the adopted fork".to_string())
+            } else if age > max_age {
+                Err(format!("expired {}ms after submission", age.as_millis()))
+            } else {
+                self.pool.add_bundle(bundle.clone()).map_err(|e| e.to_string())
+            };
+            match result {
+                Ok(()) => outcome.requeued.push(id),
+                Err(reason) => outcome.dropped.push((id, reason)),
+            }
+        }
+
+        if reorg.orphaned.iter().any(|b| b.blockhash == self.parent_hash) {
+            self.parent_hash = reorg.new_head;
+        }
+        self.metrics.bundles_orphaned.with_label_values(&["requeued"]).inc_by(outcome.requeued.len() as u64);
+        self.metrics.bundles_orphaned.with_label_values(&["dropped"]).inc_by(outcome.dropped.len() as u64);
+        for (id, reason) in &outcome.dropped {
+            warn!(bundle_id = %id, "Dropping orphaned bundle {}: {}", id, reason);
+        }
+        info!(
+            "Reorg to {} orphaned {} blocks: {} bundles re-queued, {} dropped",
+            reorg.new_head,
+            reorg.orphaned.len(),
+            outcome.requeued.len(),
+            outcome.dropped.len()
+        );
+        outcome
+    }
+
+    /// Probe the simulator's RPC endpoint at most once per `SIMULATOR_CHECK_INTERVAL`
+    async fn check_simulator(&mut self) {
+        let Some(simulator) = &self.simulator else {
+            return;
+        };
+        if self.simulator_checked_at.is_some_and(|at| at.elapsed() < SIMULATOR_CHECK_INTERVAL) {
+            return;
+        }
+        self.simulator_checked_at = Some(Instant::now());
+
+        let result = match tokio::time::timeout(SIMULATOR_CHECK_INTERVAL, simulator.check_rpc()).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("no response within {}ms", SIMULATOR_CHECK_INTERVAL.as_millis())),
+        };
+        if let Err(reason) = &result {
+            warn!("Simulator RPC check failed: {}", reason);
+        }
+        self.health.record_simulator(result);
+    }
+
+    /// Run the auction for a closed window over `incoming` plus anything queued in the pool
+    #[instrument(skip_all, fields(window_id = window_id, slot = window_id, incoming = incoming.len()))]
+    pub async fn process_window(&mut self, window_id: u64, incoming: Vec<Bundle>) -> Result<WindowOutcome> {
+        let started = Instant::now();
+        let mut rejected = Vec::new();
+        let mut inputs = incoming.clone();
+        self.metrics.bundles_received.inc_by(incoming.len() as u64);
+
+        for bundle in incoming {
+            let bundle_id = bundle.id;
+            let _span = bundle_span(&bundle).entered();
+            match self.pool.add_bundle(bundle) {
+                Ok(()) => debug!("Bundle admitted to pool"),
+                Err(e) => {
+                    self.metrics.record_rejection(e.kind());
+                    rejected.push((bundle_id, e.to_string()));
+                }
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for bundle in self.pool.drain_pending() {
+            if !inputs.iter().any(|b| b.id == bundle.id) {
+                inputs.push(bundle.clone());
+            }
+            match &self.simulator {
+                Some(simulator) => match simulator.validate_bundle(&bundle).instrument(bundle_span(&bundle)).await {
+                    Ok(_) => {
+                        self.metrics.bundles_simulated.with_label_values(&["ok"]).inc();
+                        candidates.push(bundle)
+                    }
+                    Err(e) => {
+                        self.metrics.bundles_simulated.with_label_values(&["failed"]).inc();
+                        self.metrics.record_rejection(e.kind());
+                        rejected.push((bundle.id, e.to_string()))
+                    }
+                },
+                None => candidates.push(bundle),
+            }
+        }
+
+        for (bundle_id, reason) in &rejected {
+            warn!(bundle_id = %bundle_id, "Bundle {} rejected in window {}: {}", bundle_id, window_id, reason);
+        }
+
+        let mut auction_window = AuctionWindow::new(
+            window_id,
+            self.config.window_duration_ms,
+            self.config.max_bundles_per_block,
+        );
+        auction_window.bundles = candidates.clone();
+        let stats = auction_window.get_auction_stats();
+
+        self.metrics.observe_pool(&self.pool.get_stats());
+
+        if candidates.is_empty() {
+            self.metrics.observe_window(&stats, started.elapsed());
+            let outcome = WindowOutcome { window_id, stats, rejected, block: None, submission: None };
+            if !inputs.is_empty() {
+                self.record_window(&outcome, &inputs, &[]).await;
+                self.publish_outcome(&outcome, &inputs, &[]).await;
+            }
+            return Ok(outcome);
+        }
+
+        info!(
+            "Processing auction window {} with {} bundles",
+            window_id, candidates.len()
+        );
+
+        let (ranking, winners) = info_span!("auction", candidates = candidates.len()).in_scope(|| {
+            let ranking = self.policy.rank(window_id, candidates);
+            let winners = self.policy.select_winners(window_id, ranking.clone(), self.config.max_bundles_per_block);
+            for (position, bundle) in ranking.iter().enumerate() {
+                let won = winners.iter().any(|w| w.id == bundle.id);
+                debug!(bundle_id = %bundle.id, searcher = %bundle.searcher_pubkey, tip = bundle.tip_lamports, position, won, "Bundle ranked");
+            }
+            (ranking, winners)
+        });
+        self.metrics.bundles_won.inc_by(winners.len() as u64);
+        self.metrics.bundles_lost.inc_by(ranking.len().saturating_sub(winners.len()) as u64);
+
+        // Each window fills exactly one slot
+        let template = self.assembler.create_block_template(window_id, self.parent_hash);
+        let block = self
+            .assembler
+            .assemble_block(template, winners)
+            .instrument(info_span!("assemble_block", slot = window_id))
+            .await
+            .inspect_err(|_| self.return_to_pool(&ranking))?;
+        let blockhash = block.blockhash;
+
+        // Included transactions change state, so cached simulations touching them are stale
+        if let Some(simulator) = &self.simulator {
+            for tx in &block.transactions {
+                simulator.invalidate_accounts(&tx.message.account_keys);
+            }
+        }
+
+        let ordered_block = OrderedBlock::new(window_id, block)
+            .inspect_err(|_| self.return_to_pool(&ranking))?
+            .with_window_inputs(inputs.iter().map(|b| b.id.to_string()).collect())
+            .signed(&self.keypair);
+
+        info!(
+            "Built block for window {} with {} winning bundles → hash: {}",
+            window_id,
+            ordered_block.ordered_bundles.len(),
+            &ordered_block.ordered_hash[..16]
+        );
+
+        let submit_span = info_span!("submit_block", slot = window_id, ordered_hash = %ordered_block.ordered_hash);
+        let submission = SubmissionOutcome::from(self.sink.submit_block(&ordered_block).instrument(submit_span).await);
+        // Only a delivered block extends the chain; the next block builds on the last one a validator holds
+        if matches!(submission, SubmissionOutcome::Delivered(_)) {
+            self.parent_hash = blockhash;
+        }
+        self.metrics.observe_submission(&submission);
+        for stats in self.sink.validator_stats() {
+            self.metrics.observe_validator(&stats);
+        }
+        self.health.record_submission(&submission);
+        self.metrics.observe_window(&stats, started.elapsed());
+        match &submission {
+            SubmissionOutcome::Delivered(ack) => {
+                info!("Block for window {} delivered ({})", window_id, ack.status)
+            }
+            SubmissionOutcome::Rejected { reason } => {
+                warn!("Block for window {} rejected by validator: {}", window_id, reason)
+            }
+            SubmissionOutcome::Failed { reason } => {
+                warn!("Block for window {} could not be delivered: {}", window_id, reason)
+            }
+        }
+
+        let outcome = WindowOutcome {
+            window_id,
+            stats,
+            rejected,
+            block: Some(ordered_block),
+            submission: Some(submission),
+        };
+        self.record_window(&outcome, &inputs, &ranking).await;
+        self.publish_outcome(&outcome, &inputs, &ranking).await;
+        Ok(outcome)
+    }
+
+    /// Queue a failed window's candidates again so the next window can include them
+    fn return_to_pool(&self, bundles: &[Bundle]) {
+        for bundle in bundles {
+            if let Err(e) = self.pool.add_bundle(bundle.clone()) {
+                warn!(bundle_id = %bundle.id, "Dropping bundle from failed window: {}", e);
+            }
+        }
+    }
+
+    /// Write bundles still queued in the pool to the pending file, replacing it atomically
+    fn persist_pending(&self) -> Result<usize> {
+        let Some(path) = &self.pending_path else {
+            return Ok(0);
+        };
+        let bundles = self.pool.drain_pending();
+        if bundles.is_empty() {
+            return Ok(0);
+        }
+
+        let mut contents = String::new();
+        for bundle in &bundles {
+            contents.push_str(&serde_json::to_string(&WireBundle::from_bundle(bundle)?)?);
+            contents.push('\n');
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(bundles.len())
+    }
+
+    /// Requeue bundles saved by `persist_pending` and remove the file
+    fn restore_pending(&mut self) -> Result<()> {
+        let Some(path) = &self.pending_path else {
+            return Ok(());
+        };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut restored = 0;
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            match parse_wire_bundle(line).map(|bundle| self.pool.add_bundle(bundle)) {
+                Ok(Ok(())) => restored += 1,
+                Ok(Err(e)) => warn!("Dropping saved bundle: {}", e),
+                Err(e) => warn!("Skipping unreadable saved bundle: {}", e),
+            }
+        }
+        std::fs::remove_file(path)?;
+        info!("Restored {} bundles queued before the last shutdown", restored);
+        Ok(())
+    }
+
+    /// Marker file holding the last processed window, kept next to the pending file
+    fn last_window_path(&self) -> Option<PathBuf> {
+        self.pending_path.as_ref().map(|path| path.with_extension("last_window"))
+    }
+
+    /// Save the last processed window so the next run replays the windows missed while down
+    fn persist_last_window(&self) -> Result<()> {
+        let (Some(path), Some(window_id)) =
+            (self.last_window_path(), self.scheduler.stats().last_processed_window)
+        else {
+            return Ok(());
+        };
+        let tmp = path.with_extension("last_window.tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(window_id.to_string().as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Resume the scheduler after the window saved by `persist_last_window` and remove the file
+    fn restore_last_window(&mut self) -> Result<()> {
+        let Some(path) = self.last_window_path() else {
+            return Ok(());
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        match contents.trim().parse::<u64>() {
+            Ok(window_id) => {
+                self.scheduler.resume_after(window_id);
+                info!("Resuming after window {}", window_id);
+            }
+            Err(e) => warn!("Ignoring unreadable last window marker: {}", e),
+        }
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    /// Append the window to the store, if any; storage failures never stop the auction loop
+    async fn record_window(&self, outcome: &WindowOutcome, inputs: &[Bundle], ranking: &[Bundle]) {
+        let Some(store) = &self.window_store else {
+            return;
+        };
+
+        let record = WindowRecord {
+            window_id: outcome.window_id,
+            recorded_at: now_millis(),
+            inputs: inputs.iter().filter_map(|b| WireBundle::from_bundle(b).ok()).collect(),
+            ranking: ranking
+                .iter()
+                .map(|b| RankedBundle {
+                    bundle_id: b.id.to_string(),
+                    searcher_pubkey: b.searcher_pubkey.clone(),
+                    tip: b.tip_lamports,
+                })
+                .collect(),
+            winners: outcome
+                .block
+                .iter()
+                .flat_map(|block| block.ordered_bundles.iter().map(|b| b.id.clone()))
+                .collect(),
+            rejected: outcome
+                .rejected
+                .iter()
+                .map(|(id, reason)| RejectedBundle {
+                    bundle_id: id.to_string(),
+                    reason: reason.clone(),
+                })
+                .collect(),
+            block: outcome.block.clone(),
+            submission: outcome.submission.clone(),
+        };
+
+        if let Err(e) = store.append(&record).await {
+            warn!("Failed to persist window {}: {}", outcome.window_id, e);
+        }
+    }
+
+    /// Publish winners, losers with reasons and block hashes; failures are logged and dropped
+    async fn publish_outcome(&self, outcome: &WindowOutcome, inputs: &[Bundle], ranking: &[Bundle]) {
+        let Some(publisher) = &self.publisher else {
+            return;
+        };
+
+        let winner_ids: Vec<&str> = outcome
+            .block
+            .iter()
+            .flat_map(|block| block.ordered_bundles.iter().map(|b| b.id.as_str()))
+            .collect();
+        let winners = outcome
+            .block
+            .iter()
+            .flat_map(|block| block.ordered_bundles.iter())
+            .enumerate()
+            .map(|(position, b)| WinningBundle {
+                bundle_id: b.id.clone(),
+                searcher_pubkey: b.searcher_pubkey.clone(),
+                tip: b.tip,
+                position,
+            })
+            .collect();
+
+        let mut losers: Vec<LosingBundle> = outcome
+            .rejected
+            .iter()
+            .map(|(id, reason)| {
+                let input = inputs.iter().find(|b| b.id == *id);
+                LosingBundle {
+                    bundle_id: id.to_string(),
+                    searcher_pubkey: input.map(|b| b.searcher_pubkey.clone()),
+                    tip: input.map(|b| b.tip_lamports),
+                    reason: reason.clone(),
+                }
+            })
+            .collect();
+        losers.extend(
+            ranking
+                .iter()
+                .filter(|b| !winner_ids.contains(&b.id.to_string().as_str()))
+                .map(|b| LosingBundle {
+                    bundle_id: b.id.to_string(),
+                    searcher_pubkey: Some(b.searcher_pubkey.clone()),
+                    tip: Some(b.tip_lamports),
+                    reason: "outbid".to_string(),
+                }),
+        );
+
+        let event = AuctionOutcomeEvent {
+            schema_version: OUTCOME_SCHEMA_VERSION,
+            window_id: outcome.window_id,
+            published_at: now_millis(),
+            stats: outcome.stats.clone(),
+            winners,
+            losers,
+            block: outcome.block.as_ref().map(|b| BlockHashes {
+                slot: b.block.slot,
+                blockhash: b.block.blockhash.to_string(),
+                parent_hash: b.block.parent_hash.to_string(),
+                ordered_hash: b.ordered_hash.clone(),
+            }),
+            submission: outcome.submission.clone(),
+        };
+
+        if let Err(e) = publisher.publish(&event).await {
+            warn!("Failed to publish outcome for window {}: {}", outcome.window_id, e);
+        }
+    }
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("Cannot listen for SIGTERM, only Ctrl-C will stop the engine: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Span that ties every log line about one bundle to its ID and searcher
+fn bundle_span(bundle: &Bundle) -> Span {
+    info_span!("bundle", bundle_id = %bundle.id, searcher = %bundle.searcher_pubkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leader_schedule::StakedValidator;
+    use crate::block_assembler::{Block, MockValidatorClient};
+    use crate::validator::{QuorumThreshold, ValidatorNetwork};
+    use crate::bundle_source::InMemorySource;
+    use crate::sink::BlockAck;
+    use tokio::time::sleep;
+    use crate::simulator::MockSolanaRpcClient;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+
+    fn create_test_bundle(tip: u64) -> Bundle {
+        let keypair = Keypair::new();
+        let mut tx = Transaction::new_with_payer(
+            &[system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 100)],
+            Some(&keypair.pubkey()),
+        );
+        tx.sign(&[&keypair], Hash::new_unique());
+        Bundle::new(vec![tx], tip, keypair.pubkey().to_string())
+    }
+
+    fn engine_with_mock_sink(config: EngineConfig) -> (BundleEngine, MockValidatorClient) {
+        let sink = MockValidatorClient::new();
+        let observer = MockValidatorClient {
+            submitted_blocks: Arc::clone(&sink.submitted_blocks),
+            should_fail: false,
+        };
+        (BundleEngine::new(config, Box::new(sink)), observer)
+    }
+
+    #[tokio::test]
+    async fn test_process_window_builds_and_submits_block() {
+        let config = EngineConfig { max_bundles_per_block: 2, ..EngineConfig::default() };
+        let (engine, observer) = engine_with_mock_sink(config);
+        let mut engine = engine.with_simulator(TransactionSimulator::new(Box::new(MockSolanaRpcClient::new())));
+
+        let incoming = vec![
+            create_test_bundle(1000),
+            create_test_bundle(3000),
+            create_test_bundle(2000),
+            Bundle::new(vec![], 9000, "empty".to_string()),
+        ];
+        let outcome = engine.process_window(7, incoming).await.unwrap();
+
+        assert_eq!(outcome.stats.total_bundles, 3);
+        assert_eq!(outcome.rejected.len(), 1);
+        let block = outcome.block.unwrap();
+        assert_eq!(block.window_id, 7);
+        assert_eq!(block.block.slot, 7);
+        assert_eq!(block.ordered_bundles.iter().map(|b| b.tip).collect::<Vec<_>>(), vec![3000, 2000]);
+        assert!(block.verify_ordered_hash());
+        assert_eq!(outcome.submission, Some(SubmissionOutcome::Delivered(BlockAck::accepted(7))));
+
+        let submitted = observer.get_submitted_blocks();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0].total_tips, 5000);
+        assert_eq!(engine.pool().get_stats().total_bundles, 0);
+    }
+
+    #[tokio::test]
+    async fn test_blocks_are_signed_over_window_inputs() {
+        let keypair = Keypair::new();
+        let engine_key = keypair.pubkey();
+        let (engine, _observer) = engine_with_mock_sink(EngineConfig { max_bundles_per_block: 1, ..EngineConfig::default() });
+        let mut engine = engine.with_keypair(keypair);
+        assert_eq!(engine.engine_pubkey(), engine_key);
+
+        let incoming = vec![create_test_bundle(1000), create_test_bundle(3000)];
+        let ids: Vec<String> = incoming.iter().map(|b| b.id.to_string()).collect();
+        let block = engine.process_window(7, incoming).await.unwrap().block.unwrap();
+
+        assert_eq!(block.window_inputs, ids);
+        assert_eq!(crate::attestation::verify_block_attestation(&block, Some(&engine_key)), Ok(engine_key));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_are_led_by_scheduled_leader() {
+        let staked: Vec<StakedValidator> = (0..3).map(|_| StakedValidator::new(Pubkey::new_unique(), 1)).collect();
+        let schedule = LeaderSchedule::new(staked).with_slots_per_leader(1);
+        let (engine, observer) = engine_with_mock_sink(EngineConfig::default());
+        let mut engine = engine.with_leader_schedule(schedule.clone());
+
+        for slot in 20..26 {
+            engine.process_window(slot, vec![create_test_bundle(1000)]).await.unwrap();
+        }
+
+        let blocks = observer.get_submitted_blocks();
+        assert_eq!(blocks.len(), 6);
+        for block in blocks {
+            assert_eq!(Some(block.leader_pubkey), schedule.leader_for_slot(block.slot));
+            assert_eq!(block.blockhash, block.compute_blockhash());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_block_recorded_as_failed() {
+        let mut sink = MockValidatorClient::new();
+        sink.should_fail = true;
+        let mut engine = BundleEngine::new(EngineConfig::default(), Box::new(sink));
+
+        let outcome = engine.process_window(3, vec![create_test_bundle(1000)]).await.unwrap();
+
+        assert!(outcome.block.is_some());
+        assert!(matches!(outcome.submission, Some(SubmissionOutcome::Failed { .. })));
+
+        // The next block builds on the last delivered one, not the lost block
+        let next = engine.process_window(4, vec![create_test_bundle(1000)]).await.unwrap().block.unwrap();
+        assert_eq!(next.block.parent_hash, Hash::default());
+    }
+
+    #[tokio::test]
+    async fn test_window_inputs_ranking_and_block_are_persisted() {
+        use crate::bundle_source::MemoryListStore;
+        use crate::window_store::RedisWindowStore;
+
+        let lists = MemoryListStore::new();
+        let config = EngineConfig { max_bundles_per_block: 1, ..EngineConfig::default() };
+        let (engine, _observer) = engine_with_mock_sink(config);
+        let mut engine = engine
+            .with_simulator(TransactionSimulator::new(Box::new(MockSolanaRpcClient::new())))
+            .with_window_store(Box::new(RedisWindowStore::with_store(lists.clone())));
+
+        let low = create_test_bundle(1000);
+        let high = create_test_bundle(4000);
+        let empty = Bundle::new(vec![], 9000, "empty".to_string());
+        engine.process_window(11, vec![low.clone(), high.clone(), empty.clone()]).await.unwrap();
+        engine.process_window(12, vec![]).await.unwrap();
+
+        let records = RedisWindowStore::with_store(lists).load_all().await.unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.window_id, 11);
+        assert_eq!(record.inputs.len(), 3);
+        assert_eq!(
+            record.ranking.iter().map(|r| r.tip).collect::<Vec<_>>(),
+            vec![4000, 1000]
+        );
+        assert_eq!(record.winners, vec![high.id.to_string()]);
+        assert_eq!(record.rejected.len(), 1);
+        assert_eq!(record.rejected[0].bundle_id, empty.id.to_string());
+        assert_eq!(record.block.as_ref().unwrap().block.slot, 11);
+        assert!(matches!(record.submission, Some(SubmissionOutcome::Delivered(_))));
+    }
+
+    #[tokio::test]
+    async fn test_outcome_published_with_winners_and_losers() {
+        use crate::publisher::MemoryOutcomePublisher;
+
+        let publisher = MemoryOutcomePublisher::new();
+        let config = EngineConfig { max_bundles_per_block: 1, ..EngineConfig::default() };
+        let (engine, _observer) = engine_with_mock_sink(config);
+        let mut engine = engine
+            .with_simulator(TransactionSimulator::new(Box::new(MockSolanaRpcClient::new())))
+            .with_publisher(Box::new(publisher.clone()));
+
+        let low = create_test_bundle(1000);
+        let high = create_test_bundle(4000);
+        let empty = Bundle::new(vec![], 9000, "empty".to_string());
+        let outcome = engine.process_window(21, vec![low.clone(), high.clone(), empty.clone()]).await.unwrap();
+        engine.process_window(22, vec![]).await.unwrap();
+
+        let events = publisher.events();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.schema_version, OUTCOME_SCHEMA_VERSION);
+        assert_eq!(event.stats, outcome.stats);
+        assert_eq!(event.winners.len(), 1);
+        assert_eq!(event.winners[0].bundle_id, high.id.to_string());
+
+        let reasons: Vec<(&str, &str)> = event
+            .losers
+            .iter()
+            .map(|l| (l.bundle_id.as_str(), l.reason.as_str()))
+            .collect();
+        let low_id = low.id.to_string();
+        assert!(reasons.contains(&(low_id.as_str(), "outbid")));
+        let empty_loser = event.losers.iter().find(|l| l.bundle_id == empty.id.to_string()).unwrap();
+        assert_eq!(empty_loser.tip, Some(9000));
+        assert!(empty_loser.reason.contains("empty"));
+
+        let hashes = event.block.as_ref().unwrap();
+        assert_eq!(hashes.slot, 21);
+        assert_eq!(hashes.ordered_hash, outcome.block.unwrap().ordered_hash);
+    }
+
+    #[tokio::test]
+    async fn test_window_metrics_recorded() {
+        let config = EngineConfig { max_bundles_per_block: 1, max_pool_size: 3, ..EngineConfig::default() };
+        let (engine, _observer) = engine_with_mock_sink(config);
+        let mut engine = engine.with_simulator(TransactionSimulator::new(Box::new(MockSolanaRpcClient::new())));
+
+        let incoming = vec![
+            create_test_bundle(1000),
+            create_test_bundle(2000),
+            Bundle::new(vec![], 9000, "empty".to_string()),
+            create_test_bundle(3000),
+            create_test_bundle(4000),
+        ];
+        engine.process_window(4, incoming).await.unwrap();
+
+        let metrics = engine.metrics();
+        assert_eq!(metrics.bundles_received.get(), 5);
+        assert_eq!(metrics.bundles_rejected.with_label_values(&["empty_bundle"]).get(), 1);
+        assert_eq!(metrics.bundles_rejected.with_label_values(&["pool_full"]).get(), 1);
+        assert_eq!(metrics.bundles_simulated.with_label_values(&["ok"]).get(), 3);
+        assert_eq!(metrics.bundles_won.get(), 1);
+        assert_eq!(metrics.bundles_lost.get(), 2);
+        assert_eq!(metrics.block_submissions.with_label_values(&["delivered"]).get(), 1);
+        assert_eq!(metrics.window_processing_seconds.get_sample_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_simulations_are_rejected_without_stalling_the_window() {
+        let (engine, observer) = engine_with_mock_sink(EngineConfig::default());
+        let mut rpc = MockSolanaRpcClient::new();
+        rpc.set_latency(Duration::from_secs(5));
+        let simulator = TransactionSimulator::new(Box::new(rpc)).with_rpc_timeout(Duration::from_millis(20));
+        let mut engine = engine.with_simulator(simulator);
+
+        let outcome = engine.process_window(1, vec![create_test_bundle(1000)]).await.unwrap();
+
+        assert!(outcome.block.is_none());
+        assert!(observer.get_submitted_blocks().is_empty());
+        let metrics = engine.metrics();
+        assert_eq!(metrics.bundles_simulated.with_label_values(&["failed"]).get(), 1);
+        assert_eq!(metrics.bundles_rejected.with_label_values(&["simulation_failed"]).get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_empty_window_produces_no_block() {
+        let (mut engine, observer) = engine_with_mock_sink(EngineConfig::default());
+
+        let outcome = engine.process_window(1, vec![]).await.unwrap();
+
+        assert!(outcome.block.is_none());
+        assert!(observer.get_submitted_blocks().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blocks_chain_parent_hashes() {
+        let (mut engine, _observer) = engine_with_mock_sink(EngineConfig::default());
+
+        let first = engine.process_window(1, vec![create_test_bundle(1000)]).await.unwrap().block.unwrap();
+        let second = engine.process_window(2, vec![create_test_bundle(1000)]).await.unwrap().block.unwrap();
+
+        assert_eq!(second.block.parent_hash, first.block.blockhash);
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_bundles_requeued_or_dropped() {
+        let config = EngineConfig { max_bundles_per_block: 3, ..EngineConfig::default() };
+        let (mut engine, _observer) = engine_with_mock_sink(config);
+        let (fresh, landed, stale) = (create_test_bundle(3000), create_test_bundle(2000), create_test_bundle(1000));
+        let ids = (fresh.id, landed.id, stale.id);
+        let built = engine.process_window(1, vec![fresh, landed, stale]).await.unwrap().block.unwrap();
+
+        let mut orphaned = built.block.clone();
+        let stale_bundle = orphaned.bundles.iter_mut().find(|b| b.id == ids.2).unwrap();
+        stale_bundle.created_at -= Duration::from_secs(60);
+        let mut rival = Block { bundles: vec![], total_tips: 0, ..built.block.clone() };
+        rival.transactions = orphaned.bundles.iter().find(|b| b.id == ids.1).unwrap().transactions.clone();
+        rival.blockhash = Hash::new_unique();
+        let reorg = Reorg {
+            old_head: orphaned.blockhash,
+            new_head: rival.blockhash,
+            common_ancestor: Hash::default(),
+            orphaned: vec![orphaned],
+            adopted: vec![rival.clone()],
+        };
+
+        let outcome = engine.handle_reorg(&reorg);
+
+        assert_eq!(outcome.requeued, vec![ids.0]);
+        let dropped: Vec<Uuid> = outcome.dropped.iter().map(|(id, _)| *id).collect();
+        assert_eq!(dropped, vec![ids.1, ids.2]);
+        assert!(outcome.dropped[1].1.starts_with("expired"));
+        assert_eq!(engine.metrics().bundles_orphaned.with_label_values(&["dropped"]).get(), 2);
+
+        // The re-queued bundle goes into the next block, which builds on the adopted fork
+        let next = engine.process_window(2, vec![]).await.unwrap().block.unwrap();
+        assert_eq!(next.block.parent_hash, rival.blockhash);
+        assert_eq!(next.ordered_bundles.iter().map(|b| b.id.clone()).collect::<Vec<_>>(), vec![ids.0.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_engine_follows_network_reorgs() {
+        let mut network = ValidatorNetwork::with_stakes(&[1, 1, 1])
+            .with_seed(3)
+            .with_quorum(QuorumThreshold::new(1, 1))
+            .with_fork_rate(1.0);
+        for validator in &mut network.validators {
+            validator.verification_delay_ms = 0;
+        }
+        let reorgs = network.subscribe_reorgs();
+        let mut engine = BundleEngine::new(EngineConfig::default(), Box::new(network)).with_reorg_feed(reorgs);
+
+        let bundle = create_test_bundle(1000);
+        let bundle_id = bundle.id;
+        let first = engine.process_window(1, vec![bundle]).await.unwrap();
+        assert!(matches!(first.submission, Some(SubmissionOutcome::Delivered(_))));
+        assert!(engine.metrics().render().contains("block_engine_validator_blocks{"));
+
+        // Every validator switched to the same rival branch, so one reorg is reported
+        let outcomes = engine.apply_reorgs();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].requeued, vec![bundle_id]);
+
+        let second = engine.process_window(2, vec![]).await.unwrap();
+        let block = second.block.unwrap();
+        assert_eq!(block.block.parent_hash, outcomes[0].new_head);
+        assert_eq!(block.ordered_bundles[0].id, bundle_id.to_string());
+        assert!(matches!(second.submission, Some(SubmissionOutcome::Delivered(_))));
+    }
+
+    #[tokio::test]
+    async fn test_run_reads_windows_from_source() {
+        let config = EngineConfig { window_duration_ms: 20, ..EngineConfig::default() };
+        let (engine, observer) = engine_with_mock_sink(config);
+        let source = InMemorySource::new();
+        let mut engine = engine.with_source(Box::new(source.clone()));
+
+        // Queue bundles for the next few windows so at least one is picked up
+        let current_window = now_millis() / 20;
+        for window_id in current_window..current_window + 3 {
+            source.push(window_id, create_test_bundle(1000));
+        }
+
+        engine.run(sleep(Duration::from_millis(100))).await.unwrap();
+
+        assert!(!observer.get_submitted_blocks().is_empty());
+        for block in observer.get_submitted_blocks() {
+            assert!(block.slot >= current_window);
+        }
+    }
+
+    /// Fails its first read and records every ack and requeue
+    struct FlakySource {
+        log: Arc<std::sync::Mutex<Vec<(&'static str, u64)>>>,
+        reads: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl BundleSource for FlakySource {
+        async fn take_window(&mut self, window_id: u64) -> Result<Vec<Bundle>> {
+            self.reads += 1;
+            if self.reads == 1 {
+                anyhow::bail!("connection reset reading window {}", window_id);
+            }
+            Ok(Vec::new())
+        }
+
+        async fn ack_window(&mut self, window_id: u64) -> Result<()> {
+            self.log.lock().unwrap().push(("ack", window_id));
+            Ok(())
+        }
+
+        async fn requeue_window(&mut self, window_id: u64) -> Result<bool> {
+            self.log.lock().unwrap().push(("requeue", window_id));
+            Ok(true)
+        }
+
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unread_window_is_requeued_not_acked() {
+        let config = EngineConfig { window_duration_ms: 20, ..EngineConfig::default() };
+        let (engine, _observer) = engine_with_mock_sink(config);
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut engine = engine.with_source(Box::new(FlakySource { log: Arc::clone(&log), reads: 0 }));
+
+        engine.run(sleep(Duration::from_millis(100))).await.unwrap();
+
+        let log = log.lock().unwrap();
+        let (action, failed_window) = log[0];
+        assert_eq!(action, "requeue");
+        assert!(!log.contains(&("ack", failed_window)));
+        assert!(log[1..].iter().all(|(action, window)| *action == "ack" && *window > failed_window));
+        assert!(log.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_processes_pool_until_shutdown() {
+        let config = EngineConfig { window_duration_ms: 20, ..EngineConfig::default() };
+        let (mut engine, observer) = engine_with_mock_sink(config);
+        engine.submit_bundle(create_test_bundle(1000)).unwrap();
+
+        engine.run(sleep(Duration::from_millis(100))).await.unwrap();
+
+        assert_eq!(observer.get_submitted_blocks().len(), 1);
+        let stats = engine.scheduler_stats();
+        assert!(stats.on_time_windows + stats.late_windows >= 3);
+        assert!(stats.last_processed_window.is_some());
+
+        let report = engine.health().report();
+        assert_eq!(report.last_processed_window, stats.last_processed_window);
+        assert!(report.validator_sink.unwrap().healthy);
+    }
+
+    /// Signals when a submission starts, then takes a while to deliver it
+    struct SlowSink {
+        started: Arc<tokio::sync::Notify>,
+        delivered: Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl BlockSink for SlowSink {
+        async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, crate::sink::SubmissionError> {
+            self.started.notify_one();
+            sleep(Duration::from_millis(100)).await;
+            self.delivered.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(BlockAck::accepted(block.window_id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_progress_window() {
+        let started = Arc::new(tokio::sync::Notify::new());
+        let delivered = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let sink = SlowSink { started: Arc::clone(&started), delivered: Arc::clone(&delivered) };
+        let config = EngineConfig { window_duration_ms: 20, ..EngineConfig::default() };
+        let mut engine = BundleEngine::new(config, Box::new(sink));
+        engine.submit_bundle(create_test_bundle(1000)).unwrap();
+
+        // Shutdown is requested while the block is still being submitted
+        engine.run(started.notified()).await.unwrap();
+
+        assert_eq!(delivered.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(engine.scheduler_stats().last_processed_window.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_queued_bundles_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending.jsonl");
+        let bundle = create_test_bundle(4000);
+
+        // Windows are long enough that none closes before shutdown
+        let config = EngineConfig { window_duration_ms: 60_000, ..EngineConfig::default() };
+        let (engine, _) = engine_with_mock_sink(config);
+        let mut engine = engine.with_pending_bundles_path(&path);
+        engine.submit_bundle(bundle.clone()).unwrap();
+        engine.run(std::future::ready(())).await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        assert_eq!(engine.pool().get_stats().pending_count, 0);
+
+        let config = EngineConfig { window_duration_ms: 20, ..EngineConfig::default() };
+        let (engine, observer) = engine_with_mock_sink(config);
+        let mut engine = engine.with_pending_bundles_path(&path);
+        engine.run(sleep(Duration::from_millis(100))).await.unwrap();
+
+        let blocks = observer.get_submitted_blocks();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].bundles[0].id, bundle.id);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_last_window_survives_restart() {
+        use crate::scheduler::ManualClock;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending.jsonl");
+        let marker = dir.path().join("pending.last_window");
+
+        // The first run processed window 9 before shutting down
+        let clock = ManualClock::new(10 * 200);
+        let mut scheduler = WindowScheduler::with_clock(200, 50, 50, Arc::new(clock.clone()));
+        scheduler.resume_after(8);
+        let (engine, _) = engine_with_mock_sink(EngineConfig::default());
+        let mut engine = engine.with_pending_bundles_path(&path).with_scheduler(scheduler);
+        engine.run(sleep(Duration::from_millis(50))).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "9");
+
+        // The restarted engine replays windows 10..=13 from the source
+        clock.set(14 * 200);
+        let source = InMemorySource::new();
+        for window_id in 10..14 {
+            source.push(window_id, create_test_bundle(1000 + window_id));
+        }
+        let scheduler = WindowScheduler::with_clock(200, 50, 50, Arc::new(clock.clone()));
+        let (engine, observer) = engine_with_mock_sink(EngineConfig::default());
+        let mut engine = engine
+            .with_source(Box::new(source))
+            .with_pending_bundles_path(&path)
+            .with_scheduler(scheduler);
+        engine.run(sleep(Duration::from_millis(50))).await.unwrap();
+
+        let slots: Vec<u64> = observer.get_submitted_blocks().iter().map(|b| b.slot).collect();
+        assert_eq!(slots, vec![10, 11, 12, 13]);
+    }
+
+    #[tokio::test]
+    async fn test_run_replays_missed_windows_from_source() {
+        use crate::scheduler::ManualClock;
+
+        // The engine last processed window 9 and is now at window 14
+        let clock = ManualClock::new(15 * 200);
+        let mut scheduler = WindowScheduler::with_clock(200, 50, 50, Arc::new(clock.clone()));
+        scheduler.resume_after(9);
+
+        let source = InMemorySource::new();
+        for window_id in 10..14 {
+            source.push(window_id, create_test_bundle(1000 + window_id));
+        }
+
+        let (engine, observer) = engine_with_mock_sink(EngineConfig::default());
+        let mut engine = engine.with_source(Box::new(source.clone())).with_scheduler(scheduler);
+        engine.run(sleep(Duration::from_millis(50))).await.unwrap();
+
+        let slots: Vec<u64> = observer.get_submitted_blocks().iter().map(|b| b.slot).collect();
+        assert_eq!(slots, vec![10, 11, 12, 13]);
+        assert_eq!(engine.scheduler_stats().late_windows, 4);
+        assert_eq!(engine.scheduler_stats().on_time_windows, 1);
+        assert_eq!(engine.metrics().scheduler_windows.with_label_values(&["late"]).get(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_bundles_in_skipped_windows_roll_into_the_next_one() {
+        use crate::scheduler::ManualClock;
+
+        // Window 5 was last processed, the clock is in window 20 and only two windows are caught up
+        let clock = ManualClock::new(20 * 200 + 10);
+        let mut scheduler = WindowScheduler::with_clock(200, 50, 2, Arc::new(clock.clone()));
+        scheduler.resume_after(5);
+
+        let source = InMemorySource::new();
+        source.push(7, create_test_bundle(7_000));
+
+        let (engine, observer) = engine_with_mock_sink(EngineConfig::default());
+        let mut engine = engine.with_source(Box::new(source.clone())).with_scheduler(scheduler);
+        engine.run(sleep(Duration::from_millis(50))).await.unwrap();
+
+        let blocks = observer.get_submitted_blocks();
+        assert_eq!(blocks[0].slot, 18);
+        assert_eq!(blocks[0].total_tips, 7_000);
+        assert_eq!(source.pending_windows(), 0);
+    }
+
+    /// Never answers
+    struct StalledSink;
+
+    #[async_trait::async_trait]
+    impl BlockSink for StalledSink {
+        async fn submit_block(&self, _block: &OrderedBlock) -> Result<BlockAck, crate::sink::SubmissionError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stalled_sink_delays_the_next_window_by_at_most_one_window() {
+        use crate::sink::{RetryPolicy, RetryingBlockSink};
+
+        let window = Duration::from_millis(100);
+        let sink = RetryingBlockSink::new(Box::new(StalledSink), RetryPolicy::default()).with_deadline(window);
+        let config = EngineConfig { window_duration_ms: 100, late_tolerance_ms: 100, ..EngineConfig::default() };
+        let mut engine = BundleEngine::new(config, Box::new(sink));
+        for tip in 1..=10 {
+            engine.submit_bundle(create_test_bundle(tip * 1000)).unwrap();
+        }
+
+        engine.run(sleep(Duration::from_millis(750))).await.unwrap();
+
+        let stats = engine.scheduler_stats();
+        assert!(stats.on_time_windows >= 4, "{:?}", stats);
+        assert_eq!((stats.late_windows, stats.skipped_windows), (0, 0));
+    }
+}
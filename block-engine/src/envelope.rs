@@ -0,0 +1,442 @@
+use crate::ordered_block::WireBundle;
+use crate::redis_connection::RedisConnection;
+use aes_gcm_siv::aead::{Aead, NewAead, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rand::RngCore;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+use std::sync::RwLock;
+use tracing::info;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// Envelope format this engine seals and opens
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Redis key holding the engine's current envelope public key as JSON
+pub const ENVELOPE_KEY_KEY: &str = "bundle_envelope:public_key";
+
+const NONCE_LEN: usize = 12;
+
+/// Why an encrypted bundle could not be opened
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EnvelopeError {
+    #[error("unsupported envelope version {0}")]
+    UnsupportedVersion(u8),
+    #[error("envelope is sealed to unknown key {0}")]
+    UnknownKey(String),
+    #[error("bundle sealed for window {sealed_for} cannot be opened before it closes (closed: {closed})")]
+    WindowNotClosed { sealed_for: u64, closed: u64 },
+    #[error("malformed envelope: {0}")]
+    Malformed(String),
+    #[error("decryption failed")]
+    DecryptionFailed,
+    #[error("decrypted payload is not a bundle: {0}")]
+    InvalidPayload(String),
+}
+
+impl EnvelopeError {
+    /// Stable variant name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EnvelopeError::UnsupportedVersion(_) => "unsupported_version",
+            EnvelopeError::UnknownKey(_) => "unknown_key",
+            EnvelopeError::WindowNotClosed { .. } => "window_not_closed",
+            EnvelopeError::Malformed(_) => "malformed",
+            EnvelopeError::DecryptionFailed => "decryption_failed",
+            EnvelopeError::InvalidPayload(_) => "invalid_payload",
+        }
+    }
+}
+
+/// A bundle encrypted to the engine's (or an enclave's) X25519 key. Only the format version, the
+/// recipient key ID and the target window are visible until the holder of the key opens it.
+///
+/// Version 1: an ephemeral X25519 exchange derives an AES-256-GCM-SIV key, and the version, key ID
+/// and window ID are authenticated so an envelope cannot be moved to another window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEnvelope {
+    pub version: u8,
+    pub key_id: String,
+    pub window_id: u64,
+    pub ephemeral_key: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl BundleEnvelope {
+    /// Encrypt `bundle` to `public_key` for `window_id`
+    pub fn seal(public_key: &[u8; 32], window_id: u64, bundle: &WireBundle) -> Result<Self> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ephemeral = EnvelopeKey::generate();
+        let ephemeral_key = ephemeral.public_key();
+        let key_id = key_id(public_key);
+        let shared = ephemeral
+            .diffie_hellman(public_key)
+            .ok_or_else(|| anyhow::anyhow!("envelope public key is a low-order point"))?;
+        let plaintext = serde_json::to_vec(bundle)?;
+        let ciphertext = cipher(&shared, &ephemeral_key, public_key)
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload { msg: &plaintext, aad: &associated_data(ENVELOPE_VERSION, &key_id, window_id) },
+            )
+            .map_err(|_| anyhow::anyhow!("bundle too large to seal"))?;
+
+        Ok(Self {
+            version: ENVELOPE_VERSION,
+            key_id,
+            window_id,
+            ephemeral_key: BASE64.encode(ephemeral_key),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Hash of the envelope as received, for committing to inputs without revealing them
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update([self.version]);
+        hasher.update(self.key_id.as_bytes());
+        hasher.update(self.window_id.to_le_bytes());
+        hasher.update(self.ephemeral_key.as_bytes());
+        hasher.update(self.nonce.as_bytes());
+        hasher.update(self.ciphertext.as_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// Short identifier for a public key, carried in envelopes so the recipient can pick the right secret
+pub fn key_id(public_key: &[u8; 32]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..8])
+}
+
+/// Public half of an envelope key as published for searchers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopePublicKey {
+    pub version: u8,
+    pub key_id: String,
+    /// Base64 X25519 public key
+    pub public_key: String,
+}
+
+impl EnvelopePublicKey {
+    pub fn new(public_key: &[u8; 32]) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            key_id: key_id(public_key),
+            public_key: BASE64.encode(public_key),
+        }
+    }
+
+    /// Raw key to pass to `BundleEnvelope::seal`
+    pub fn to_bytes(&self) -> Result<[u8; 32]> {
+        BASE64
+            .decode(&self.public_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("envelope public key must be 32 bytes"))
+    }
+}
+
+/// X25519 secret that opens envelopes sealed to its public key
+#[derive(Clone)]
+pub struct EnvelopeKey {
+    secret: [u8; 32],
+}
+
+impl std::fmt::Debug for EnvelopeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvelopeKey").field("key_id", &self.key_id()).finish()
+    }
+}
+
+impl EnvelopeKey {
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::from_bytes(secret)
+    }
+
+    pub fn from_bytes(secret: [u8; 32]) -> Self {
+        Self { secret }
+    }
+
+    /// Read a base64-encoded 32-byte secret
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let encoded = std::fs::read_to_string(path)?;
+        let bytes = BASE64.decode(encoded.trim())?;
+        let secret: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| anyhow::anyhow!("envelope key must be 32 bytes, got {}", bytes.len()))?;
+        Ok(Self::from_bytes(secret))
+    }
+
+    /// Write the secret in the format `from_file` reads, readable only by the owner on Unix
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        // The mode only applies to new files; an existing one would keep its permissions
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(BASE64.encode(self.secret).as_bytes())?;
+        Ok(())
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        PublicKey::from(&StaticSecret::from(self.secret)).to_bytes()
+    }
+
+    pub fn key_id(&self) -> String {
+        key_id(&self.public_key())
+    }
+
+    pub fn published(&self) -> EnvelopePublicKey {
+        EnvelopePublicKey::new(&self.public_key())
+    }
+
+    /// Decrypt an envelope once `closed_window` (the latest window to have closed) has reached its window
+    pub fn open(&self, envelope: &BundleEnvelope, closed_window: u64) -> Result<WireBundle, EnvelopeError> {
+        if envelope.version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(envelope.version));
+        }
+        let public_key = self.public_key();
+        if envelope.key_id != key_id(&public_key) {
+            return Err(EnvelopeError::UnknownKey(envelope.key_id.clone()));
+        }
+        if envelope.window_id > closed_window {
+            return Err(EnvelopeError::WindowNotClosed { sealed_for: envelope.window_id, closed: closed_window });
+        }
+
+        let ephemeral_key: [u8; 32] = decode(&envelope.ephemeral_key, "ephemeral_key")?
+            .try_into()
+            .map_err(|_| EnvelopeError::Malformed("ephemeral_key must be 32 bytes".to_string()))?;
+        let nonce: [u8; NONCE_LEN] = decode(&envelope.nonce, "nonce")?
+            .try_into()
+            .map_err(|_| EnvelopeError::Malformed(format!("nonce must be {} bytes", NONCE_LEN)))?;
+        let ciphertext = decode(&envelope.ciphertext, "ciphertext")?;
+
+        let shared = self
+            .diffie_hellman(&ephemeral_key)
+            .ok_or_else(|| EnvelopeError::Malformed("ephemeral_key is a low-order point".to_string()))?;
+        let aad = associated_data(envelope.version, &envelope.key_id, envelope.window_id);
+        let plaintext = cipher(&shared, &ephemeral_key, &public_key)
+            .decrypt(&Nonce::from(nonce), Payload { msg: &ciphertext, aad: &aad })
+            .map_err(|_| EnvelopeError::DecryptionFailed)?;
+        serde_json::from_slice(&plaintext).map_err(|e| EnvelopeError::InvalidPayload(e.to_string()))
+    }
+
+    /// X25519 shared secret with `public_key`, or `None` for a low-order point that would force a
+    /// known, all-zero secret
+    fn diffie_hellman(&self, public_key: &[u8; 32]) -> Option<[u8; 32]> {
+        let shared = StaticSecret::from(self.secret).diffie_hellman(&PublicKey::from(*public_key));
+        shared.was_contributory().then(|| shared.to_bytes())
+    }
+}
+
+/// The current envelope key plus recently retired ones, so bundles sealed just before a
+/// rotation can still be opened
+#[derive(Debug)]
+pub struct EnvelopeKeyring {
+    keys: RwLock<Keys>,
+    max_retired: usize,
+}
+
+#[derive(Debug)]
+struct Keys {
+    current: EnvelopeKey,
+    retired: VecDeque<EnvelopeKey>,
+}
+
+impl EnvelopeKeyring {
+    pub fn new(current: EnvelopeKey) -> Self {
+        Self {
+            keys: RwLock::new(Keys { current, retired: VecDeque::new() }),
+            max_retired: 2,
+        }
+    }
+
+    /// How many previous keys stay usable after a rotation
+    pub fn with_max_retired(mut self, max_retired: usize) -> Self {
+        self.max_retired = max_retired;
+        self
+    }
+
+    /// Keep accepting envelopes sealed to an older key
+    pub fn with_retired(self, key: EnvelopeKey) -> Self {
+        {
+            let mut keys = self.keys.write().unwrap();
+            keys.retired.push_back(key);
+            while keys.retired.len() > self.max_retired {
+                keys.retired.pop_front();
+            }
+        }
+        self
+    }
+
+    /// Key searchers should seal new bundles to
+    pub fn current(&self) -> EnvelopePublicKey {
+        self.keys.read().unwrap().current.published()
+    }
+
+    /// Make `next` the current key; the previous one is retired rather than dropped
+    pub fn rotate(&self, next: EnvelopeKey) {
+        let mut keys = self.keys.write().unwrap();
+        let previous = std::mem::replace(&mut keys.current, next);
+        info!("Rotated envelope key {} -> {}", previous.key_id(), keys.current.key_id());
+        keys.retired.push_front(previous);
+        keys.retired.truncate(self.max_retired);
+    }
+
+    /// IDs of every key that can currently open envelopes, current first
+    pub fn key_ids(&self) -> Vec<String> {
+        let keys = self.keys.read().unwrap();
+        std::iter::once(&keys.current).chain(&keys.retired).map(EnvelopeKey::key_id).collect()
+    }
+
+    pub fn open(&self, envelope: &BundleEnvelope, closed_window: u64) -> Result<WireBundle, EnvelopeError> {
+        let keys = self.keys.read().unwrap();
+        std::iter::once(&keys.current)
+            .chain(&keys.retired)
+            .find(|key| key.key_id() == envelope.key_id)
+            .ok_or_else(|| EnvelopeError::UnknownKey(envelope.key_id.clone()))?
+            .open(envelope, closed_window)
+    }
+}
+
+/// Publish the keyring's current public key under `ENVELOPE_KEY_KEY` so searchers know what to seal to
+pub async fn publish_envelope_key(connection: &mut RedisConnection, keyring: &EnvelopeKeyring) -> Result<()> {
+    let current = keyring.current();
+    let mut redis = connection.get().await?;
+    let result: redis::RedisResult<()> = redis.set(ENVELOPE_KEY_KEY, serde_json::to_string(&current)?).await;
+    connection.check(result)?;
+    info!("Published envelope key {} (version {})", current.key_id, current.version);
+    Ok(())
+}
+
+fn associated_data(version: u8, key_id: &str, window_id: u64) -> Vec<u8> {
+    let mut aad = vec![version];
+    aad.extend_from_slice(key_id.as_bytes());
+    aad.extend_from_slice(&window_id.to_le_bytes());
+    aad
+}
+
+fn cipher(shared: &[u8; 32], ephemeral_key: &[u8; 32], public_key: &[u8; 32]) -> Aes256GcmSiv {
+    let mut hasher = Sha256::new();
+    hasher.update(b"bundle envelope v1");
+    hasher.update(shared);
+    hasher.update(ephemeral_key);
+    hasher.update(public_key);
+    Aes256GcmSiv::new(&hasher.finalize())
+}
+
+fn decode(value: &str, field: &str) -> Result<Vec<u8>, EnvelopeError> {
+    BASE64
+        .decode(value)
+        .map_err(|e| EnvelopeError::Malformed(format!("{} is not valid base64: {}", field, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::Bundle;
+
+    fn wire_bundle(tip: u64) -> WireBundle {
+        WireBundle::from_bundle(&Bundle::new(vec![], tip, "searcher".to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_envelope_opens_only_after_its_window_closes() {
+        let key = EnvelopeKey::generate();
+        let bundle = wire_bundle(1000);
+        let envelope = BundleEnvelope::seal(&key.public_key(), 7, &bundle).unwrap();
+        assert_eq!(envelope.version, ENVELOPE_VERSION);
+        assert_eq!(envelope.key_id, key.key_id());
+        assert!(!envelope.ciphertext.contains("searcher"));
+
+        assert_eq!(
+            key.open(&envelope, 6),
+            Err(EnvelopeError::WindowNotClosed { sealed_for: 7, closed: 6 })
+        );
+        assert_eq!(key.open(&envelope, 7).unwrap(), bundle);
+    }
+
+    #[test]
+    fn test_tampered_envelopes_are_refused() {
+        let key = EnvelopeKey::generate();
+        let envelope = BundleEnvelope::seal(&key.public_key(), 7, &wire_bundle(1000)).unwrap();
+
+        let mut moved = envelope.clone();
+        moved.window_id = 5;
+        assert_eq!(key.open(&moved, 7), Err(EnvelopeError::DecryptionFailed));
+
+        let mut future = envelope.clone();
+        future.version = 2;
+        assert_eq!(key.open(&future, 7), Err(EnvelopeError::UnsupportedVersion(2)));
+
+        let other = EnvelopeKey::generate();
+        assert!(matches!(other.open(&envelope, 7), Err(EnvelopeError::UnknownKey(_))));
+
+        // A low-order ephemeral key would make the shared secret all zeros
+        let mut low_order = envelope.clone();
+        low_order.ephemeral_key = BASE64.encode([0u8; 32]);
+        assert!(matches!(key.open(&low_order, 7), Err(EnvelopeError::Malformed(_))));
+        assert!(BundleEnvelope::seal(&[0u8; 32], 7, &wire_bundle(1000)).is_err());
+    }
+
+    #[test]
+    fn test_public_key_matches_rfc7748() {
+        let secret = hex::decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a").unwrap();
+        let key = EnvelopeKey::from_bytes(secret.try_into().unwrap());
+        assert_eq!(
+            hex::encode(key.public_key()),
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"
+        );
+    }
+
+    #[test]
+    fn test_keyring_opens_with_retired_keys_after_rotation() {
+        let first = EnvelopeKey::generate();
+        let keyring = EnvelopeKeyring::new(first.clone()).with_max_retired(1);
+        let early = BundleEnvelope::seal(&first.public_key(), 3, &wire_bundle(1)).unwrap();
+
+        let second = EnvelopeKey::generate();
+        keyring.rotate(second.clone());
+        assert_eq!(keyring.current().key_id, second.key_id());
+        assert!(keyring.open(&early, 3).is_ok());
+
+        keyring.rotate(EnvelopeKey::generate());
+        assert_eq!(keyring.key_ids().len(), 2);
+        assert!(matches!(keyring.open(&early, 3), Err(EnvelopeError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn test_key_round_trips_through_file() {
+        let key = EnvelopeKey::generate();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        key.save(file.path()).unwrap();
+        assert_eq!(EnvelopeKey::from_file(file.path()).unwrap().key_id(), key.key_id());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_saved_key_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("envelope.key");
+        EnvelopeKey::generate().save(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        EnvelopeKey::generate().save(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,298 @@
+use crate::ordered_block::now_millis;
+use crate::sink::SubmissionOutcome;
+use serde::Serialize;
+use std::sync::RwLock;
+
+/// Limits beyond which the engine stops reporting itself as ready or live
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthThresholds {
+    /// Closed windows allowed to be waiting for processing
+    pub max_window_lag: u64,
+    /// Undelivered blocks in a row before the validator sink counts as down
+    pub max_submission_failures: u32,
+    /// Liveness fails when no window has been processed for this long
+    pub stall_after_ms: u64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            max_window_lag: 5,
+            max_submission_failures: 3,
+            stall_after_ms: 10_000,
+        }
+    }
+}
+
+/// Latest result of checking one dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentCheck {
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub checked_at: u64,
+}
+
+impl ComponentCheck {
+    fn from_result(result: Result<(), String>, checked_at: u64) -> Self {
+        Self {
+            healthy: result.is_ok(),
+            detail: result.err(),
+            checked_at,
+        }
+    }
+}
+
+/// Body of `/healthz` and `/readyz`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// The auction loop is still making progress
+    pub live: bool,
+    /// Every dependency is reachable and the engine is keeping up with windows
+    pub ready: bool,
+    /// Why `ready` or `live` is false
+    pub problems: Vec<String>,
+    /// `None` until the first check
+    pub redis: Option<ComponentCheck>,
+    /// `None` when the engine runs without a simulator
+    pub simulator: Option<ComponentCheck>,
+    /// `None` until the first block is submitted
+    pub validator_sink: Option<ComponentCheck>,
+    pub consecutive_submission_failures: u32,
+    pub last_processed_window: Option<u64>,
+    /// Most recently closed window
+    pub current_window: u64,
+    pub window_lag: u64,
+    pub uptime_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    redis: Option<ComponentCheck>,
+    simulator: Option<ComponentCheck>,
+    validator_sink: Option<ComponentCheck>,
+    consecutive_submission_failures: u32,
+    last_processed_window: Option<u64>,
+    last_processed_at: Option<u64>,
+    first_processed_at: Option<u64>,
+    processed_windows: u64,
+}
+
+/// Health of the engine and its dependencies, updated by the auction loop and background probes
+pub struct EngineHealth {
+    window_duration_ms: u64,
+    thresholds: HealthThresholds,
+    started_at: u64,
+    /// Readiness waits on a Redis check; off when nothing the engine uses lives in Redis
+    redis_required: bool,
+    /// Windows held by a replay source, whose lag counts recorded windows rather than wall-clock ones
+    replay_windows: Option<u64>,
+    state: RwLock<HealthState>,
+}
+
+impl EngineHealth {
+    pub fn new(window_duration_ms: u64) -> Self {
+        Self {
+            window_duration_ms: window_duration_ms.max(1),
+            thresholds: HealthThresholds::default(),
+            started_at: now_millis(),
+            redis_required: true,
+            replay_windows: None,
+            state: RwLock::new(HealthState::default()),
+        }
+    }
+
+    pub fn with_thresholds(mut self, thresholds: HealthThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Report ready without a Redis check, for engines that use no Redis
+    pub fn without_redis(mut self) -> Self {
+        self.redis_required = false;
+        self
+    }
+
+    /// Measure lag against a replay of `windows` recorded windows, one due per window length
+    /// from the first processed window on
+    pub fn with_replay_windows(mut self, windows: u64) -> Self {
+        self.replay_windows = Some(windows);
+        self
+    }
+
+    pub fn record_redis(&self, result: Result<(), String>) {
+        self.state.write().unwrap().redis = Some(ComponentCheck::from_result(result, now_millis()));
+    }
+
+    pub fn record_simulator(&self, result: Result<(), String>) {
+        self.state.write().unwrap().simulator = Some(ComponentCheck::from_result(result, now_millis()));
+    }
+
+    /// A rejection still proves the validator is reachable; only undelivered blocks count against the sink
+    pub fn record_submission(&self, outcome: &SubmissionOutcome) {
+        let mut state = self.state.write().unwrap();
+        let result = match outcome {
+            SubmissionOutcome::Delivered(_) | SubmissionOutcome::Rejected { .. } => {
+                state.consecutive_submission_failures = 0;
+                Ok(())
+            }
+            SubmissionOutcome::Failed { reason } => {
+                state.consecutive_submission_failures += 1;
+                Err(reason.clone())
+            }
+        };
+        state.validator_sink = Some(ComponentCheck::from_result(result, now_millis()));
+    }
+
+    pub fn record_window(&self, window_id: u64) {
+        let mut state = self.state.write().unwrap();
+        let now = now_millis();
+        state.last_processed_window = Some(window_id);
+        state.last_processed_at = Some(now);
+        state.first_processed_at.get_or_insert(now);
+        state.processed_windows += 1;
+    }
+
+    pub fn report(&self) -> HealthReport {
+        self.report_at(now_millis())
+    }
+
+    pub fn report_at(&self, now: u64) -> HealthReport {
+        let state = self.state.read().unwrap();
+        let thresholds = &self.thresholds;
+        let mut problems = Vec::new();
+
+        let current_window = (now / self.window_duration_ms).saturating_sub(1);
+        let window_lag = match (state.first_processed_at, state.last_processed_window, self.replay_windows) {
+            (Some(first), _, Some(windows)) => {
+                let due = (now.saturating_sub(first) / self.window_duration_ms + 1).min(windows);
+                due.saturating_sub(state.processed_windows)
+            }
+            (_, Some(last), None) => current_window.saturating_sub(last),
+            _ => now.saturating_sub(self.started_at) / self.window_duration_ms,
+        };
+
+        let last_progress = state.last_processed_at.unwrap_or(self.started_at);
+        let live = now.saturating_sub(last_progress) <= thresholds.stall_after_ms;
+        if !live {
+            problems.push(format!("no window processed for {}ms", now.saturating_sub(last_progress)));
+        }
+
+        match &state.redis {
+            None if self.redis_required => problems.push("redis not checked yet".to_string()),
+            Some(check) if !check.healthy => problems.push("redis unreachable".to_string()),
+            _ => {}
+        }
+        if state.simulator.as_ref().is_some_and(|check| !check.healthy) {
+            problems.push("simulator unreachable".to_string());
+        }
+        if state.consecutive_submission_failures >= thresholds.max_submission_failures {
+            problems.push(format!(
+                "{} blocks in a row not delivered to validators",
+                state.consecutive_submission_failures
+            ));
+        }
+        if window_lag > thresholds.max_window_lag {
+            problems.push(format!("{} windows behind", window_lag));
+        }
+
+        HealthReport {
+            live,
+            ready: problems.is_empty(),
+            problems,
+            redis: state.redis.clone(),
+            simulator: state.simulator.clone(),
+            validator_sink: state.validator_sink.clone(),
+            consecutive_submission_failures: state.consecutive_submission_failures,
+            last_processed_window: state.last_processed_window,
+            current_window,
+            window_lag,
+            uptime_ms: now.saturating_sub(self.started_at),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::BlockAck;
+
+    fn failed() -> SubmissionOutcome {
+        SubmissionOutcome::Failed { reason: "timed out".to_string() }
+    }
+
+    #[test]
+    fn test_ready_when_dependencies_up_and_caught_up() {
+        let health = EngineHealth::new(200);
+        let now = now_millis();
+        health.record_redis(Ok(()));
+        health.record_simulator(Ok(()));
+        health.record_window(now / 200 - 1);
+        health.record_submission(&SubmissionOutcome::Delivered(BlockAck::accepted(1)));
+
+        let report = health.report_at(now);
+        assert!(report.live && report.ready, "{:?}", report.problems);
+        assert_eq!(report.window_lag, 0);
+    }
+
+    #[test]
+    fn test_not_ready_reports_each_problem() {
+        let health = EngineHealth::new(200).with_thresholds(HealthThresholds {
+            max_submission_failures: 2,
+            ..HealthThresholds::default()
+        });
+        let now = now_millis();
+        health.record_redis(Err("connection refused".to_string()));
+        health.record_simulator(Err("rpc timeout".to_string()));
+        health.record_window(now / 200 - 20);
+        health.record_submission(&failed());
+        health.record_submission(&failed());
+
+        let report = health.report_at(now);
+        assert!(report.live);
+        assert!(!report.ready);
+        assert_eq!(report.window_lag, 19);
+        assert_eq!(report.problems.len(), 4);
+        assert_eq!(report.redis.unwrap().detail.as_deref(), Some("connection refused"));
+
+        // A rejection means the validator answered, so the sink recovers
+        health.record_submission(&SubmissionOutcome::Rejected { reason: "bad parent".to_string() });
+        assert_eq!(health.report_at(now).consecutive_submission_failures, 0);
+    }
+
+    #[test]
+    fn test_redis_not_needed_without_redis_dependencies() {
+        let health = EngineHealth::new(200).without_redis();
+        let now = now_millis();
+        health.record_window(now / 200 - 1);
+
+        let report = health.report_at(now);
+        assert!(report.ready, "{:?}", report.problems);
+        assert!(report.redis.is_none());
+    }
+
+    #[test]
+    fn test_replay_lag_counts_recorded_windows() {
+        let health = EngineHealth::new(200).without_redis().with_replay_windows(10);
+        health.record_window(1);
+        health.record_window(2);
+        let now = now_millis();
+
+        // Two windows processed; six more were due since the first, however the live windows ran
+        assert_eq!(health.report_at(now + 1_500).window_lag, 6);
+        // Only the ten recorded windows can ever be due
+        assert_eq!(health.report_at(now + 60_000).window_lag, 8);
+    }
+
+    #[test]
+    fn test_stalled_loop_is_not_live() {
+        let health = EngineHealth::new(200);
+        health.record_redis(Ok(()));
+        health.record_window(1);
+
+        let report = health.report_at(now_millis() + 60_000);
+        assert!(!report.live);
+        assert!(!report.ready);
+    }
+}
@@ -2,11 +2,12 @@ use crate::{
     auction::BundleAuction,
     block_assembler::{BlockAssembler, MockValidatorClient},
     bundle::Bundle,
-    simulator::{MockSolanaRpcClient, TransactionSimulator},
+    simulator::{MockSolanaRpcClient, ProgramBehavior, TransactionSimulator},
     transaction_pool::{TransactionPool, PoolEvent},
 };
 use solana_sdk::{
     hash::Hash,
+    instruction::Instruction,
     signature::{Keypair, Signer},
     system_instruction,
     transaction::Transaction,
@@ -377,3 +378,65 @@ async fn test_high_volume_bundle_processing() {
     // Top winner should have the highest tip
     assert_eq!(winners[0].tip_lamports, NUM_BUNDLES as u64 * 100);
 }
+
+#[tokio::test]
+async fn test_auction_rejects_bundle_on_simulation_timeout() {
+    let mut mock_rpc = MockSolanaRpcClient::new();
+    mock_rpc.set_latency(Duration::from_millis(200));
+    let simulator = TransactionSimulator::new(Box::new(mock_rpc)).with_rpc_timeout(Duration::from_millis(50));
+    let mut auction = BundleAuction::new_with_simulator(1, simulator);
+
+    let started = Instant::now();
+    let error = auction.add_bundle(create_test_bundle(5000, 1)).await.unwrap_err();
+
+    assert!(error.to_string().contains("timed out"), "unexpected error: {}", error);
+    assert!(started.elapsed() < Duration::from_millis(200), "simulation was not cut short");
+    assert_eq!(auction.get_auction_stats().total_bundles, 0);
+}
+
+#[tokio::test]
+async fn test_partial_bundle_failure_rejects_whole_bundle() {
+    let mut mock_rpc = MockSolanaRpcClient::new();
+    let keypair = Keypair::new();
+    let bundle = create_test_bundle_with_keypair(8000, 3, &keypair);
+
+    // Only the last transaction of the bundle fails
+    let failing_sig = bundle.transactions[2].signatures[0];
+    mock_rpc.fail_when(move |tx| tx.signatures[0] == failing_sig, "slippage exceeded");
+    let simulator = TransactionSimulator::new(Box::new(mock_rpc));
+    let mut auction = BundleAuction::new_with_simulator(1, simulator);
+
+    assert!(auction.add_bundle(bundle).await.is_err());
+    auction.add_bundle(create_test_bundle(1000, 2)).await.expect("Healthy bundle should be added");
+
+    let winners = auction.select_winning_bundles(10);
+    assert_eq!(winners.len(), 1);
+    assert_eq!(winners[0].tip_lamports, 1000);
+}
+
+#[tokio::test]
+async fn test_compute_unit_limit_edge_cases() {
+    let heavy_program = Pubkey::new_unique();
+    let mut mock_rpc = MockSolanaRpcClient::new();
+    mock_rpc.set_compute_units(0);
+    mock_rpc.set_compute_unit_limit(1_400_000);
+    mock_rpc.set_program_behavior(heavy_program, ProgramBehavior::succeed(700_000));
+    let simulator = TransactionSimulator::new(Box::new(mock_rpc));
+
+    let payer = Keypair::new();
+    let heavy_tx = |instructions: usize| {
+        let ixs: Vec<Instruction> = (0..instructions)
+            .map(|i| Instruction::new_with_bytes(heavy_program, &[i as u8], vec![]))
+            .collect();
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+        tx.sign(&[&payer], Hash::new_unique());
+        tx
+    };
+
+    // Exactly at the limit succeeds, one instruction more exceeds it
+    let at_limit = Bundle::new(vec![heavy_tx(2)], 1000, payer.pubkey().to_string());
+    assert!(simulator.validate_bundle(&at_limit).await.is_ok());
+
+    let over_limit = Bundle::new(vec![heavy_tx(3)], 1000, payer.pubkey().to_string());
+    assert!(simulator.validate_bundle(&over_limit).await.is_err());
+}
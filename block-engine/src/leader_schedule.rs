@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+
+/// Consecutive slots each leader is scheduled for, as on Solana
+pub const DEFAULT_SLOTS_PER_LEADER: u64 = 4;
+
+/// A validator identity and the stake it is scheduled by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakedValidator {
+    pub identity: Pubkey,
+    pub stake: u64,
+}
+
+impl StakedValidator {
+    pub fn new(identity: Pubkey, stake: u64) -> Self {
+        Self { identity, stake }
+    }
+}
+
+/// Stake-weighted leader rotation. Every `slots_per_leader` slots a leader is drawn from a hash of
+/// the seed and the rotation index, so any party holding the same stakes and seed derives the same
+/// schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderSchedule {
+    /// Sorted by identity so the schedule does not depend on the order validators were listed in
+    validators: Vec<StakedValidator>,
+    total_stake: u64,
+    slots_per_leader: u64,
+    seed: u64,
+}
+
+impl LeaderSchedule {
+    /// Validators without stake are never scheduled
+    pub fn new(validators: impl IntoIterator<Item = StakedValidator>) -> Self {
+        let mut validators: Vec<StakedValidator> = validators.into_iter().filter(|v| v.stake > 0).collect();
+        validators.sort_by_key(|v| v.identity);
+        validators.dedup_by_key(|v| v.identity);
+        let total_stake = validators.iter().map(|v| v.stake).sum();
+        Self {
+            validators,
+            total_stake,
+            slots_per_leader: DEFAULT_SLOTS_PER_LEADER,
+            seed: 0,
+        }
+    }
+
+    pub fn with_slots_per_leader(mut self, slots_per_leader: u64) -> Self {
+        self.slots_per_leader = slots_per_leader.max(1);
+        self
+    }
+
+    /// Draw a different rotation, e.g. one per epoch
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn validators(&self) -> &[StakedValidator] {
+        &self.validators
+    }
+
+    pub fn total_stake(&self) -> u64 {
+        self.total_stake
+    }
+
+    pub fn slots_per_leader(&self) -> u64 {
+        self.slots_per_leader
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// Leader for `slot`, or `None` when nobody has stake
+    pub fn leader_for_slot(&self, slot: u64) -> Option<Pubkey> {
+        if self.total_stake == 0 {
+            return None;
+        }
+        let rotation = slot / self.slots_per_leader;
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed.to_le_bytes());
+        hasher.update(rotation.to_le_bytes());
+        let digest = hasher.finalize();
+        let draw = u64::from_le_bytes(digest[..8].try_into().unwrap()) % self.total_stake;
+
+        let mut cumulative = 0;
+        self.validators.iter().find_map(|v| {
+            cumulative += v.stake;
+            (draw < cumulative).then_some(v.identity)
+        })
+    }
+
+    /// Leader of the rotation after the one `slot` falls in
+    pub fn next_leader(&self, slot: u64) -> Option<Pubkey> {
+        let next_rotation_start = (slot / self.slots_per_leader + 1) * self.slots_per_leader;
+        self.leader_for_slot(next_rotation_start)
+    }
+
+    /// Who a block for `slot` should go to: the current leader, then the next one if different
+    pub fn target_leaders(&self, slot: u64) -> Vec<Pubkey> {
+        let mut targets: Vec<Pubkey> = self.leader_for_slot(slot).into_iter().collect();
+        if let Some(next) = self.next_leader(slot) {
+            if !targets.contains(&next) {
+                targets.push(next);
+            }
+        }
+        targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators(stakes: &[u64]) -> Vec<StakedValidator> {
+        stakes.iter().map(|&stake| StakedValidator::new(Pubkey::new_unique(), stake)).collect()
+    }
+
+    #[test]
+    fn test_schedule_is_deterministic_and_order_independent() {
+        let staked = validators(&[10, 20, 30]);
+        let schedule = LeaderSchedule::new(staked.clone());
+        let reversed = LeaderSchedule::new(staked.into_iter().rev());
+
+        for slot in 0..100 {
+            assert_eq!(schedule.leader_for_slot(slot), reversed.leader_for_slot(slot));
+        }
+        // Leaders hold for a full rotation
+        assert_eq!(schedule.leader_for_slot(8), schedule.leader_for_slot(11));
+
+        let reseeded = schedule.clone().with_seed(7);
+        assert!((0..100).any(|slot| reseeded.leader_for_slot(slot) != schedule.leader_for_slot(slot)));
+    }
+
+    #[test]
+    fn test_leader_share_follows_stake() {
+        let staked = validators(&[1, 9, 0]);
+        let schedule = LeaderSchedule::new(staked.clone()).with_slots_per_leader(1);
+        let heavy_slots = (0..2_000).filter(|&slot| schedule.leader_for_slot(slot) == Some(staked[1].identity)).count();
+
+        assert!((1_650..=1_950).contains(&heavy_slots), "heavy validator led {} of 2000 slots", heavy_slots);
+        assert!((0..2_000).all(|slot| schedule.leader_for_slot(slot) != Some(staked[2].identity)));
+        assert_eq!(LeaderSchedule::new(validators(&[0])).leader_for_slot(0), None);
+    }
+
+    #[test]
+    fn test_targets_current_and_next_leader() {
+        let schedule = LeaderSchedule::new(validators(&[1, 1, 1, 1]));
+        for slot in 0..40 {
+            let targets = schedule.target_leaders(slot);
+            assert_eq!(targets[0], schedule.leader_for_slot(slot).unwrap());
+            let next = schedule.leader_for_slot((slot / 4 + 1) * 4).unwrap();
+            assert_eq!(targets.len(), if next == targets[0] { 1 } else { 2 });
+            assert!(targets.contains(&next));
+        }
+    }
+}
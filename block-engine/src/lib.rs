@@ -4,10 +4,47 @@ pub mod simulator;
 pub mod transaction_pool;
 pub mod block_assembler;
 pub mod validator;
+pub mod rpc_recorder;
+pub mod ordered_block;
+pub mod sink;
+pub mod bundle_source;
+pub mod engine;
+pub mod scheduler;
+pub mod config;
+pub mod window_store;
+pub mod publisher;
+pub mod metrics;
+pub mod telemetry;
+pub mod redis_connection;
+pub mod health;
+pub mod envelope;
+pub mod attestation;
+pub mod leader_schedule;
+pub mod block_tree;
 
 // Re-export commonly used types
-pub use auction::{BundleAuction, AuctionStats, AuctionWindow, AuctionWindowStats, simulate_auction_window, simulate_auction_with_bundles};
-pub use bundle::{Bundle, BundleError, BundleEngine};
-pub use simulator::TransactionSimulator;
-pub use block_assembler::{Block, BlockSummary, BlockAssembler, assemble_block, assemble_block_with_params};
-pub use validator::{MockValidator, ValidatorNetwork, BlockSubmissionResult, ValidatorStats};
+pub use auction::{AuctionPolicy, PriorityFeePolicy, BundleAuction, AuctionStats, AuctionWindow, AuctionWindowStats, simulate_auction_window, simulate_auction_with_bundles};
+pub use bundle::{Bundle, BundleError, PostCondition};
+pub use engine::{BundleEngine, EngineConfig, WindowOutcome, ReorgOutcome, shutdown_signal};
+pub use scheduler::{WindowScheduler, SchedulerStats, ScheduledWindow, WindowTiming};
+pub use ordered_block::{OrderedBlock, WireBundle};
+pub use attestation::{BlockAttestation, AttestationError, block_digest, verify_block_attestation, load_engine_keypair};
+pub use sink::{BlockSink, BlockAck, SubmissionError, SubmissionOutcome, HttpBlockSink, RetryingBlockSink, RetryPolicy, FailedBlockStore, FanOutBlockSink, LeaderTargetedSink};
+pub use window_store::{WindowStore, WindowRecord, RankedBundle, RejectedBundle, FileWindowStore, RedisWindowStore, DEFAULT_WINDOW_LOG_MAX_LEN, WINDOW_LOG_KEY};
+pub use publisher::{OutcomePublisher, AuctionOutcomeEvent, WinningBundle, LosingBundle, BlockHashes, RedisOutcomePublisher, MemoryOutcomePublisher, OUTCOME_SCHEMA_VERSION, AUCTION_OUTCOME_CHANNEL, AUCTION_OUTCOME_STREAM};
+pub use metrics::{EngineMetrics, serve_http, probe_redis_rtt};
+pub use health::{EngineHealth, HealthReport, HealthThresholds, ComponentCheck};
+pub use telemetry::{LogFormat, TelemetryGuard, init_telemetry, otlp_tracer_provider};
+pub use redis_connection::{RedisConnection, ReconnectPolicy};
+pub use config::{BlockEngineConfig, BundlePolicy, BundleSourceKind, CliArgs, ConfigError, LeaderConfig, WindowStoreKind};
+pub use block_tree::{BlockTree, BlockTreeError, Reorg, TreeUpdate};
+pub use leader_schedule::{LeaderSchedule, StakedValidator, DEFAULT_SLOTS_PER_LEADER};
+pub use envelope::{BundleEnvelope, EnvelopeError, EnvelopeKey, EnvelopeKeyring, EnvelopePublicKey, publish_envelope_key, ENVELOPE_VERSION, ENVELOPE_KEY_KEY};
+pub use bundle_source::{BundleSource, parse_bundle_entry, parse_wire_bundle, RedisListSource, LateBundlePolicy, ListStore, MemoryListStore, RedisStreamSource, StreamStore, StreamEntry, RedisStreamStore, MemoryStreamStore, ChannelSource, JsonlReplaySource, InMemorySource};
+pub use simulator::{TransactionSimulator, SimulationCacheStats, SolanaRpcClient, MockSolanaRpcClient, HttpSolanaRpcClient, ProgramBehavior};
+pub use rpc_recorder::{RpcSession, RecordingRpcClient, ReplayRpcClient};
+pub use block_assembler::{Block, BlockSummary, BlockAssembler, assemble_block, assemble_block_at, assemble_block_with_params, validate_bundle_layout};
+pub use validator::{MockValidator, FaultModel, ValidatorNetwork, BlockSubmissionResult, ValidatorStats, QuorumThreshold, ValidatorResponse, ValidatorVote, ConsensusStatus, ConsensusOutcome, ForkOutcome};
+
+#[cfg(test)]
+mod integration_tests;
@@ -1,182 +1,224 @@
-use redis::AsyncCommands;
-use serde::{Deserialize, Serialize};
-use tokio::time::{sleep, Duration};
-use std::collections::HashMap;
-use sha2::{Digest, Sha256};
-use reqwest::Client;
-use tracing::{info, warn, debug, Level};
-use tracing_subscriber;
-
-// Import our auction modules
-mod auction;
-mod bundle;
-mod simulator;
-use auction::{simulate_auction_with_bundles};
-use bundle::Bundle as InternalBundle;
-
-// --- Bundle Data ---
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct Bundle {
-    id: String,
-    transactions: Vec<String>,
-    tip: u64,
-    searcher_pubkey: String,
-    timestamp: u64,
-}
-
-// --- Ordered Block ---
-#[derive(Debug, Serialize, Deserialize)]
-struct OrderedBlock {
-    window_id: u64,
-    ordered_bundles: Vec<Bundle>,
-    ordered_hash: String,
-}
+use block_engine::{
+    init_telemetry, load_engine_keypair, parse_wire_bundle, probe_redis_rtt, serve_http, EngineHealth, EngineMetrics,
+    BlockSink, BundleEngine, BundleSource, BundleSourceKind, ChannelSource, CliArgs, JsonlReplaySource, RedisStreamSource, FailedBlockStore, FanOutBlockSink, FileWindowStore, HttpBlockSink, LeaderTargetedSink,
+    publish_envelope_key, shutdown_signal, HttpSolanaRpcClient, RedisConnection, RedisListSource, RedisOutcomePublisher, RedisWindowStore, RetryPolicy, RetryingBlockSink,
+    TransactionSimulator, WindowStoreKind,
+};
+use clap::Parser;
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::watch;
+use tracing::info;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .init();
-
-    info!("🧠 Block Engine: Listening for bundles with 200ms auction windows...");
-
-    let client = Client::new();
-    let redis_client = redis::Client::open("redis://127.0.0.1/")?;
-    let mut con = redis_client.get_async_connection().await?;
-
-    loop {
-        let window_id = (chrono::Utc::now().timestamp_millis() / 200) as u64;
-        let key = format!("bundle_window:{}", window_id);
-
-        let bundles_json: Vec<String> = con.lrange(&key, 0, -1).await.unwrap_or_default();
-        if bundles_json.is_empty() {
-            sleep(Duration::from_millis(100)).await;
-            continue;
+    let config = CliArgs::parse().resolve()?;
+
+    // Initialize logging and, when configured, span export; the guard flushes spans on exit
+    let _telemetry = init_telemetry(config.tracing_level(), config.log_format, config.otlp_endpoint.as_deref())?;
+
+    info!(
+        "Block Engine: Listening for bundles with {}ms auction windows...",
+        config.window_duration_ms
+    );
+
+    // One signal stops the auction loop first, then the background tasks
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let stopped = |mut rx: watch::Receiver<bool>| async move {
+        let _ = rx.wait_for(|stop| *stop).await;
+    };
+
+    let keyring = config.envelope_keyring()?.map(Arc::new);
+    if let Some(keyring) = keyring.clone() {
+        let mut connection = RedisConnection::connect(&config.redis_url).await?;
+        if let Err(e) = publish_envelope_key(&mut connection, &keyring).await {
+            tracing::warn!("Could not publish envelope key: {}", e);
         }
 
-        // Parse Redis bundles
-        let mut redis_bundles: Vec<Bundle> = bundles_json
-            .iter()
-            .filter_map(|b| serde_json::from_str(b).ok())
-            .collect();
-
-        // Convert Redis bundles to our internal Bundle format for auction processing
-        let internal_bundles: Vec<InternalBundle> = redis_bundles
-            .iter()
-            .map(|b| InternalBundle::new(
-                vec![], // Empty transactions for now - would be parsed from b.transactions
-                b.tip,
-                b.searcher_pubkey.clone(),
-            ))
-            .collect();
-
-        info!(
-            "📦 Processing auction window {} with {} bundles from Redis",
-            window_id, internal_bundles.len()
-        );
-
-        // Run our sophisticated auction logic with 200ms window simulation
-        const MAX_BUNDLES_FOR_BLOCK: usize = 5;
-        match simulate_auction_with_bundles(window_id, internal_bundles, MAX_BUNDLES_FOR_BLOCK) {
-            Ok(winning_bundles) => {
-                // Convert winners back to Redis format for compatibility
-                let ordered_bundles: Vec<Bundle> = winning_bundles
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(idx, winner)| {
-                        if idx < redis_bundles.len() {
-                            // Find matching bundle by tip amount and searcher
-                            redis_bundles.iter().find(|rb| 
-                                rb.tip == winner.tip_lamports && 
-                                rb.searcher_pubkey == winner.searcher_pubkey
-                            ).cloned()
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                // Create deterministic ordered hash
-                let mut hasher = Sha256::new();
-                for b in &ordered_bundles {
-                    hasher.update(b.id.as_bytes());
-                    hasher.update(b.tip.to_le_bytes());
-                }
-                let ordered_hash = format!("{:x}", hasher.finalize());
-
-                let block = OrderedBlock {
-                    window_id,
-                    ordered_bundles: ordered_bundles.clone(),
-                    ordered_hash,
+        // SIGHUP re-reads envelope_key_path; a new key becomes current and the old one is retired
+        #[cfg(unix)]
+        {
+            let key_path = config.envelope_key_path.clone().unwrap_or_default();
+            tokio::spawn(async move {
+                let Ok(mut hangups) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) else {
+                    return;
                 };
-
-                info!(
-                    "✅ Built block for window {} with {} winning bundles → hash: {}",
-                    window_id,
-                    block.ordered_bundles.len(),
-                    &block.ordered_hash[..16]
-                );
-
-                // Log top bundles with more detail
-                for (i, bundle) in ordered_bundles.iter().take(3).enumerate() {
-                    info!(
-                        "🏆 Winner #{}: Bundle {} from {} with {} lamports tip",
-                        i + 1,
-                        bundle.id,
-                        bundle.searcher_pubkey,
-                        bundle.tip
-                    );
+                while hangups.recv().await.is_some() {
+                    match block_engine::EnvelopeKey::from_file(&key_path) {
+                        Ok(key) if key.key_id() != keyring.current().key_id => {
+                            keyring.rotate(key);
+                            if let Err(e) = publish_envelope_key(&mut connection, &keyring).await {
+                                tracing::warn!("Could not publish envelope key: {}", e);
+                            }
+                        }
+                        Ok(_) => info!("Envelope key unchanged"),
+                        Err(e) => tracing::warn!("Could not reload envelope key {}: {}", key_path.display(), e),
+                    }
                 }
+            });
+        }
+    }
 
-                // Optional: send to mock validator
-                let _ = client.post("http://localhost:4000/submit_block")
-                    .json(&block)
-                    .send()
-                    .await;
+    let mut replay_windows = None;
+    let source: Box<dyn BundleSource> = match config.source {
+        BundleSourceKind::List => {
+            let mut source = RedisListSource::connect(&config.redis_url).await?;
+            if let Some(keyring) = keyring {
+                source = source.with_keyring(keyring);
             }
-            Err(e) => {
-                warn!("Auction processing failed for window {}: {}", window_id, e);
-                
-                // Fallback to simple sorting as before
-                redis_bundles.sort_by_key(|b| (b.tip, hash_str(&b.id)));
-                let ordered_hash = create_simple_hash(&redis_bundles);
-                
-                let block = OrderedBlock {
-                    window_id,
-                    ordered_bundles: redis_bundles,
-                    ordered_hash,
-                };
-
-                info!(
-                    "⚠️ Fallback: Built block for window {} with {} bundles (simple sort)",
-                    window_id,
-                    block.ordered_bundles.len()
-                );
+            Box::new(source)
+        }
+        BundleSourceKind::Stream => {
+            let mut source = RedisStreamSource::connect(
+                &config.redis_url,
+                &config.bundle_stream_key,
+                &config.bundle_stream_group,
+                &config.bundle_stream_consumer,
+            )
+            .await?;
+            if let Some(keyring) = keyring {
+                source = source.with_keyring(keyring);
             }
+            Box::new(source)
+        }
+        BundleSourceKind::Jsonl => {
+            let path = config.replay_path.clone().unwrap_or_default();
+            let source = JsonlReplaySource::from_file(&path, config.window_duration_ms)?;
+            info!("Replaying {} recorded windows from {}", source.remaining_windows(), path.display());
+            replay_windows = Some(source.remaining_windows() as u64);
+            Box::new(source)
         }
+        BundleSourceKind::Channel => {
+            let (sender, source) = ChannelSource::channel(config.max_pool_size);
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(tokio::io::stdin()).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    match parse_wire_bundle(&line) {
+                        Ok(bundle) => {
+                            if sender.send(bundle).await.is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => tracing::warn!("Skipping unparseable bundle on stdin: {}", e),
+                    }
+                }
+            });
+            Box::new(source)
+        }
+    };
+    info!("Reading bundles from the {} source", source.name());
 
-        // Clean up Redis key after processing
-        let _: () = con.del(&key).await?;
-        sleep(Duration::from_millis(200)).await;
+    let metrics = Arc::new(EngineMetrics::new());
+    let mut health = EngineHealth::new(config.window_duration_ms);
+    if let Some(windows) = replay_windows {
+        health = health.with_replay_windows(windows);
+    }
+    if !config.uses_redis() {
+        health = health.without_redis();
+    }
+    let health = Arc::new(health);
+    let http_server = tokio::spawn(serve_http(
+        config.metrics_addr,
+        Arc::clone(&metrics),
+        Arc::clone(&health),
+        stopped(stop_rx.clone()),
+    ));
+    if config.uses_redis() {
+        let redis_url = config.redis_url.clone();
+        let probe_metrics = Arc::clone(&metrics);
+        let probe_health = Arc::clone(&health);
+        let probe_stop = stopped(stop_rx.clone());
+        tokio::spawn(async move {
+            let interval = Duration::from_secs(5);
+            if let Err(e) = probe_redis_rtt(&redis_url, probe_metrics, probe_health, interval, probe_stop).await {
+                tracing::warn!("Redis latency probe stopped: {}", e);
+            }
+        });
     }
-}
 
-// --- helper: hash a string deterministically ---
-fn hash_str(input: &str) -> u64 {
-    use std::hash::{Hasher, Hash};
-    use std::collections::hash_map::DefaultHasher;
-    let mut hasher = DefaultHasher::new();
-    input.hash(&mut hasher);
-    hasher.finish()
-}
+    let timeout = Duration::from_millis(config.submit_timeout_ms);
+    let mut validators: Vec<Box<dyn BlockSink>> = config
+        .validator_endpoints
+        .iter()
+        .map(|endpoint| Box::new(HttpBlockSink::new(endpoint.clone()).with_timeout(timeout)) as Box<dyn BlockSink>)
+        .collect();
+    let schedule = config.leader_schedule();
+    let delivery: Box<dyn BlockSink> = if let Some(schedule) = &schedule {
+        info!(
+            "Targeting scheduled leaders among {} staked validators ({} slots each)",
+            schedule.validators().len(),
+            schedule.slots_per_leader()
+        );
+        let mut sink = LeaderTargetedSink::new(schedule.clone());
+        for leader in &config.leaders {
+            let identity = leader
+                .identity
+                .parse()
+                .map_err(|e| anyhow::anyhow!("leader identity `{}`: {}", leader.identity, e))?;
+            sink = sink.with_leader(identity, Box::new(HttpBlockSink::new(leader.endpoint.clone()).with_timeout(timeout)));
+        }
+        Box::new(sink)
+    } else if validators.len() == 1 {
+        validators.remove(0)
+    } else {
+        Box::new(FanOutBlockSink::new(validators))
+    };
+    let retry = RetryPolicy {
+        max_attempts: config.submit_max_attempts,
+        ..RetryPolicy::default()
+    };
+    // Give up on a block after one window so a stalled validator cannot hold up the next ones
+    let sink = RetryingBlockSink::new(delivery, retry)
+        .with_deadline(Duration::from_millis(config.window_duration_ms))
+        .with_failed_block_store(FailedBlockStore::new(&config.failed_blocks_dir));
+
+    let keypair = match &config.engine_keypair_path {
+        Some(path) => load_engine_keypair(path)?,
+        None => {
+            tracing::warn!("No engine keypair configured, signing blocks with a throwaway identity");
+            Keypair::new()
+        }
+    };
+    info!("Engine identity {}", keypair.pubkey());
+
+    let mut engine = BundleEngine::new(config.engine_config(), Box::new(sink))
+        .with_keypair(keypair)
+        .with_policy(config.bundle_policy.build())
+        .with_source(source)
+        .with_metrics(metrics)
+        .with_health(health)
+        .with_pending_bundles_path(&config.pending_bundles_path);
+    if let Some(schedule) = schedule {
+        engine = engine.with_leader_schedule(schedule);
+    }
+    if let Some(rpc_url) = &config.simulator_rpc_url {
+        info!("Simulating bundles against {}", rpc_url);
+        let simulator = TransactionSimulator::new(Box::new(HttpSolanaRpcClient::new(rpc_url.clone())))
+            .with_rpc_timeout(Duration::from_millis(config.simulation_timeout_ms))
+            .with_cache_ttl(Duration::from_millis(config.simulation_cache_ttl_ms));
+        engine = engine.with_simulator(simulator);
+    } else {
+        tracing::warn!("No simulator_rpc_url configured, bundles are auctioned without simulation");
+    }
+    engine = match config.window_store {
+        WindowStoreKind::File => engine.with_window_store(Box::new(FileWindowStore::new(&config.window_log_path))),
+        WindowStoreKind::Redis => engine.with_window_store(Box::new(RedisWindowStore::connect(&config.redis_url).await?.with_max_len(config.window_log_max_len))),
+        WindowStoreKind::None => engine,
+    };
+    if config.publish_outcomes {
+        let publisher = RedisOutcomePublisher::connect(&config.redis_url)
+            .await?
+            .with_stream_max_len(config.outcome_stream_max_len);
+        engine = engine.with_publisher(Box::new(publisher));
+    }
 
-// --- helper: create simple hash for fallback ---
-fn create_simple_hash(bundles: &[Bundle]) -> String {
-    let mut hasher = Sha256::new();
-    for b in bundles {
-        hasher.update(b.id.as_bytes());
-        hasher.update(b.tip.to_le_bytes());
+    let result = engine.run(shutdown_signal()).await;
+    let _ = stop_tx.send(true);
+    if let Ok(Err(e)) = http_server.await {
+        tracing::warn!("HTTP server stopped with error: {}", e);
     }
-    format!("{:x}", hasher.finalize())
+    info!("Block Engine stopped");
+    result
 }
@@ -0,0 +1,411 @@
+use crate::auction::AuctionWindowStats;
+use crate::health::{EngineHealth, HealthReport};
+use crate::scheduler::SchedulerStats;
+use crate::simulator::SimulationCacheStats;
+use crate::sink::SubmissionOutcome;
+use crate::transaction_pool::PoolStats;
+use crate::validator::ValidatorStats;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use crate::redis_connection::RedisConnection;
+use tracing::{info, warn};
+use anyhow::Result;
+
+/// Buckets from 1ms to ~4s, covering a 200ms window with room for slow ones
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 4.0];
+
+/// Prometheus metrics for the auction loop, all registered under the `block_engine_` prefix
+pub struct EngineMetrics {
+    registry: Registry,
+    pub bundles_received: IntCounter,
+    /// Labelled by the `BundleError`/`PoolError` variant, e.g. `pool_full`
+    pub bundles_rejected: IntCounterVec,
+    /// Labelled `ok` or `failed`
+    pub bundles_simulated: IntCounterVec,
+    pub bundles_won: IntCounter,
+    pub bundles_lost: IntCounter,
+    /// Bundles from orphaned blocks, labelled `requeued` or `dropped`
+    pub bundles_orphaned: IntCounterVec,
+    pub window_processing_seconds: Histogram,
+    pub window_bundles: Histogram,
+    pub window_tip_lamports: IntCounter,
+    pub window_highest_tip_lamports: IntGauge,
+    /// Labelled by operation, e.g. `ping` or `take_window`
+    pub redis_round_trip_seconds: HistogramVec,
+    /// Labelled `delivered`, `rejected` or `failed`
+    pub block_submissions: IntCounterVec,
+    pub pool_bundles: IntGauge,
+    pub pool_pending_bundles: IntGauge,
+    pub pool_tip_value_lamports: IntGauge,
+    /// Labelled by validator and `accepted`/`rejected`
+    pub validator_blocks: IntGaugeVec,
+    /// Windows processed so far, labelled `on_time`, `late` or `skipped`
+    pub scheduler_windows: IntGaugeVec,
+    /// Simulation cache lookups so far, labelled `hit` or `miss`
+    pub simulation_cache_lookups: IntGaugeVec,
+}
+
+impl EngineMetrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("block_engine".to_string()), None)
+            .expect("static registry prefix is valid");
+
+        let bundles_received = IntCounter::new("bundles_received_total", "Bundles received from sources or submit_bundle").unwrap();
+        let bundles_rejected = IntCounterVec::new(
+            Opts::new("bundles_rejected_total", "Bundles rejected before the auction, by error variant"),
+            &["reason"],
+        )
+        .unwrap();
+        let bundles_simulated = IntCounterVec::new(
+            Opts::new("bundles_simulated_total", "Bundles run through the simulator, by result"),
+            &["result"],
+        )
+        .unwrap();
+        let bundles_won = IntCounter::new("bundles_won_total", "Bundles included in a block").unwrap();
+        let bundles_lost = IntCounter::new("bundles_lost_total", "Simulated bundles that were outbid").unwrap();
+        let bundles_orphaned = IntCounterVec::new(
+            Opts::new("bundles_orphaned_total", "Bundles whose block was orphaned by a reorg, by action"),
+            &["action"],
+        )
+        .unwrap();
+        let window_processing_seconds = Histogram::with_opts(
+            HistogramOpts::new("window_processing_seconds", "Time to process one auction window")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let window_bundles = Histogram::with_opts(
+            HistogramOpts::new("window_bundles", "Candidate bundles per auction window")
+                .buckets(vec![0.0, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0]),
+        )
+        .unwrap();
+        let window_tip_lamports = IntCounter::new("window_tip_lamports_total", "Tips offered by auction candidates").unwrap();
+        let window_highest_tip_lamports =
+            IntGauge::new("window_highest_tip_lamports", "Highest tip in the last non-empty window").unwrap();
+        let redis_round_trip_seconds = HistogramVec::new(
+            HistogramOpts::new("redis_round_trip_seconds", "Redis round-trip time by operation")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["operation"],
+        )
+        .unwrap();
+        let block_submissions = IntCounterVec::new(
+            Opts::new("block_submissions_total", "Block submissions by final outcome"),
+            &["outcome"],
+        )
+        .unwrap();
+        let pool_bundles = IntGauge::new("pool_bundles", "Bundles held in the transaction pool").unwrap();
+        let pool_pending_bundles = IntGauge::new("pool_pending_bundles", "Bundles waiting for the next window").unwrap();
+        let pool_tip_value_lamports = IntGauge::new("pool_tip_value_lamports", "Sum of tips held in the pool").unwrap();
+        let validator_blocks = IntGaugeVec::new(
+            Opts::new("validator_blocks", "Blocks processed per validator, by result"),
+            &["validator", "result"],
+        )
+        .unwrap();
+
+        let scheduler_windows = IntGaugeVec::new(
+            Opts::new("scheduler_windows", "Auction windows handled by the scheduler, by timing"),
+            &["timing"],
+        )
+        .unwrap();
+        let simulation_cache_lookups = IntGaugeVec::new(
+            Opts::new("simulation_cache_lookups", "Simulation cache lookups, by result"),
+            &["result"],
+        )
+        .unwrap();
+
+        let metrics = Self {
+            registry,
+            bundles_received,
+            bundles_rejected,
+            bundles_simulated,
+            bundles_won,
+            bundles_lost,
+            bundles_orphaned,
+            window_processing_seconds,
+            window_bundles,
+            window_tip_lamports,
+            window_highest_tip_lamports,
+            redis_round_trip_seconds,
+            block_submissions,
+            pool_bundles,
+            pool_pending_bundles,
+            pool_tip_value_lamports,
+            validator_blocks,
+            scheduler_windows,
+            simulation_cache_lookups,
+        };
+        metrics.register_all();
+        metrics
+    }
+
+    fn register_all(&self) {
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(self.bundles_received.clone()),
+            Box::new(self.bundles_rejected.clone()),
+            Box::new(self.bundles_simulated.clone()),
+            Box::new(self.bundles_won.clone()),
+            Box::new(self.bundles_lost.clone()),
+            Box::new(self.bundles_orphaned.clone()),
+            Box::new(self.window_processing_seconds.clone()),
+            Box::new(self.window_bundles.clone()),
+            Box::new(self.window_tip_lamports.clone()),
+            Box::new(self.window_highest_tip_lamports.clone()),
+            Box::new(self.redis_round_trip_seconds.clone()),
+            Box::new(self.block_submissions.clone()),
+            Box::new(self.pool_bundles.clone()),
+            Box::new(self.pool_pending_bundles.clone()),
+            Box::new(self.pool_tip_value_lamports.clone()),
+            Box::new(self.validator_blocks.clone()),
+            Box::new(self.scheduler_windows.clone()),
+            Box::new(self.simulation_cache_lookups.clone()),
+        ];
+        for collector in collectors {
+            self.registry.register(collector).expect("metric names are unique");
+        }
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn record_rejection(&self, reason: &str) {
+        self.bundles_rejected.with_label_values(&[reason]).inc();
+    }
+
+    pub fn observe_window(&self, stats: &AuctionWindowStats, elapsed: Duration) {
+        self.window_processing_seconds.observe(elapsed.as_secs_f64());
+        self.window_bundles.observe(stats.total_bundles as f64);
+        self.window_tip_lamports.inc_by(stats.total_tip_value);
+        if stats.total_bundles > 0 {
+            self.window_highest_tip_lamports.set(stats.highest_tip as i64);
+        }
+    }
+
+    pub fn observe_submission(&self, outcome: &SubmissionOutcome) {
+        let label = match outcome {
+            SubmissionOutcome::Delivered(_) => "delivered",
+            SubmissionOutcome::Rejected { .. } => "rejected",
+            SubmissionOutcome::Failed { .. } => "failed",
+        };
+        self.block_submissions.with_label_values(&[label]).inc();
+    }
+
+    pub fn observe_pool(&self, stats: &PoolStats) {
+        self.pool_bundles.set(stats.total_bundles as i64);
+        self.pool_pending_bundles.set(stats.pending_count as i64);
+        self.pool_tip_value_lamports.set(stats.total_tip_value as i64);
+    }
+
+    pub fn observe_validator(&self, stats: &ValidatorStats) {
+        self.validator_blocks
+            .with_label_values(&[&stats.validator_id, "accepted"])
+            .set(stats.blocks_accepted as i64);
+        self.validator_blocks
+            .with_label_values(&[&stats.validator_id, "rejected"])
+            .set(stats.blocks_rejected as i64);
+    }
+
+    pub fn observe_scheduler(&self, stats: &SchedulerStats) {
+        self.scheduler_windows.with_label_values(&["on_time"]).set(stats.on_time_windows as i64);
+        self.scheduler_windows.with_label_values(&["late"]).set(stats.late_windows as i64);
+        self.scheduler_windows.with_label_values(&["skipped"]).set(stats.skipped_windows as i64);
+    }
+
+    pub fn observe_simulation_cache(&self, stats: &SimulationCacheStats) {
+        self.simulation_cache_lookups.with_label_values(&["hit"]).set(stats.hits as i64);
+        self.simulation_cache_lookups.with_label_values(&["miss"]).set(stats.misses as i64);
+    }
+
+    pub fn observe_redis_rtt(&self, operation: &str, elapsed: Duration) {
+        self.redis_round_trip_seconds
+            .with_label_values(&[operation])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Current values in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for EngineMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// PING Redis every `interval`, recording the round trip and Redis health, until `shutdown` resolves
+pub async fn probe_redis_rtt<F: Future<Output = ()>>(
+    redis_url: &str,
+    metrics: Arc<EngineMetrics>,
+    health: Arc<EngineHealth>,
+    interval: Duration,
+    shutdown: F,
+) -> Result<()> {
+    let mut redis = RedisConnection::open(redis_url)?;
+    tokio::pin!(shutdown);
+
+    loop {
+        let started = Instant::now();
+        let pinged = match redis.get().await {
+            Ok(mut connection) => {
+                let result = redis::cmd("PING").query_async::<_, String>(&mut connection).await;
+                redis.check(result)
+            }
+            Err(e) => Err(e),
+        };
+        match pinged {
+            Ok(_) => {
+                metrics.observe_redis_rtt("ping", started.elapsed());
+                health.record_redis(Ok(()));
+            }
+            Err(e) => {
+                warn!("Redis PING failed: {}", e);
+                health.record_redis(Err(e.to_string()));
+            }
+        }
+
+        tokio::select! {
+            _ = &mut shutdown => return Ok(()),
+            _ = sleep(interval) => {}
+        }
+    }
+}
+
+/// JSON health report, 200 when `healthy` and 503 otherwise
+fn health_response(report: &HealthReport, healthy: bool) -> hyper::http::Result<Response<Body>> {
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_vec(report).unwrap_or_default()))
+}
+
+async fn handle(
+    metrics: Arc<EngineMetrics>,
+    health: Arc<EngineHealth>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("content-type", TextEncoder::new().format_type())
+            .body(Body::from(metrics.render())),
+        (&Method::GET, "/healthz") => {
+            let report = health.report();
+            health_response(&report, report.live)
+        }
+        (&Method::GET, "/readyz") => {
+            let report = health.report();
+            health_response(&report, report.ready)
+        }
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+/// Serve `GET /metrics`, `/healthz` (liveness) and `/readyz` (readiness) on `addr` until `shutdown` resolves
+pub async fn serve_http<F: Future<Output = ()>>(
+    addr: SocketAddr,
+    metrics: Arc<EngineMetrics>,
+    health: Arc<EngineHealth>,
+    shutdown: F,
+) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let metrics = Arc::clone(&metrics);
+        let health = Arc::clone(&health);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle(Arc::clone(&metrics), Arc::clone(&health), request)
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    info!("Serving metrics and health checks on http://{}", server.local_addr());
+    server.with_graceful_shutdown(shutdown).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ordered_block::now_millis;
+    use crate::sink::BlockAck;
+
+    #[test]
+    fn test_render_includes_labelled_series() {
+        let metrics = EngineMetrics::new();
+        metrics.bundles_received.inc_by(3);
+        metrics.record_rejection("pool_full");
+        metrics.record_rejection("empty_bundle");
+        metrics.observe_submission(&SubmissionOutcome::Delivered(BlockAck::accepted(1)));
+        metrics.observe_submission(&SubmissionOutcome::Failed { reason: "timeout".to_string() });
+        metrics.observe_redis_rtt("ping", Duration::from_millis(2));
+        metrics.observe_pool(&PoolStats {
+            total_bundles: 4,
+            pending_count: 2,
+            total_tip_value: 9000,
+            avg_tip: 2250,
+        });
+
+        let text = metrics.render();
+        assert!(text.contains("block_engine_bundles_received_total 3"));
+        assert!(text.contains("block_engine_bundles_rejected_total{reason=\"pool_full\"} 1"));
+        assert!(text.contains("block_engine_block_submissions_total{outcome=\"failed\"} 1"));
+        assert!(text.contains("block_engine_redis_round_trip_seconds_count{operation=\"ping\"} 1"));
+        assert!(text.contains("block_engine_pool_pending_bundles 2"));
+    }
+
+    #[tokio::test]
+    async fn test_http_serves_metrics_and_health() {
+        let metrics = Arc::new(EngineMetrics::new());
+        metrics.bundles_won.inc();
+        let health = Arc::new(EngineHealth::new(200));
+
+        // Bind to an ephemeral port first so the test knows where to connect
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve_http(addr, Arc::clone(&metrics), Arc::clone(&health), async {
+            let _ = stopped.await;
+        }));
+        sleep(Duration::from_millis(50)).await;
+
+        let body = reqwest::get(format!("http://{}/metrics", addr)).await.unwrap().text().await.unwrap();
+        assert!(body.contains("block_engine_bundles_won_total 1"));
+
+        // Live from startup, but not ready until Redis has been checked
+        let live = reqwest::get(format!("http://{}/healthz", addr)).await.unwrap();
+        assert_eq!(live.status(), reqwest::StatusCode::OK);
+        let not_ready = reqwest::get(format!("http://{}/readyz", addr)).await.unwrap();
+        assert_eq!(not_ready.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let report: serde_json::Value = not_ready.json().await.unwrap();
+        assert_eq!(report["problems"][0], "redis not checked yet");
+
+        health.record_redis(Ok(()));
+        health.record_window(now_millis() / 200 - 1);
+        let ready = reqwest::get(format!("http://{}/readyz", addr)).await.unwrap();
+        assert_eq!(ready.status(), reqwest::StatusCode::OK);
+
+        let missing = reqwest::get(format!("http://{}/other", addr)).await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+}
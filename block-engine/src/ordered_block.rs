@@ -0,0 +1,217 @@
+use crate::attestation::BlockAttestation;
+use crate::block_assembler::Block;
+use crate::bundle::{Bundle, PostCondition};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::signature::Keypair;
+use solana_sdk::transaction::Transaction;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+/// Bundle as submitted through the API server, with base64-encoded bincode transactions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireBundle {
+    pub id: String,
+    pub transactions: Vec<String>,
+    pub tip: u64,
+    pub searcher_pubkey: String,
+    pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_conditions: Vec<PostCondition>,
+}
+
+impl WireBundle {
+    pub fn from_bundle(bundle: &Bundle) -> Result<Self> {
+        let transactions = bundle
+            .transactions
+            .iter()
+            .map(|tx| Ok(BASE64.encode(bincode::serialize(tx)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let timestamp = bundle
+            .created_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        Ok(Self {
+            id: bundle.id.to_string(),
+            transactions,
+            tip: bundle.tip_lamports,
+            searcher_pubkey: bundle.searcher_pubkey.clone(),
+            timestamp,
+            post_conditions: bundle.post_conditions.clone(),
+        })
+    }
+
+    /// Decode into an engine bundle; fails if any transaction is not valid base64 bincode
+    pub fn to_bundle(&self) -> Result<Bundle> {
+        let transactions = self
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(i, encoded)| {
+                let bytes = BASE64
+                    .decode(encoded)
+                    .map_err(|e| anyhow!("transaction {} is not valid base64: {}", i, e))?;
+                bincode::deserialize::<Transaction>(&bytes)
+                    .map_err(|e| anyhow!("transaction {} is not a valid transaction: {}", i, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut bundle = Bundle::new(transactions, self.tip, self.searcher_pubkey.clone())
+            .with_post_conditions(self.post_conditions.clone());
+        bundle.id = bundle_id_from_wire(&self.id);
+        bundle.created_at = UNIX_EPOCH + Duration::from_millis(self.timestamp);
+        Ok(bundle)
+    }
+}
+
+/// API server IDs are short hex strings; map them onto stable UUIDs
+fn bundle_id_from_wire(id: &str) -> Uuid {
+    Uuid::parse_str(id).unwrap_or_else(|_| {
+        let digest = Sha256::digest(id.as_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        Uuid::from_bytes(bytes)
+    })
+}
+
+/// Block as posted to validators: the winning bundles in auction order plus the assembled block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderedBlock {
+    pub window_id: u64,
+    pub ordered_bundles: Vec<WireBundle>,
+    pub ordered_hash: String,
+    pub block: Block,
+    /// IDs of every bundle that entered the window's auction, winners included
+    #[serde(default)]
+    pub window_inputs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<BlockAttestation>,
+}
+
+impl OrderedBlock {
+    pub fn new(window_id: u64, block: Block) -> Result<Self> {
+        let ordered_bundles = block
+            .bundles
+            .iter()
+            .map(WireBundle::from_bundle)
+            .collect::<Result<Vec<_>>>()?;
+        let ordered_hash = compute_ordered_hash(&ordered_bundles);
+
+        Ok(Self {
+            window_id,
+            ordered_bundles,
+            ordered_hash,
+            block,
+            window_inputs: Vec::new(),
+            attestation: None,
+        })
+    }
+
+    /// Record the window's inputs; call before signing, as the attestation covers them
+    pub fn with_window_inputs(mut self, window_inputs: Vec<String>) -> Self {
+        self.window_inputs = window_inputs;
+        self.attestation = None;
+        self
+    }
+
+    /// Attach the engine's signature over the block digest
+    pub fn signed(mut self, keypair: &Keypair) -> Self {
+        self.attestation = Some(BlockAttestation::sign(keypair, &self));
+        self
+    }
+
+    /// Recompute the ordering hash and compare it with the advertised one
+    pub fn verify_ordered_hash(&self) -> bool {
+        compute_ordered_hash(&self.ordered_bundles) == self.ordered_hash
+    }
+}
+
+/// Deterministic hash over bundle IDs and tips in block order
+pub fn compute_ordered_hash(bundles: &[WireBundle]) -> String {
+    let mut hasher = Sha256::new();
+    for b in bundles {
+        hasher.update(b.id.as_bytes());
+        hasher.update(b.tip.to_le_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Milliseconds since the Unix epoch
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    #[test]
+    fn test_wire_bundle_round_trip() {
+        let keypair = Keypair::new();
+        let mut tx = Transaction::new_with_payer(
+            &[system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 100)],
+            Some(&keypair.pubkey()),
+        );
+        tx.sign(&[&keypair], Hash::new_unique());
+        let bundle = Bundle::new(vec![tx], 5000, keypair.pubkey().to_string())
+            .with_post_conditions(vec![PostCondition::MaxBalanceDecrease { account: keypair.pubkey(), lamports: 5100 }]);
+
+        let wire = WireBundle::from_bundle(&bundle).unwrap();
+        let decoded = wire.to_bundle().unwrap();
+
+        assert_eq!(decoded.id, bundle.id);
+        assert_eq!(decoded.transactions, bundle.transactions);
+        assert_eq!(decoded.tip_lamports, 5000);
+        assert_eq!(decoded.post_conditions, bundle.post_conditions);
+    }
+
+    #[test]
+    fn test_wire_bundle_rejects_garbage_transactions() {
+        let wire = WireBundle {
+            id: "a1b2c3d4e5f60718".to_string(),
+            transactions: vec!["not a transaction".to_string()],
+            tip: 1000,
+            searcher_pubkey: "searcher".to_string(),
+            timestamp: 0,
+            post_conditions: Vec::new(),
+        };
+        assert!(wire.to_bundle().is_err());
+    }
+
+    #[test]
+    fn test_post_conditions_parse_from_api_json() {
+        let tip_account = Pubkey::new_unique();
+        let json = format!(
+            r#"{{"id":"a1b2","transactions":[],"tip":1000,"searcher_pubkey":"s","timestamp":0,
+                "post_conditions":[{{"type":"tip_paid","tip_account":"{}"}}]}}"#,
+            tip_account
+        );
+        let wire: WireBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(wire.post_conditions, vec![PostCondition::TipPaid { tip_account }]);
+        assert_eq!(wire.to_bundle().unwrap().post_conditions, wire.post_conditions);
+
+        // Bundles without post-conditions keep their existing wire form
+        let plain: WireBundle = serde_json::from_str(r#"{"id":"a","transactions":[],"tip":1,"searcher_pubkey":"s","timestamp":0}"#).unwrap();
+        assert!(!serde_json::to_string(&plain).unwrap().contains("post_conditions"));
+    }
+
+    #[test]
+    fn test_hex_ids_map_to_stable_uuids() {
+        assert_eq!(bundle_id_from_wire("a1b2c3d4e5f60718"), bundle_id_from_wire("a1b2c3d4e5f60718"));
+        assert_ne!(bundle_id_from_wire("a1b2c3d4e5f60718"), bundle_id_from_wire("a1b2c3d4e5f60719"));
+    }
+}
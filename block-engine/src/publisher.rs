@@ -0,0 +1,213 @@
+use crate::auction::AuctionWindowStats;
+use crate::redis_connection::RedisConnection;
+use crate::sink::SubmissionOutcome;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+
+/// Bumped whenever a field of `AuctionOutcomeEvent` changes meaning or is removed
+pub const OUTCOME_SCHEMA_VERSION: u32 = 1;
+/// Pub/Sub channel carrying one JSON `AuctionOutcomeEvent` per window
+pub const AUCTION_OUTCOME_CHANNEL: &str = "engine:auction_outcomes";
+/// Capped stream holding recent events under the `event` field, for consumers that start late
+pub const AUCTION_OUTCOME_STREAM: &str = "engine:auction_outcomes:stream";
+pub const DEFAULT_STREAM_MAX_LEN: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WinningBundle {
+    pub bundle_id: String,
+    pub searcher_pubkey: String,
+    pub tip: u64,
+    /// Position in the block, starting at 0
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LosingBundle {
+    pub bundle_id: String,
+    pub searcher_pubkey: Option<String>,
+    pub tip: Option<u64>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHashes {
+    pub slot: u64,
+    pub blockhash: String,
+    pub parent_hash: String,
+    pub ordered_hash: String,
+}
+
+/// What the engine decided for one window, as published to the API server and dashboard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuctionOutcomeEvent {
+    pub schema_version: u32,
+    pub window_id: u64,
+    /// Milliseconds since the Unix epoch
+    pub published_at: u64,
+    pub stats: AuctionWindowStats,
+    pub winners: Vec<WinningBundle>,
+    pub losers: Vec<LosingBundle>,
+    pub block: Option<BlockHashes>,
+    pub submission: Option<SubmissionOutcome>,
+}
+
+impl AuctionOutcomeEvent {
+    /// Parse an event, refusing schema versions newer than this build understands
+    pub fn from_json(json: &str) -> Result<Self> {
+        let event: Self = serde_json::from_str(json)?;
+        if event.schema_version > OUTCOME_SCHEMA_VERSION {
+            anyhow::bail!(
+                "unsupported auction outcome schema version {} (max {})",
+                event.schema_version,
+                OUTCOME_SCHEMA_VERSION
+            );
+        }
+        Ok(event)
+    }
+}
+
+/// Destination for per-window auction outcomes
+#[async_trait]
+pub trait OutcomePublisher: Send + Sync {
+    async fn publish(&self, event: &AuctionOutcomeEvent) -> Result<()>;
+}
+
+/// Publishes to `engine:auction_outcomes` and appends to a capped `engine:auction_outcomes:stream`
+pub struct RedisOutcomePublisher {
+    connection: tokio::sync::Mutex<RedisConnection>,
+    stream_max_len: usize,
+}
+
+impl RedisOutcomePublisher {
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        Ok(Self {
+            connection: tokio::sync::Mutex::new(RedisConnection::connect(redis_url).await?),
+            stream_max_len: DEFAULT_STREAM_MAX_LEN,
+        })
+    }
+
+    pub fn with_stream_max_len(mut self, max_len: usize) -> Self {
+        self.stream_max_len = max_len;
+        self
+    }
+}
+
+/// PUBLISH plus an approximately trimmed XADD, sent as one round trip
+fn outcome_pipeline(json: &str, stream_max_len: usize) -> redis::Pipeline {
+    let mut pipe = redis::pipe();
+    pipe.cmd("PUBLISH")
+        .arg(AUCTION_OUTCOME_CHANNEL)
+        .arg(json)
+        .ignore()
+        .cmd("XADD")
+        .arg(AUCTION_OUTCOME_STREAM)
+        .arg("MAXLEN")
+        .arg("~")
+        .arg(stream_max_len)
+        .arg("*")
+        .arg("event")
+        .arg(json)
+        .ignore();
+    pipe
+}
+
+#[async_trait]
+impl OutcomePublisher for RedisOutcomePublisher {
+    async fn publish(&self, event: &AuctionOutcomeEvent) -> Result<()> {
+        let json = serde_json::to_string(event)?;
+        let mut redis = self.connection.lock().await;
+        let mut connection = redis.get().await?;
+        let result = outcome_pipeline(&json, self.stream_max_len)
+            .query_async::<_, ()>(&mut connection)
+            .await;
+        redis.check(result)
+    }
+}
+
+/// Keeps published events in memory; clones share the same list
+#[derive(Debug, Clone, Default)]
+pub struct MemoryOutcomePublisher {
+    events: Arc<Mutex<Vec<AuctionOutcomeEvent>>>,
+}
+
+impl MemoryOutcomePublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<AuctionOutcomeEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl OutcomePublisher for MemoryOutcomePublisher {
+    async fn publish(&self, event: &AuctionOutcomeEvent) -> Result<()> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(schema_version: u32) -> AuctionOutcomeEvent {
+        AuctionOutcomeEvent {
+            schema_version,
+            window_id: 8,
+            published_at: 1_700_000_000_000,
+            stats: AuctionWindowStats {
+                window_id: 8,
+                total_bundles: 2,
+                total_tip_value: 3000,
+                highest_tip: 2000,
+                lowest_tip: 1000,
+                avg_tip: 1500,
+                duration_ms: 200,
+                elapsed_ms: 0,
+            },
+            winners: vec![WinningBundle {
+                bundle_id: "winner".to_string(),
+                searcher_pubkey: "searcher_a".to_string(),
+                tip: 2000,
+                position: 0,
+            }],
+            losers: vec![LosingBundle {
+                bundle_id: "loser".to_string(),
+                searcher_pubkey: Some("searcher_b".to_string()),
+                tip: Some(1000),
+                reason: "outbid".to_string(),
+            }],
+            block: None,
+            submission: None,
+        }
+    }
+
+    #[test]
+    fn test_event_round_trips_with_version() {
+        let json = serde_json::to_string(&event(OUTCOME_SCHEMA_VERSION)).unwrap();
+        assert!(json.contains("\"schema_version\":1"));
+        assert_eq!(AuctionOutcomeEvent::from_json(&json).unwrap(), event(OUTCOME_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_newer_schema_version_rejected() {
+        let json = serde_json::to_string(&event(OUTCOME_SCHEMA_VERSION + 1)).unwrap();
+        assert!(AuctionOutcomeEvent::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_pipeline_publishes_and_caps_stream() {
+        let packed = outcome_pipeline("{}", 500).get_packed_pipeline();
+        let packed = String::from_utf8(packed).unwrap();
+
+        assert!(packed.contains("PUBLISH"));
+        assert!(packed.contains(AUCTION_OUTCOME_CHANNEL));
+        assert!(packed.contains("XADD"));
+        assert!(packed.contains(AUCTION_OUTCOME_STREAM));
+        assert!(packed.contains("MAXLEN\r\n$1\r\n~\r\n$3\r\n500"));
+    }
+}
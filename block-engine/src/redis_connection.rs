@@ -0,0 +1,228 @@
+use redis::aio::MultiplexedConnection;
+use redis::{RedisError, RedisResult};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use anyhow::{anyhow, Result};
+
+/// How often to retry after losing the Redis connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Give up on a single connection attempt after this long
+    pub connect_timeout: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            connect_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Wait before the next attempt after `failures` consecutive failed ones (starting at 1)
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Redis connection that is re-established on demand after it drops.
+///
+/// Callers fetch a handle with `get` and pass each command's result through `check`. A connection
+/// error drops the handle, and the next `get` reconnects. While Redis is down, `get` fails fast
+/// until the backoff has elapsed, so an outage never blocks the auction loop.
+pub struct RedisConnection {
+    client: redis::Client,
+    connection: Option<MultiplexedConnection>,
+    policy: ReconnectPolicy,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl RedisConnection {
+    /// Validate the URL without connecting; the first `get` connects
+    pub fn open(redis_url: &str) -> Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(redis_url)?,
+            connection: None,
+            policy: ReconnectPolicy::default(),
+            failures: 0,
+            retry_at: None,
+        })
+    }
+
+    /// Open and try to connect once. An unreachable server is retried on later use instead of failing startup.
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        let mut connection = Self::open(redis_url)?;
+        if let Err(e) = connection.get().await {
+            warn!("Redis unavailable at startup, will keep retrying: {}", e);
+        }
+        Ok(connection)
+    }
+
+    pub fn with_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// A handle to the live connection, reconnecting first if it was lost
+    pub async fn get(&mut self) -> Result<MultiplexedConnection> {
+        if let Some(connection) = &self.connection {
+            return Ok(connection.clone());
+        }
+
+        if let Some(retry_at) = self.retry_at {
+            let now = Instant::now();
+            if now < retry_at {
+                return Err(anyhow!(
+                    "Redis unavailable, next reconnect in {}ms",
+                    (retry_at - now).as_millis()
+                ));
+            }
+        }
+
+        let attempt = tokio::time::timeout(
+            self.policy.connect_timeout,
+            self.client.get_multiplexed_async_connection(),
+        )
+        .await;
+        match attempt {
+            Ok(Ok(connection)) => {
+                if self.failures > 0 {
+                    info!("Reconnected to Redis after {} failed attempts", self.failures);
+                }
+                self.failures = 0;
+                self.retry_at = None;
+                self.connection = Some(connection.clone());
+                Ok(connection)
+            }
+            Ok(Err(e)) => Err(self.connect_failed(e.to_string())),
+            Err(_) => Err(self.connect_failed(format!(
+                "timed out after {}ms",
+                self.policy.connect_timeout.as_millis()
+            ))),
+        }
+    }
+
+    /// Pass a command result through, dropping the connection if the error means it is broken
+    pub fn check<T>(&mut self, result: RedisResult<T>) -> Result<T> {
+        result.map_err(|e| {
+            if is_connection_error(&e) && self.connection.take().is_some() {
+                warn!("Lost Redis connection: {}", e);
+            }
+            e.into()
+        })
+    }
+
+    fn connect_failed(&mut self, reason: String) -> anyhow::Error {
+        self.failures += 1;
+        let backoff = self.policy.backoff(self.failures);
+        self.retry_at = Some(Instant::now() + backoff);
+        anyhow!(
+            "Redis connection attempt {} failed ({}), retrying in {}ms",
+            self.failures,
+            reason,
+            backoff.as_millis()
+        )
+    }
+}
+
+fn is_connection_error(e: &RedisError) -> bool {
+    e.is_io_error() || e.is_connection_dropped() || e.is_connection_refusal() || e.is_timeout()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal Redis stand-in: answers PING with `+PONG` and anything else (connection setup) with `+OK`,
+    /// hanging up after `pings` PINGs to simulate a dropped server
+    async fn pong_server(listener: TcpListener, pings: usize) {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let mut answered = 0;
+        while answered < pings {
+            let n = match socket.read(&mut buf).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => n,
+            };
+            // Each command is a RESP array whose first bulk string is the command name
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let lines: Vec<&str> = request.split("\r\n").collect();
+            for (i, line) in lines.iter().enumerate() {
+                if !line.starts_with('*') {
+                    continue;
+                }
+                if lines.get(i + 2).is_some_and(|name| name.eq_ignore_ascii_case("PING")) {
+                    socket.write_all(b"+PONG\r\n").await.unwrap();
+                    answered += 1;
+                } else {
+                    socket.write_all(b"+OK\r\n").await.unwrap();
+                }
+            }
+        }
+    }
+
+    async fn ping(connection: &mut RedisConnection) -> Result<String> {
+        let mut handle = connection.get().await?;
+        let result = redis::cmd("PING").query_async(&mut handle).await;
+        connection.check(result)
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(30), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_after_backoff_once_server_returns() {
+        // Reserve a port, then leave it closed so the first attempt is refused
+        let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let mut connection = RedisConnection::open(&format!("redis://{}/", addr))
+            .unwrap()
+            .with_policy(ReconnectPolicy {
+                initial_backoff: Duration::from_millis(100),
+                ..ReconnectPolicy::default()
+            });
+
+        assert!(ping(&mut connection).await.is_err());
+        // Within the backoff window no attempt is made
+        let err = connection.get().await.unwrap_err();
+        assert!(err.to_string().contains("next reconnect"));
+
+        let listener = TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(pong_server(listener, 1));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        assert_eq!(ping(&mut connection).await.unwrap(), "PONG");
+        assert!(connection.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_connection_is_discarded() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(pong_server(listener, 1));
+
+        let mut connection = RedisConnection::open(&format!("redis://{}/", addr)).unwrap();
+        assert_eq!(ping(&mut connection).await.unwrap(), "PONG");
+
+        // The server hangs up after its one reply
+        assert!(ping(&mut connection).await.is_err());
+        assert!(!connection.is_connected());
+    }
+}
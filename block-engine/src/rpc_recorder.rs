@@ -0,0 +1,284 @@
+use crate::simulator::{SimulationResult, SolanaRpcClient};
+use async_trait::async_trait;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Result};
+
+/// A captured sequence of RPC responses that can be replayed in tests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcSession {
+    /// Simulation results keyed by transaction fingerprint
+    pub simulations: HashMap<String, SimulationResult>,
+    /// Account lookups keyed by base58 pubkey
+    pub accounts: HashMap<String, Option<Account>>,
+    /// Blockhashes in the order they were returned
+    pub blockhashes: Vec<Hash>,
+}
+
+impl RpcSession {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// Fingerprint of the full transaction, so unsigned transactions don't collide on the default signature
+pub fn transaction_fingerprint(transaction: &Transaction) -> Result<String> {
+    let bytes = bincode::serialize(transaction)?;
+    Ok(hex::encode(Sha256::digest(bytes)))
+}
+
+/// Wraps a real client and records every response into an `RpcSession`
+pub struct RecordingRpcClient {
+    inner: Box<dyn SolanaRpcClient>,
+    session: Arc<Mutex<RpcSession>>,
+}
+
+impl RecordingRpcClient {
+    pub fn new(inner: Box<dyn SolanaRpcClient>) -> Self {
+        Self {
+            inner,
+            session: Arc::new(Mutex::new(RpcSession::default())),
+        }
+    }
+
+    /// Snapshot of everything recorded so far
+    pub fn session(&self) -> RpcSession {
+        self.session.lock().unwrap().clone()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.session().save(path)
+    }
+}
+
+#[async_trait]
+impl SolanaRpcClient for RecordingRpcClient {
+    async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult> {
+        let result = self.inner.simulate_transaction(transaction).await?;
+        let fingerprint = transaction_fingerprint(transaction)?;
+        self.session.lock().unwrap().simulations.insert(fingerprint, result.clone());
+        Ok(result)
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        let account = self.inner.get_account(pubkey).await?;
+        self.session.lock().unwrap().accounts.insert(pubkey.to_string(), account.clone());
+        Ok(account)
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        let blockhash = self.inner.get_latest_blockhash().await?;
+        self.session.lock().unwrap().blockhashes.push(blockhash);
+        Ok(blockhash)
+    }
+}
+
+/// Answers RPC calls from a previously recorded session
+pub struct ReplayRpcClient {
+    session: RpcSession,
+    next_blockhash: AtomicUsize,
+}
+
+impl ReplayRpcClient {
+    pub fn new(session: RpcSession) -> Self {
+        Self {
+            session,
+            next_blockhash: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(RpcSession::load(path)?))
+    }
+}
+
+#[async_trait]
+impl SolanaRpcClient for ReplayRpcClient {
+    async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult> {
+        let fingerprint = transaction_fingerprint(transaction)?;
+        self.session
+            .simulations
+            .get(&fingerprint)
+            .cloned()
+            .ok_or_else(|| anyhow!("No recorded simulation for transaction {}", fingerprint))
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        self.session
+            .accounts
+            .get(&pubkey.to_string())
+            .cloned()
+            .ok_or_else(|| anyhow!("No recorded account lookup for {}", pubkey))
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        if self.session.blockhashes.is_empty() {
+            return Err(anyhow!("No recorded blockhashes"));
+        }
+
+        // Replay in order, then keep returning the last one
+        let index = self.next_blockhash.fetch_add(1, Ordering::Relaxed);
+        let index = index.min(self.session.blockhashes.len() - 1);
+        Ok(self.session.blockhashes[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{HttpSolanaRpcClient, MockSolanaRpcClient, ProgramBehavior};
+    use serde_json::{json, Value};
+    use solana_sdk::{
+        instruction::Instruction,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Minimal JSON-RPC node: answers each request with `respond(method)`, one request per connection
+    async fn serve_rpc(respond: fn(&str) -> Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(answer_rpc(socket, respond));
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    async fn answer_rpc(mut socket: TcpStream, respond: fn(&str) -> Value) {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        let body = loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            if n == 0 {
+                return;
+            }
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    break serde_json::from_slice::<Value>(&request[end + 4..end + 4 + length]).unwrap();
+                }
+            }
+        };
+
+        let reply = json!({"jsonrpc": "2.0", "id": body["id"], "result": respond(body["method"].as_str().unwrap())}).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            reply.len(),
+            reply
+        );
+        let _ = socket.write_all(response.as_bytes()).await;
+    }
+
+    fn ui_account(lamports: u64) -> Value {
+        json!({"lamports": lamports, "data": ["", "base64"], "owner": "11111111111111111111111111111111", "executable": false, "rentEpoch": 0, "space": 0})
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay_session() {
+        let program_id = Pubkey::new_unique();
+        let mut mock = MockSolanaRpcClient::new();
+        mock.set_program_behavior(program_id, ProgramBehavior::succeed(42_000));
+        let account_key = Pubkey::new_unique();
+        mock.add_account(account_key, Account::new(1_000, 0, &Pubkey::default()));
+
+        let recorder = RecordingRpcClient::new(Box::new(mock));
+        let payer = Keypair::new();
+        let transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_bytes(program_id, &[1], vec![])],
+            Some(&payer.pubkey()),
+        );
+
+        let recorded = recorder.simulate_transaction(&transaction).await.unwrap();
+        let recorded_account = recorder.get_account(&account_key).await.unwrap();
+        let recorded_hash = recorder.get_latest_blockhash().await.unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        recorder.save(&path).unwrap();
+
+        let replay = ReplayRpcClient::from_file(&path).unwrap();
+        let replayed = replay.simulate_transaction(&transaction).await.unwrap();
+        assert_eq!(replayed.compute_units_consumed, recorded.compute_units_consumed);
+        assert_eq!(replayed.logs, recorded.logs);
+        assert_eq!(replay.get_account(&account_key).await.unwrap(), recorded_account);
+        assert_eq!(replay.get_latest_blockhash().await.unwrap(), recorded_hash);
+
+        // Unrecorded calls are errors rather than silent defaults
+        let other = Transaction::new_with_payer(
+            &[Instruction::new_with_bytes(program_id, &[2], vec![])],
+            Some(&payer.pubkey()),
+        );
+        assert!(replay.simulate_transaction(&other).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_records_a_json_rpc_node() {
+        let url = serve_rpc(|method| match method {
+            "getVersion" => json!({"solana-core": "1.18.26", "feature-set": 1}),
+            "getMultipleAccounts" => json!({"context": {"slot": 1}, "value": [ui_account(10_000), null]}),
+            "simulateTransaction" => json!({"context": {"slot": 1}, "value": {
+                "err": null,
+                "logs": ["Program 11111111111111111111111111111111 success"],
+                "accounts": [ui_account(8_900), ui_account(100)],
+                "unitsConsumed": 150,
+                "returnData": null,
+            }}),
+            "getLatestBlockhash" => json!({"context": {"slot": 1}, "value": {
+                "blockhash": "4uQeVj5tqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM",
+                "lastValidBlockHeight": 10,
+            }}),
+            other => panic!("unexpected RPC method {}", other),
+        })
+        .await;
+
+        let recorder = RecordingRpcClient::new(Box::new(HttpSolanaRpcClient::new(url)));
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &recipient, 100)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+
+        let result = recorder.simulate_transaction(&transaction).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.compute_units_consumed, 150);
+        let delta = |pubkey: Pubkey| result.balance_changes.iter().find(|c| c.pubkey == pubkey).unwrap().lamports_delta;
+        assert_eq!((delta(payer.pubkey()), delta(recipient)), (-1_100, 100));
+        let blockhash = recorder.get_latest_blockhash().await.unwrap();
+
+        // What the node said replays without it
+        let replay = ReplayRpcClient::new(recorder.session());
+        let replayed = replay.simulate_transaction(&transaction).await.unwrap();
+        assert_eq!(replayed.balance_changes, result.balance_changes);
+        assert_eq!(replayed.logs, result.logs);
+        assert_eq!(replay.get_latest_blockhash().await.unwrap(), blockhash);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::ordered_block::now_millis;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+use tracing::warn;
+
+/// Source of wall-clock milliseconds, replaceable in tests
+pub trait Clock: Send + Sync {
+    fn now_millis(&self) -> u64;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        now_millis()
+    }
+}
+
+/// Manually advanced clock for tests. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    millis: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis: Arc::new(AtomicU64::new(millis)),
+        }
+    }
+
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, millis: u64) {
+        self.millis.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
+
+/// How a window was picked up relative to its closing boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowTiming {
+    OnTime,
+    /// Picked up `lag_ms` after it closed, e.g. while catching up after a stall
+    Late { lag_ms: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledWindow {
+    pub window_id: u64,
+    pub timing: WindowTiming,
+}
+
+/// Counters describing how well the engine is keeping up with window boundaries
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulerStats {
+    pub on_time_windows: u64,
+    pub late_windows: u64,
+    pub skipped_windows: u64,
+    pub last_processed_window: Option<u64>,
+}
+
+/// Hands out windows in order, exactly once each, as their boundaries pass.
+///
+/// Window `n` covers `[n * window_ms, (n + 1) * window_ms)` since the epoch. After a stall
+/// the scheduler replays every missed window in order, unless the backlog exceeds
+/// `max_catch_up_windows`, in which case the oldest windows are skipped and counted, and handed to
+/// the engine through `take_skipped` so their bundles are not left behind.
+pub struct WindowScheduler {
+    window_ms: u64,
+    late_tolerance_ms: u64,
+    max_catch_up_windows: u64,
+    clock: Arc<dyn Clock>,
+    next_window: Option<u64>,
+    // Skipped windows not yet collected by `take_skipped`
+    skipped: Option<Range<u64>>,
+    stats: SchedulerStats,
+}
+
+impl WindowScheduler {
+    pub fn new(window_ms: u64, late_tolerance_ms: u64, max_catch_up_windows: u64) -> Self {
+        Self::with_clock(window_ms, late_tolerance_ms, max_catch_up_windows, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(
+        window_ms: u64,
+        late_tolerance_ms: u64,
+        max_catch_up_windows: u64,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            window_ms: window_ms.max(1),
+            late_tolerance_ms,
+            max_catch_up_windows: max_catch_up_windows.max(1),
+            clock,
+            next_window: None,
+            skipped: None,
+            stats: SchedulerStats::default(),
+        }
+    }
+
+    pub fn window_ms(&self) -> u64 {
+        self.window_ms
+    }
+
+    /// Window containing the current time
+    pub fn current_window(&self) -> u64 {
+        self.clock.now_millis() / self.window_ms
+    }
+
+    /// Millisecond timestamp at which a window closes
+    pub fn window_close_millis(&self, window_id: u64) -> u64 {
+        (window_id + 1) * self.window_ms
+    }
+
+    /// Resume after a previously processed window, e.g. one restored from storage
+    pub fn resume_after(&mut self, window_id: u64) {
+        self.next_window = Some(window_id + 1);
+        self.stats.last_processed_window = Some(window_id);
+    }
+
+    /// Pick the next window to process and when it becomes due, without waiting
+    pub fn plan(&mut self) -> (u64, u64) {
+        let now = self.clock.now_millis();
+        // Start with the window in progress; it is processed once it closes
+        let mut next = self.next_window.unwrap_or(now / self.window_ms);
+
+        let current = now / self.window_ms;
+        let backlog = current.saturating_sub(next);
+        if backlog > self.max_catch_up_windows {
+            let skipped = backlog - self.max_catch_up_windows;
+            warn!(
+                "Window scheduler is {} windows behind; skipping windows {}..{}",
+                backlog, next, next + skipped
+            );
+            self.stats.skipped_windows += skipped;
+            let start = self.skipped.take().map_or(next, |earlier| earlier.start);
+            self.skipped = Some(start..next + skipped);
+            next += skipped;
+        }
+
+        self.next_window = Some(next);
+        (next, self.window_close_millis(next))
+    }
+
+    /// Windows skipped since the last call, which will never be handed out by `next_window`
+    pub fn take_skipped(&mut self) -> Option<Range<u64>> {
+        self.skipped.take()
+    }
+
+    /// Wait until the next window closes and return it; missed windows are returned immediately
+    pub async fn next_window(&mut self) -> ScheduledWindow {
+        let (window_id, close_at) = self.plan();
+
+        let now = self.clock.now_millis();
+        if now < close_at {
+            sleep(Duration::from_millis(close_at - now)).await;
+        }
+
+        let lag_ms = self.clock.now_millis().saturating_sub(close_at);
+        let timing = if lag_ms <= self.late_tolerance_ms {
+            WindowTiming::OnTime
+        } else {
+            WindowTiming::Late { lag_ms }
+        };
+
+        ScheduledWindow { window_id, timing }
+    }
+
+    /// Record that a window handed out by `next_window` has been processed
+    pub fn complete(&mut self, window: ScheduledWindow) {
+        match window.timing {
+            WindowTiming::OnTime => self.stats.on_time_windows += 1,
+            WindowTiming::Late { .. } => self.stats.late_windows += 1,
+        }
+        self.stats.last_processed_window = Some(window.window_id);
+        self.next_window = Some(window.window_id + 1);
+    }
+
+    /// Windows that have closed but not yet been processed
+    pub fn lag_windows(&self) -> u64 {
+        let last_closed = self.current_window().saturating_sub(1);
+        match self.stats.last_processed_window {
+            Some(last) => last_closed.saturating_sub(last),
+            None => 0,
+        }
+    }
+
+    pub fn stats(&self) -> SchedulerStats {
+        self.stats.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler_at(millis: u64, max_catch_up: u64) -> (WindowScheduler, ManualClock) {
+        let clock = ManualClock::new(millis);
+        let scheduler = WindowScheduler::with_clock(200, 50, max_catch_up, Arc::new(clock.clone()));
+        (scheduler, clock)
+    }
+
+    #[tokio::test]
+    async fn test_windows_processed_in_order_on_time() {
+        let (mut scheduler, clock) = scheduler_at(1_000, 10);
+
+        // Window 5 is in progress at t=1000 and closes at t=1200
+        assert_eq!(scheduler.plan(), (5, 1_200));
+        clock.set(1_200);
+        let window = scheduler.next_window().await;
+        assert_eq!(window, ScheduledWindow { window_id: 5, timing: WindowTiming::OnTime });
+        scheduler.complete(window);
+
+        clock.set(1_410);
+        let window = scheduler.next_window().await;
+        assert_eq!(window, ScheduledWindow { window_id: 6, timing: WindowTiming::OnTime });
+        scheduler.complete(window);
+
+        let stats = scheduler.stats();
+        assert_eq!(stats.on_time_windows, 2);
+        assert_eq!(stats.last_processed_window, Some(6));
+    }
+
+    #[tokio::test]
+    async fn test_catches_up_missed_windows_in_order() {
+        // Window 5 was the last one processed, then the engine stalled until t=2000
+        let (mut scheduler, _clock) = scheduler_at(2_000, 10);
+        scheduler.resume_after(5);
+
+        let mut replayed = Vec::new();
+        for _ in 0..4 {
+            let window = scheduler.next_window().await;
+            replayed.push(window.window_id);
+            scheduler.complete(window);
+        }
+
+        assert_eq!(replayed, vec![6, 7, 8, 9]);
+        let stats = scheduler.stats();
+        assert_eq!(stats.late_windows, 3);
+        assert_eq!(stats.on_time_windows, 1);
+        assert_eq!(stats.skipped_windows, 0);
+    }
+
+    #[test]
+    fn test_skips_windows_beyond_catch_up_limit() {
+        // Window 6 is next, but the clock is already in window 20
+        let (mut scheduler, _clock) = scheduler_at(4_000, 2);
+        scheduler.resume_after(5);
+
+        let (next, _) = scheduler.plan();
+
+        assert_eq!(next, 18);
+        assert_eq!(scheduler.stats().skipped_windows, 12);
+        assert_eq!(scheduler.take_skipped(), Some(6..18));
+        assert_eq!(scheduler.take_skipped(), None);
+    }
+
+    #[test]
+    fn test_lag_and_resume() {
+        let (mut scheduler, clock) = scheduler_at(1_000, 10);
+        scheduler.resume_after(2);
+        assert_eq!(scheduler.lag_windows(), 2);
+        assert_eq!(scheduler.plan().0, 3);
+
+        clock.set(600);
+        assert_eq!(scheduler.lag_windows(), 0);
+    }
+}
@@ -1,14 +1,38 @@
 use crate::bundle::{Bundle, BundleError};
 use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
 use solana_sdk::{
     account::Account,
+    commitment_config::CommitmentConfig,
     hash::Hash,
     pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::SystemInstruction,
+    system_program,
     transaction::Transaction,
 };
+use lru::LruCache;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use anyhow::Result;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+
+/// Compute units the mock charges per transaction unless scripted otherwise
+pub const DEFAULT_COMPUTE_UNITS_PER_TRANSACTION: u64 = 5000;
+/// Solana's per-transaction compute budget ceiling
+pub const MAX_COMPUTE_UNITS_PER_TRANSACTION: u64 = 1_400_000;
+/// Simulations kept before the least recently used one is evicted
+pub const DEFAULT_SIMULATION_CACHE_CAPACITY: usize = 10_000;
+/// How long a cached simulation may be served, one slot by default. Account versions only move for
+/// blocks this engine builds, so for state changed anywhere else on chain the TTL is the only bound
+/// on how stale a served result can be.
+pub const DEFAULT_SIMULATION_CACHE_TTL: Duration = Duration::from_millis(400);
+/// How long a single RPC simulation may take before the bundle is rejected
+pub const DEFAULT_SIMULATION_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimulationResult {
@@ -17,6 +41,15 @@ pub struct SimulationResult {
     pub accounts_accessed: Vec<Pubkey>,
     pub compute_units_consumed: u64,
     pub error: Option<String>,
+    #[serde(default)]
+    pub balance_changes: Vec<BalanceChange>,
+}
+
+/// Net lamport change of one account caused by a simulated transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub pubkey: Pubkey,
+    pub lamports_delta: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -32,9 +65,57 @@ pub trait SolanaRpcClient: Send + Sync {
     async fn get_latest_blockhash(&self) -> Result<Hash>;
 }
 
+/// Predicate over a transaction, used to script mock RPC behaviour
+pub type TransactionPredicate = Box<dyn Fn(&Transaction) -> bool + Send + Sync>;
+
+/// Scripted behaviour for every instruction that invokes a given program
+#[derive(Debug, Clone, Default)]
+pub struct ProgramBehavior {
+    pub compute_units: u64,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub latency: Duration,
+}
+
+impl ProgramBehavior {
+    /// Program that succeeds and consumes the given compute units per instruction
+    pub fn succeed(compute_units: u64) -> Self {
+        Self {
+            compute_units,
+            ..Self::default()
+        }
+    }
+
+    /// Program whose instructions always fail with the given error
+    pub fn fail(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Self::default()
+        }
+    }
+}
+
+struct FailureRule {
+    predicate: TransactionPredicate,
+    error: String,
+    // RPC-level errors surface as `Err`, simulation failures as `success: false`
+    rpc_error: bool,
+}
+
 pub struct MockSolanaRpcClient {
     pub accounts: HashMap<Pubkey, Account>,
     pub simulation_failures: Vec<String>, // Transaction signatures that should fail
+    pub program_behaviors: HashMap<Pubkey, ProgramBehavior>,
+    pub compute_units_per_transaction: u64,
+    pub max_compute_units_per_transaction: u64,
+    pub latency: Duration,
+    failure_rules: Vec<FailureRule>,
+}
+
+impl Default for MockSolanaRpcClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MockSolanaRpcClient {
@@ -42,6 +123,11 @@ impl MockSolanaRpcClient {
         Self {
             accounts: HashMap::new(),
             simulation_failures: Vec::new(),
+            program_behaviors: HashMap::new(),
+            compute_units_per_transaction: DEFAULT_COMPUTE_UNITS_PER_TRANSACTION,
+            max_compute_units_per_transaction: MAX_COMPUTE_UNITS_PER_TRANSACTION,
+            latency: Duration::ZERO,
+            failure_rules: Vec::new(),
         }
     }
 
@@ -52,61 +138,495 @@ impl MockSolanaRpcClient {
     pub fn set_simulation_failure(&mut self, tx_signature: String) {
         self.simulation_failures.push(tx_signature);
     }
+
+    /// Script how instructions invoking `program_id` behave
+    pub fn set_program_behavior(&mut self, program_id: Pubkey, behavior: ProgramBehavior) {
+        self.program_behaviors.insert(program_id, behavior);
+    }
+
+    /// Fail simulation of every transaction matching the predicate
+    pub fn fail_when<F>(&mut self, predicate: F, error: impl Into<String>)
+    where
+        F: Fn(&Transaction) -> bool + Send + Sync + 'static,
+    {
+        self.failure_rules.push(FailureRule {
+            predicate: Box::new(predicate),
+            error: error.into(),
+            rpc_error: false,
+        });
+    }
+
+    /// Return an RPC error (rather than a failed simulation) for matching transactions
+    pub fn rpc_error_when<F>(&mut self, predicate: F, error: impl Into<String>)
+    where
+        F: Fn(&Transaction) -> bool + Send + Sync + 'static,
+    {
+        self.failure_rules.push(FailureRule {
+            predicate: Box::new(predicate),
+            error: error.into(),
+            rpc_error: true,
+        });
+    }
+
+    /// Base compute units charged to every transaction
+    pub fn set_compute_units(&mut self, compute_units: u64) {
+        self.compute_units_per_transaction = compute_units;
+    }
+
+    /// Per-transaction compute budget; transactions above it fail simulation
+    pub fn set_compute_unit_limit(&mut self, limit: u64) {
+        self.max_compute_units_per_transaction = limit;
+    }
+
+    /// Delay applied to every RPC call
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    fn failed(logs: Vec<String>, compute_units_consumed: u64, error: String) -> SimulationResult {
+        SimulationResult {
+            success: false,
+            logs,
+            accounts_accessed: vec![],
+            compute_units_consumed,
+            error: Some(error),
+            balance_changes: vec![],
+        }
+    }
+
+    /// Apply system transfers to the known account balances, failing on overdrafts
+    fn transfer_balance_changes(&self, transaction: &Transaction) -> Result<Vec<BalanceChange>, String> {
+        let message = &transaction.message;
+        let mut deltas: Vec<BalanceChange> = Vec::new();
+
+        for ix in &message.instructions {
+            if message.account_keys.get(ix.program_id_index as usize) != Some(&system_program::id()) {
+                continue;
+            }
+            let lamports = match bincode::deserialize::<SystemInstruction>(&ix.data) {
+                Ok(SystemInstruction::Transfer { lamports }) => lamports,
+                _ => continue,
+            };
+            // No account holds more than i64::MAX lamports, so larger transfers can only fail
+            let lamports = i64::try_from(lamports)
+                .map_err(|_| format!("Transfer: {} lamports exceeds any balance", lamports))?;
+            let account_at = |position: usize| {
+                ix.accounts
+                    .get(position)
+                    .and_then(|&index| message.account_keys.get(index as usize))
+                    .copied()
+                    .ok_or_else(|| "Transfer: account index out of range".to_string())
+            };
+            let (from, to) = (account_at(0)?, account_at(1)?);
+
+            for (pubkey, delta) in [(from, -lamports), (to, lamports)] {
+                match deltas.iter_mut().find(|change| change.pubkey == pubkey) {
+                    Some(change) => change.lamports_delta = change.lamports_delta.saturating_add(delta),
+                    None => deltas.push(BalanceChange { pubkey, lamports_delta: delta }),
+                }
+            }
+
+            // Only accounts registered with the mock have a balance to overdraw
+            if let Some(account) = self.accounts.get(&from) {
+                let spent = -deltas.iter().find(|change| change.pubkey == from).map_or(0, |change| i128::from(change.lamports_delta));
+                if spent > i128::from(account.lamports) {
+                    return Err(format!("Transfer: insufficient lamports {}, need {}", account.lamports, spent));
+                }
+            }
+        }
+
+        Ok(deltas)
+    }
 }
 
 #[async_trait]
 impl SolanaRpcClient for MockSolanaRpcClient {
     async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult> {
+        let message = &transaction.message;
+        let behaviors: Vec<(&Pubkey, &ProgramBehavior)> = message
+            .instructions
+            .iter()
+            .filter_map(|ix| message.account_keys.get(ix.program_id_index as usize))
+            .filter_map(|program_id| self.program_behaviors.get(program_id).map(|b| (program_id, b)))
+            .collect();
+
+        let latency = self.latency + behaviors.iter().map(|(_, b)| b.latency).max().unwrap_or_default();
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+
+        for rule in &self.failure_rules {
+            if rule.rpc_error && (rule.predicate)(transaction) {
+                return Err(anyhow::anyhow!(rule.error.clone()));
+            }
+        }
+
         let tx_signature = transaction.signatures[0].to_string();
         
         if self.simulation_failures.contains(&tx_signature) {
-            return Ok(SimulationResult {
-                success: false,
-                logs: vec!["Program execution failed".to_string()],
-                accounts_accessed: vec![],
-                compute_units_consumed: 0,
-                error: Some("Instruction failed".to_string()),
-            });
+            return Ok(Self::failed(
+                vec!["Program execution failed".to_string()],
+                0,
+                "Instruction failed".to_string(),
+            ));
+        }
+
+        for rule in &self.failure_rules {
+            if !rule.rpc_error && (rule.predicate)(transaction) {
+                return Ok(Self::failed(
+                    vec!["Program execution failed".to_string()],
+                    0,
+                    rule.error.clone(),
+                ));
+            }
+        }
+
+        let mut logs = Vec::new();
+        let mut compute_units = self.compute_units_per_transaction;
+
+        for (program_id, behavior) in behaviors {
+            logs.push(format!("Program {} invoke [1]", program_id));
+            logs.extend(behavior.logs.iter().cloned());
+            compute_units += behavior.compute_units;
+
+            if let Some(error) = &behavior.error {
+                logs.push(format!("Program {} failed: {}", program_id, error));
+                return Ok(Self::failed(logs, compute_units, error.clone()));
+            }
+        }
+
+        if compute_units > self.max_compute_units_per_transaction {
+            logs.push("Computational budget exceeded".to_string());
+            return Ok(Self::failed(
+                logs,
+                self.max_compute_units_per_transaction,
+                format!(
+                    "Exceeded compute unit limit ({} > {})",
+                    compute_units, self.max_compute_units_per_transaction
+                ),
+            ));
         }
 
+        let balance_changes = match self.transfer_balance_changes(transaction) {
+            Ok(balance_changes) => balance_changes,
+            Err(error) => return Ok(Self::failed(logs, compute_units, error)),
+        };
+
+        logs.push("Program log: Success".to_string());
+
         Ok(SimulationResult {
             success: true,
-            logs: vec!["Program log: Success".to_string()],
-            accounts_accessed: transaction.message.account_keys.clone(),
-            compute_units_consumed: 5000,
+            logs,
+            accounts_accessed: message.account_keys.clone(),
+            compute_units_consumed: compute_units,
             error: None,
+            balance_changes,
         })
     }
 
     async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
         Ok(self.accounts.get(pubkey).cloned())
     }
 
     async fn get_latest_blockhash(&self) -> Result<Hash> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
         Ok(Hash::new_unique())
     }
 }
 
+/// `SolanaRpcClient` backed by a Solana JSON-RPC endpoint
+pub struct HttpSolanaRpcClient {
+    client: RpcClient,
+}
+
+impl HttpSolanaRpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(url.into(), CommitmentConfig::confirmed()),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.client.url()
+    }
+}
+
+#[async_trait]
+impl SolanaRpcClient for HttpSolanaRpcClient {
+    /// Simulates against the latest bank; balance changes cover the transaction's writable accounts
+    async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult> {
+        let message = &transaction.message;
+        let writable: Vec<Pubkey> = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| message.is_writable(*i))
+            .map(|(_, pubkey)| *pubkey)
+            .collect();
+        let before = self.client.get_multiple_accounts(&writable).await?;
+
+        let config = RpcSimulateTransactionConfig {
+            replace_recent_blockhash: true,
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: None,
+                addresses: writable.iter().map(ToString::to_string).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let response = self.client.simulate_transaction_with_config(transaction, config).await?.value;
+
+        let after = response.accounts.unwrap_or_default();
+        let balance_changes = writable
+            .iter()
+            .zip(&before)
+            .zip(&after)
+            .filter_map(|((pubkey, before), after)| {
+                let before = before.as_ref().map_or(0, |account| account.lamports);
+                let after = after.as_ref().map_or(0, |account| account.lamports);
+                (before != after).then(|| BalanceChange { pubkey: *pubkey, lamports_delta: lamport_delta(before, after) })
+            })
+            .collect();
+
+        Ok(SimulationResult {
+            success: response.err.is_none(),
+            logs: response.logs.unwrap_or_default(),
+            accounts_accessed: message.account_keys.clone(),
+            compute_units_consumed: response.units_consumed.unwrap_or(0),
+            error: response.err.map(|err| err.to_string()),
+            balance_changes,
+        })
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self.client.get_account_with_commitment(pubkey, self.client.commitment()).await?.value)
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        Ok(self.client.get_latest_blockhash().await?)
+    }
+}
+
+/// Signed lamport change, saturating at the ends of `i64`
+fn lamport_delta(before: u64, after: u64) -> i64 {
+    let delta = i128::from(after) - i128::from(before);
+    i64::try_from(delta).unwrap_or(if delta < 0 { i64::MIN } else { i64::MAX })
+}
+
+/// A cached simulation result together with the account versions it was computed against
+#[derive(Debug, Clone)]
+struct CachedSimulation {
+    result: SimulationResult,
+    account_versions: Vec<(Pubkey, u64)>,
+    cached_at: Instant,
+}
+
+/// Hit/miss counters for the simulation cache
+#[derive(Debug, Clone, Default)]
+pub struct SimulationCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+    /// Entries dropped for capacity or age rather than an account change
+    pub evictions: u64,
+    pub entries: usize,
+}
+
+impl SimulationCacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups > 0 {
+            self.hits as f64 / lookups as f64
+        } else {
+            0.0
+        }
+    }
+}
+
 pub struct TransactionSimulator {
     rpc_client: Box<dyn SolanaRpcClient>,
+    cache: Mutex<LruCache<Signature, CachedSimulation>>,
+    cache_ttl: Duration,
+    rpc_timeout: Duration,
+    account_versions: RwLock<HashMap<Pubkey, u64>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    cache_invalidations: AtomicU64,
+    cache_evictions: AtomicU64,
 }
 
 impl TransactionSimulator {
     pub fn new(rpc_client: Box<dyn SolanaRpcClient>) -> Self {
-        Self { rpc_client }
+        Self {
+            rpc_client,
+            cache: Mutex::new(LruCache::new(cache_capacity(DEFAULT_SIMULATION_CACHE_CAPACITY))),
+            cache_ttl: DEFAULT_SIMULATION_CACHE_TTL,
+            rpc_timeout: DEFAULT_SIMULATION_TIMEOUT,
+            account_versions: RwLock::new(HashMap::new()),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            cache_invalidations: AtomicU64::new(0),
+            cache_evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Bound the cache to `capacity` entries, each served for at most `ttl`
+    pub fn with_cache_limits(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.cache = Mutex::new(LruCache::new(cache_capacity(capacity)));
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Serve each cached simulation for at most `ttl`; zero disables the cache
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Fail any simulation whose RPC call takes longer than `timeout`
+    pub fn with_rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.rpc_timeout = timeout;
+        self
+    }
+
+    /// Confirm the RPC endpoint answers by fetching the latest blockhash
+    pub async fn check_rpc(&self) -> Result<Hash> {
+        self.rpc_client.get_latest_blockhash().await
     }
 
     pub async fn simulate_bundle(&self, bundle: &Bundle) -> Result<Vec<SimulationResult>> {
         let mut results = Vec::new();
         
         for transaction in &bundle.transactions {
-            let result = self.rpc_client.simulate_transaction(transaction).await?;
+            let result = self.simulate_transaction(transaction).await?;
             results.push(result);
         }
         
         Ok(results)
     }
 
+    /// Simulate a single transaction, serving the result from cache when none of
+    /// the accounts it touches have changed since it was last simulated
+    pub async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult> {
+        let signature = match cache_key(transaction) {
+            Some(signature) => signature,
+            None => return self.simulate_uncached(transaction).await,
+        };
+
+        if let Some(result) = self.lookup_cached(&signature) {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(result);
+        }
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+
+        // Capture versions before simulating so a concurrent account change
+        // leaves the entry stale rather than looking fresh
+        let account_versions = self.current_versions(&transaction.message.account_keys);
+        let result = self.simulate_uncached(transaction).await?;
+
+        let mut cache = self.cache.lock().unwrap();
+        let evicted = cache.push(signature, CachedSimulation {
+            result: result.clone(),
+            account_versions,
+            cached_at: Instant::now(),
+        });
+        if evicted.is_some_and(|(evicted, _)| evicted != signature) {
+            self.cache_evictions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(result)
+    }
+
+    async fn simulate_uncached(&self, transaction: &Transaction) -> Result<SimulationResult> {
+        tokio::time::timeout(self.rpc_timeout, self.rpc_client.simulate_transaction(transaction))
+            .await
+            .map_err(|_| anyhow!("simulation timed out after {}ms", self.rpc_timeout.as_millis()))?
+    }
+
+    /// Record that the given accounts changed, evicting every cached simulation that touched them
+    pub fn invalidate_accounts(&self, pubkeys: &[Pubkey]) {
+        if pubkeys.is_empty() {
+            return;
+        }
+
+        {
+            let mut versions = self.account_versions.write().unwrap();
+            for pubkey in pubkeys {
+                *versions.entry(*pubkey).or_insert(0) += 1;
+            }
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        let stale: Vec<Signature> = cache
+            .iter()
+            .filter(|(_, cached)| cached.account_versions.iter().any(|(pubkey, _)| pubkeys.contains(pubkey)))
+            .map(|(signature, _)| *signature)
+            .collect();
+        for signature in &stale {
+            cache.pop(signature);
+        }
+        self.cache_invalidations.fetch_add(stale.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Current version of an account as seen by the cache
+    pub fn account_version(&self, pubkey: &Pubkey) -> u64 {
+        let versions = self.account_versions.read().unwrap();
+        versions.get(pubkey).copied().unwrap_or(0)
+    }
+
+    pub fn get_cache_stats(&self) -> SimulationCacheStats {
+        let cache = self.cache.lock().unwrap();
+
+        SimulationCacheStats {
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
+            invalidations: self.cache_invalidations.load(Ordering::Relaxed),
+            evictions: self.cache_evictions.load(Ordering::Relaxed),
+            entries: cache.len(),
+        }
+    }
+
+    pub fn clear_cache(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.clear();
+    }
+
+    fn lookup_cached(&self, signature: &Signature) -> Option<SimulationResult> {
+        let mut cache = self.cache.lock().unwrap();
+        let cached = cache.get(signature)?;
+        if cached.cached_at.elapsed() >= self.cache_ttl {
+            cache.pop(signature);
+            self.cache_evictions.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let versions = self.account_versions.read().unwrap();
+        let fresh = cached.account_versions.iter().all(|(pubkey, version)| {
+            versions.get(pubkey).copied().unwrap_or(0) == *version
+        });
+
+        if fresh {
+            Some(cached.result.clone())
+        } else {
+            // Never serve a result computed against an older account state
+            cache.pop(signature);
+            self.cache_invalidations.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    fn current_versions(&self, pubkeys: &[Pubkey]) -> Vec<(Pubkey, u64)> {
+        let versions = self.account_versions.read().unwrap();
+        pubkeys
+            .iter()
+            .map(|pubkey| (*pubkey, versions.get(pubkey).copied().unwrap_or(0)))
+            .collect()
+    }
+
     pub async fn validate_bundle(&self, bundle: &Bundle) -> Result<bool, BundleError> {
         // First validate basic bundle constraints
         bundle.validate()?;
@@ -116,18 +636,41 @@ impl TransactionSimulator {
             .map_err(|e| BundleError::SimulationFailed(e.to_string()))?;
         
         // Check if all transactions would succeed
+        let mut balance_deltas: HashMap<Pubkey, i64> = HashMap::new();
         for result in simulation_results {
             if !result.success {
                 return Err(BundleError::SimulationFailed(
                     result.error.unwrap_or_else(|| "Unknown simulation error".to_string())
                 ));
             }
+            for change in result.balance_changes {
+                let delta = balance_deltas.entry(change.pubkey).or_insert(0);
+                *delta = delta.saturating_add(change.lamports_delta);
+            }
+        }
+
+        // Enforce the searcher's post-conditions on the net effect of the bundle
+        for condition in &bundle.post_conditions {
+            condition.check(bundle.tip_lamports, &balance_deltas)?;
         }
         
         Ok(true)
     }
 }
 
+fn cache_capacity(capacity: usize) -> NonZeroUsize {
+    NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Unsigned transactions all share the default signature, so they are never cached
+fn cache_key(transaction: &Transaction) -> Option<Signature> {
+    transaction
+        .signatures
+        .first()
+        .filter(|signature| **signature != Signature::default())
+        .copied()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,4 +711,292 @@ mod tests {
         assert!(!result.success);
         assert!(result.error.is_some());
     }
+
+    struct CountingRpcClient {
+        inner: MockSolanaRpcClient,
+        calls: std::sync::Arc<AtomicU64>,
+    }
+
+    #[async_trait]
+    impl SolanaRpcClient for CountingRpcClient {
+        async fn simulate_transaction(&self, transaction: &Transaction) -> Result<SimulationResult> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.simulate_transaction(transaction).await
+        }
+
+        async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+            self.inner.get_account(pubkey).await
+        }
+
+        async fn get_latest_blockhash(&self) -> Result<Hash> {
+            self.inner.get_latest_blockhash().await
+        }
+    }
+
+    fn counting_simulator() -> (TransactionSimulator, std::sync::Arc<AtomicU64>) {
+        let calls = std::sync::Arc::new(AtomicU64::new(0));
+        let client = CountingRpcClient {
+            inner: MockSolanaRpcClient::new(),
+            calls: calls.clone(),
+        };
+        (TransactionSimulator::new(Box::new(client)), calls)
+    }
+
+    fn signed_transfer(keypair: &Keypair, to: &Pubkey) -> Transaction {
+        let mut transaction = Transaction::new_with_payer(
+            &[system_instruction::transfer(&keypair.pubkey(), to, 100)],
+            Some(&keypair.pubkey()),
+        );
+        transaction.sign(&[keypair], Hash::new_unique());
+        transaction
+    }
+
+    #[tokio::test]
+    async fn test_simulation_cache_hit() {
+        let (simulator, calls) = counting_simulator();
+        let keypair = Keypair::new();
+        let transaction = signed_transfer(&keypair, &Pubkey::new_unique());
+        let bundle = Bundle::new(vec![transaction], 1000, keypair.pubkey().to_string());
+
+        simulator.simulate_bundle(&bundle).await.unwrap();
+        simulator.simulate_bundle(&bundle).await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let stats = simulator.get_cache_stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_simulation_cache_invalidated_on_account_change() {
+        let (simulator, calls) = counting_simulator();
+        let keypair = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let transaction = signed_transfer(&keypair, &recipient);
+
+        simulator.simulate_transaction(&transaction).await.unwrap();
+        simulator.invalidate_accounts(&[recipient]);
+        assert_eq!(simulator.account_version(&recipient), 1);
+        simulator.simulate_transaction(&transaction).await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let stats = simulator.get_cache_stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.invalidations, 1);
+
+        // Unrelated account changes keep the entry
+        simulator.invalidate_accounts(&[Pubkey::new_unique()]);
+        simulator.simulate_transaction(&transaction).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_simulation_cache_is_bounded_by_size_and_age() {
+        let (simulator, calls) = counting_simulator();
+        let simulator = simulator.with_cache_limits(2, Duration::from_secs(60));
+        let keypair = Keypair::new();
+        let transactions: Vec<Transaction> = (0..3).map(|_| signed_transfer(&keypair, &Pubkey::new_unique())).collect();
+
+        for transaction in &transactions {
+            simulator.simulate_transaction(transaction).await.unwrap();
+        }
+        let stats = simulator.get_cache_stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+
+        // The oldest entry was the one evicted
+        simulator.simulate_transaction(&transactions[2]).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        simulator.simulate_transaction(&transactions[0]).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let (simulator, calls) = counting_simulator();
+        let simulator = simulator.with_cache_limits(2, Duration::ZERO);
+        simulator.simulate_transaction(&transactions[0]).await.unwrap();
+        simulator.simulate_transaction(&transactions[0]).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(simulator.get_cache_stats().evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_bounds_staleness_from_changes_the_engine_did_not_see() {
+        let (simulator, calls) = counting_simulator();
+        let simulator = simulator.with_cache_ttl(Duration::from_millis(100));
+        let transaction = signed_transfer(&Keypair::new(), &Pubkey::new_unique());
+
+        // Another leader's block changes the accounts; nothing calls `invalidate_accounts`
+        simulator.simulate_transaction(&transaction).await.unwrap();
+        simulator.simulate_transaction(&transaction).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        simulator.simulate_transaction(&transaction).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(DEFAULT_SIMULATION_CACHE_TTL, Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_transactions_are_not_cached() {
+        let (simulator, calls) = counting_simulator();
+        let keypair = Keypair::new();
+        let transaction = Transaction::new_with_payer(
+            &[system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 100)],
+            Some(&keypair.pubkey()),
+        );
+
+        simulator.simulate_transaction(&transaction).await.unwrap();
+        simulator.simulate_transaction(&transaction).await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(simulator.get_cache_stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_program_behavior_compute_units_and_failure() {
+        let mut mock_client = MockSolanaRpcClient::new();
+        let costly_program = Pubkey::new_unique();
+        let broken_program = Pubkey::new_unique();
+        mock_client.set_program_behavior(costly_program, ProgramBehavior::succeed(200_000));
+        mock_client.set_program_behavior(broken_program, ProgramBehavior::fail("custom program error: 0x1"));
+
+        let payer = Pubkey::new_unique();
+        let costly = Transaction::new_with_payer(
+            &[solana_sdk::instruction::Instruction::new_with_bytes(costly_program, &[], vec![])],
+            Some(&payer),
+        );
+        let result = mock_client.simulate_transaction(&costly).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.compute_units_consumed, DEFAULT_COMPUTE_UNITS_PER_TRANSACTION + 200_000);
+
+        let broken = Transaction::new_with_payer(
+            &[solana_sdk::instruction::Instruction::new_with_bytes(broken_program, &[], vec![])],
+            Some(&payer),
+        );
+        let result = mock_client.simulate_transaction(&broken).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("custom program error: 0x1"));
+    }
+
+    #[tokio::test]
+    async fn test_compute_unit_limit_exceeded() {
+        let mut mock_client = MockSolanaRpcClient::new();
+        mock_client.set_compute_units(10_000);
+        mock_client.set_compute_unit_limit(10_000);
+        let keypair = Keypair::new();
+
+        let at_limit = signed_transfer(&keypair, &Pubkey::new_unique());
+        assert!(mock_client.simulate_transaction(&at_limit).await.unwrap().success);
+
+        mock_client.set_compute_unit_limit(9_999);
+        let result = mock_client.simulate_transaction(&at_limit).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.compute_units_consumed, 9_999);
+    }
+
+    #[tokio::test]
+    async fn test_predicate_failures_and_rpc_errors() {
+        let mut mock_client = MockSolanaRpcClient::new();
+        let blocked = Pubkey::new_unique();
+        let unreachable = Pubkey::new_unique();
+        mock_client.fail_when(move |tx| tx.message.account_keys.contains(&blocked), "account is frozen");
+        mock_client.rpc_error_when(move |tx| tx.message.account_keys.contains(&unreachable), "connection reset");
+        let keypair = Keypair::new();
+
+        let result = mock_client.simulate_transaction(&signed_transfer(&keypair, &blocked)).await.unwrap();
+        assert_eq!(result.error.as_deref(), Some("account is frozen"));
+        assert!(mock_client.simulate_transaction(&signed_transfer(&keypair, &unreachable)).await.is_err());
+        assert!(mock_client.simulate_transaction(&signed_transfer(&keypair, &Pubkey::new_unique())).await.unwrap().success);
+    }
+
+    #[tokio::test]
+    async fn test_latency_injection() {
+        let mut mock_client = MockSolanaRpcClient::new();
+        mock_client.set_latency(Duration::from_millis(50));
+        let transaction = signed_transfer(&Keypair::new(), &Pubkey::new_unique());
+
+        let timed_out = tokio::time::timeout(
+            Duration::from_millis(10),
+            mock_client.simulate_transaction(&transaction),
+        ).await;
+        assert!(timed_out.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_conditions_enforced() {
+        use crate::bundle::PostCondition;
+
+        let mut mock_client = MockSolanaRpcClient::new();
+        let user = Keypair::new();
+        let tip_account = Pubkey::new_unique();
+        mock_client.add_account(user.pubkey(), Account::new(10_000, 0, &system_program::id()));
+        let simulator = TransactionSimulator::new(Box::new(mock_client));
+
+        let transfer = |lamports: u64| {
+            let mut tx = Transaction::new_with_payer(
+                &[system_instruction::transfer(&user.pubkey(), &tip_account, lamports)],
+                Some(&user.pubkey()),
+            );
+            tx.sign(&[&user], Hash::new_unique());
+            tx
+        };
+
+        let paying = Bundle::new(vec![transfer(1_000)], 1_000, user.pubkey().to_string())
+            .with_post_conditions(vec![
+                PostCondition::TipPaid { tip_account },
+                PostCondition::MaxBalanceDecrease { account: user.pubkey(), lamports: 1_000 },
+            ]);
+        assert!(simulator.validate_bundle(&paying).await.is_ok());
+
+        let draining = Bundle::new(vec![transfer(600), transfer(600)], 1_000, user.pubkey().to_string())
+            .with_post_conditions(vec![
+                PostCondition::MaxBalanceDecrease { account: user.pubkey(), lamports: 1_000 },
+            ]);
+        assert!(matches!(
+            simulator.validate_bundle(&draining).await,
+            Err(BundleError::PostConditionViolated(_))
+        ));
+
+        let underpaying = Bundle::new(vec![transfer(500)], 1_000, user.pubkey().to_string())
+            .with_post_conditions(vec![PostCondition::MinBalanceIncrease { account: tip_account, lamports: 1_000 }]);
+        assert!(matches!(
+            simulator.validate_bundle(&underpaying).await,
+            Err(BundleError::PostConditionViolated(_))
+        ));
+
+        // The tip account must receive the tip the bundle bid, not just anything
+        let overbidding = Bundle::new(vec![transfer(500)], 1_000, user.pubkey().to_string())
+            .with_post_conditions(vec![PostCondition::TipPaid { tip_account }]);
+        assert!(matches!(
+            simulator.validate_bundle(&overbidding).await,
+            Err(BundleError::PostConditionViolated(_))
+        ));
+
+        // Thresholds beyond i64 never wrap around into passing
+        let unreachable = Bundle::new(vec![transfer(1_000)], 1_000, user.pubkey().to_string())
+            .with_post_conditions(vec![PostCondition::MinBalanceIncrease { account: tip_account, lamports: u64::MAX }]);
+        assert!(matches!(
+            simulator.validate_bundle(&unreachable).await,
+            Err(BundleError::PostConditionViolated(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_reports_balance_changes() {
+        let mut mock_client = MockSolanaRpcClient::new();
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        mock_client.add_account(payer.pubkey(), Account::new(500, 0, &system_program::id()));
+
+        let result = mock_client.simulate_transaction(&signed_transfer(&payer, &recipient)).await.unwrap();
+        assert!(result.balance_changes.contains(&BalanceChange { pubkey: recipient, lamports_delta: 100 }));
+        assert!(result.balance_changes.contains(&BalanceChange { pubkey: payer.pubkey(), lamports_delta: -100 }));
+
+        let mut overdraft = Transaction::new_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &recipient, 501)],
+            Some(&payer.pubkey()),
+        );
+        overdraft.sign(&[&payer], Hash::new_unique());
+        assert!(!mock_client.simulate_transaction(&overdraft).await.unwrap().success);
+    }
 }
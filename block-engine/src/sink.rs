@@ -0,0 +1,739 @@
+use crate::block_assembler::MockValidatorClient;
+use crate::leader_schedule::LeaderSchedule;
+use crate::ordered_block::OrderedBlock;
+use crate::validator::{ValidatorNetwork, ValidatorResponse, ValidatorStats};
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+/// Acknowledgment body returned by a validator's `/submit_block` endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockAck {
+    pub status: String,
+    #[serde(default)]
+    pub slot: Option<u64>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Identity of the validator that signed the acknowledgment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl BlockAck {
+    pub fn accepted(slot: u64) -> Self {
+        Self {
+            status: "accepted".to_string(),
+            slot: Some(slot),
+            reason: None,
+            validator: None,
+            signature: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SubmissionError {
+    /// The validator read the block and refused it; retrying will not help
+    #[error("block rejected: {0}")]
+    Rejected(String),
+    #[error("submission timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("invalid acknowledgment: {0}")]
+    InvalidAck(String),
+}
+
+impl SubmissionError {
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, SubmissionError::Rejected(_))
+    }
+}
+
+/// Final result of delivering one window's block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum SubmissionOutcome {
+    Delivered(BlockAck),
+    Rejected { reason: String },
+    Failed { reason: String },
+}
+
+impl From<Result<BlockAck, SubmissionError>> for SubmissionOutcome {
+    fn from(result: Result<BlockAck, SubmissionError>) -> Self {
+        match result {
+            Ok(ack) => SubmissionOutcome::Delivered(ack),
+            Err(SubmissionError::Rejected(reason)) => SubmissionOutcome::Rejected { reason },
+            Err(e) => SubmissionOutcome::Failed { reason: e.to_string() },
+        }
+    }
+}
+
+/// Destination for blocks built by the engine
+#[async_trait]
+pub trait BlockSink: Send + Sync {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError>;
+
+    /// Per-validator counters, for sinks that track them
+    fn validator_stats(&self) -> Vec<ValidatorStats> {
+        Vec::new()
+    }
+}
+
+/// Posts blocks as JSON to a validator's `/submit_block` endpoint and parses its acknowledgment
+pub struct HttpBlockSink {
+    client: Client,
+    url: String,
+    timeout: Duration,
+}
+
+impl HttpBlockSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.into(),
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Map an HTTP response onto an acknowledgment, checking it refers to the submitted window
+fn parse_ack(window_id: u64, status: StatusCode, body: &str) -> Result<BlockAck, SubmissionError> {
+    let ack = serde_json::from_str::<BlockAck>(body);
+
+    // Timeouts and rate limits are worth another attempt
+    if status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(SubmissionError::Transport(format!("HTTP {}", status)));
+    }
+    if status.is_client_error() {
+        let reason = ack
+            .ok()
+            .and_then(|ack| ack.reason)
+            .unwrap_or_else(|| format!("HTTP {}", status));
+        return Err(SubmissionError::Rejected(reason));
+    }
+    if !status.is_success() {
+        return Err(SubmissionError::Transport(format!("HTTP {}", status)));
+    }
+
+    let ack = ack.map_err(|e| SubmissionError::InvalidAck(e.to_string()))?;
+    match ack.status.as_str() {
+        "accepted" => match ack.slot {
+            Some(slot) if slot != window_id => Err(SubmissionError::InvalidAck(format!(
+                "acknowledged slot {} for window {}",
+                slot, window_id
+            ))),
+            _ => Ok(ack),
+        },
+        "rejected" => Err(SubmissionError::Rejected(
+            ack.reason.unwrap_or_else(|| "no reason given".to_string()),
+        )),
+        other => Err(SubmissionError::InvalidAck(format!("unknown status `{}`", other))),
+    }
+}
+
+#[async_trait]
+impl BlockSink for HttpBlockSink {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(block)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    SubmissionError::Timeout(self.timeout)
+                } else {
+                    SubmissionError::Transport(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(|e| {
+            if e.is_timeout() {
+                SubmissionError::Timeout(self.timeout)
+            } else {
+                SubmissionError::Transport(e.to_string())
+            }
+        })?;
+        parse_ack(block.window_id, status, &body)
+    }
+}
+
+/// Blocks that could not be delivered, kept as one JSON file per window for later replay
+#[derive(Debug, Clone)]
+pub struct FailedBlockStore {
+    dir: PathBuf,
+}
+
+impl FailedBlockStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn persist(&self, block: &OrderedBlock) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("window-{}.json", block.window_id));
+        std::fs::write(&path, serde_json::to_vec_pretty(block)?)?;
+        Ok(path)
+    }
+
+    /// All persisted blocks ordered by window
+    pub fn load_all(&self) -> anyhow::Result<Vec<OrderedBlock>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut blocks = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                blocks.push(serde_json::from_slice::<OrderedBlock>(&std::fs::read(&path)?)?);
+            }
+        }
+        blocks.sort_by_key(|b| b.window_id);
+        Ok(blocks)
+    }
+}
+
+/// Bounded exponential backoff between delivery attempts
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Retries transient submission failures and persists blocks that never got through
+pub struct RetryingBlockSink {
+    inner: Box<dyn BlockSink>,
+    policy: RetryPolicy,
+    deadline: Option<Duration>,
+    failed_blocks: Option<FailedBlockStore>,
+}
+
+impl RetryingBlockSink {
+    pub fn new(inner: Box<dyn BlockSink>, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            deadline: None,
+            failed_blocks: None,
+        }
+    }
+
+    /// Give up on a block once all attempts and backoffs together have taken `deadline`, e.g. one
+    /// window, so a stalled validator cannot hold up the windows after it
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_failed_block_store(mut self, store: FailedBlockStore) -> Self {
+        self.failed_blocks = Some(store);
+        self
+    }
+}
+
+#[async_trait]
+impl BlockSink for RetryingBlockSink {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let attempts = async {
+            let mut attempt = 1;
+            loop {
+                match self.inner.submit_block(block).await {
+                    Ok(ack) => return Ok(ack),
+                    Err(e) if e.is_retryable() && attempt < max_attempts => {
+                        let backoff = self.policy.backoff(attempt);
+                        warn!(
+                            "Attempt {}/{} to submit window {} failed: {}; retrying in {:?}",
+                            attempt, max_attempts, block.window_id, e, backoff
+                        );
+                        sleep(backoff).await;
+                        attempt += 1;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        let result = match self.deadline {
+            Some(deadline) => timeout(deadline, attempts).await.unwrap_or_else(|_| {
+                warn!("Gave up submitting window {} after {:?}", block.window_id, deadline);
+                Err(SubmissionError::Timeout(deadline))
+            }),
+            None => attempts.await,
+        };
+        let error = match result {
+            Ok(ack) => return Ok(ack),
+            Err(e) => e,
+        };
+
+        if error.is_retryable() {
+            if let Some(store) = &self.failed_blocks {
+                match store.persist(block) {
+                    Ok(path) => info!("Saved undelivered block for window {} to {}", block.window_id, path.display()),
+                    Err(e) => warn!("Failed to persist undelivered block for window {}: {}", block.window_id, e),
+                }
+            }
+        }
+        Err(error)
+    }
+
+    fn validator_stats(&self) -> Vec<ValidatorStats> {
+        self.inner.validator_stats()
+    }
+}
+
+/// Submits every block to all inner sinks; succeeds if at least one accepts it
+pub struct FanOutBlockSink {
+    sinks: Vec<Box<dyn BlockSink>>,
+}
+
+impl FanOutBlockSink {
+    pub fn new(sinks: Vec<Box<dyn BlockSink>>) -> Self {
+        Self { sinks }
+    }
+}
+
+/// Submit to every sink concurrently, succeeding with the first acknowledgment
+async fn fan_out(sinks: &[&dyn BlockSink], block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+    let mut submissions: FuturesUnordered<_> = sinks
+        .iter()
+        .enumerate()
+        .map(|(i, sink)| async move { (i, sink.submit_block(block).await) })
+        .collect();
+    let mut errors = Vec::new();
+    while let Some((i, result)) = submissions.next().await {
+        match result {
+            Ok(ack) => return Ok(ack),
+            Err(e) => {
+                warn!("Sink {} failed to accept block for window {}: {}", i, block.window_id, e);
+                errors.push(e);
+            }
+        }
+    }
+
+    Err(combine_errors(errors))
+}
+
+/// The error for a block no sink accepted. Only a unanimous rejection is final; otherwise a retry
+/// may still reach someone.
+fn combine_errors(mut errors: Vec<SubmissionError>) -> SubmissionError {
+    if !errors.is_empty() && errors.iter().all(|e| !e.is_retryable()) {
+        return errors.swap_remove(0);
+    }
+    let reasons: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    SubmissionError::Transport(format!("no sink accepted block: {}", reasons.join("; ")))
+}
+
+#[async_trait]
+impl BlockSink for FanOutBlockSink {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+        let sinks: Vec<&dyn BlockSink> = self.sinks.iter().map(|s| s.as_ref()).collect();
+        fan_out(&sinks, block).await
+    }
+
+    fn validator_stats(&self) -> Vec<ValidatorStats> {
+        self.sinks.iter().flat_map(|s| s.validator_stats()).collect()
+    }
+}
+
+/// Sends every block to every configured validator, so each ledger sees an unbroken chain, but
+/// only the scheduled leader for its slot and the leader after it decide whether it was accepted
+pub struct LeaderTargetedSink {
+    schedule: LeaderSchedule,
+    leaders: HashMap<Pubkey, Box<dyn BlockSink>>,
+}
+
+impl LeaderTargetedSink {
+    pub fn new(schedule: LeaderSchedule) -> Self {
+        Self {
+            schedule,
+            leaders: HashMap::new(),
+        }
+    }
+
+    /// Deliver blocks to `sink`, counting its answer for `identity`'s slots
+    pub fn with_leader(mut self, identity: Pubkey, sink: Box<dyn BlockSink>) -> Self {
+        self.leaders.insert(identity, sink);
+        self
+    }
+
+    pub fn schedule(&self) -> &LeaderSchedule {
+        &self.schedule
+    }
+}
+
+#[async_trait]
+impl BlockSink for LeaderTargetedSink {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+        let targets = self.schedule.target_leaders(block.block.slot);
+        // Wait for every delivery: a validator whose request was cancelled would miss this block
+        // and refuse every later one as not building on its ledger
+        let deliveries = self
+            .leaders
+            .iter()
+            .map(|(identity, sink)| async move { (*identity, sink.submit_block(block).await) });
+        let mut ack = None;
+        let mut errors = Vec::new();
+        for (identity, result) in join_all(deliveries).await {
+            let is_target = targets.contains(&identity);
+            match result {
+                Ok(leader_ack) if is_target => {
+                    ack.get_or_insert(leader_ack);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("Validator {} failed to accept block for window {}: {}", identity, block.window_id, e);
+                    if is_target {
+                        errors.push(e);
+                    }
+                }
+            }
+        }
+
+        match ack {
+            Some(ack) => Ok(ack),
+            None if errors.is_empty() => {
+                let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+                Err(SubmissionError::Transport(format!(
+                    "no endpoint for the leaders of slot {} ({})",
+                    block.block.slot,
+                    targets.join(", ")
+                )))
+            }
+            None => Err(combine_errors(errors)),
+        }
+    }
+
+    fn validator_stats(&self) -> Vec<ValidatorStats> {
+        self.leaders.values().flat_map(|s| s.validator_stats()).collect()
+    }
+}
+
+#[async_trait]
+impl BlockSink for MockValidatorClient {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+        MockValidatorClient::submit_block(self, block.block.clone())
+            .await
+            .map_err(|e| SubmissionError::Transport(e.to_string()))?;
+        Ok(BlockAck::accepted(block.window_id))
+    }
+}
+
+#[async_trait]
+impl BlockSink for ValidatorNetwork {
+    async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+        let outcome = self.submit_block_to_network(block.block.clone()).await;
+        if outcome.is_confirmed() {
+            return Ok(BlockAck::accepted(block.window_id));
+        }
+        let reason = format!(
+            "quorum not reached: {}/{} stake accepted, {} rejected or unresponsive",
+            outcome.accepted_stake, outcome.total_stake, outcome.rejected_stake
+        );
+        // Timeouts and errors may clear up on a retry; explicit rejections will not
+        let refused = outcome
+            .votes
+            .iter()
+            .all(|v| matches!(v.response, ValidatorResponse::Accepted { .. } | ValidatorResponse::Rejected { .. } | ValidatorResponse::Pending));
+        Err(if refused { SubmissionError::Rejected(reason) } else { SubmissionError::Transport(reason) })
+    }
+
+    fn validator_stats(&self) -> Vec<ValidatorStats> {
+        self.get_network_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_assembler::Block;
+    use crate::leader_schedule::StakedValidator;
+    use solana_sdk::hash::Hash;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn test_block(window_id: u64) -> OrderedBlock {
+        let block = Block {
+            slot: window_id,
+            parent_hash: Hash::default(),
+            blockhash: Hash::new_unique(),
+            transactions: vec![],
+            bundles: vec![],
+            timestamp: 0,
+            leader_pubkey: Pubkey::default(),
+            total_fees: 0,
+            total_tips: 0,
+        };
+        OrderedBlock::new(window_id, block).unwrap()
+    }
+
+    /// Serve a single canned HTTP response after an optional delay
+    async fn serve_once(status_line: &'static str, body: String, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 64 * 1024];
+            let _ = socket.read(&mut buf).await;
+            sleep(delay).await;
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status_line,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+        format!("http://{}/submit_block", addr)
+    }
+
+    /// Fails with the given error for the first `failures` submissions
+    struct FlakySink {
+        failures: u32,
+        error: SubmissionError,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl BlockSink for FlakySink {
+        async fn submit_block(&self, block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                return Err(self.error.clone());
+            }
+            Ok(BlockAck::accepted(block.window_id))
+        }
+    }
+
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_sink_parses_acknowledgment() {
+        let url = serve_once("200 OK", r#"{"status":"accepted","slot":42}"#.to_string(), Duration::ZERO).await;
+        let ack = HttpBlockSink::new(url).submit_block(&test_block(42)).await.unwrap();
+        assert_eq!(ack, BlockAck::accepted(42));
+
+        let url = serve_once(
+            "200 OK",
+            r#"{"status":"rejected","reason":"bad ordering hash"}"#.to_string(),
+            Duration::ZERO,
+        )
+        .await;
+        let err = HttpBlockSink::new(url).submit_block(&test_block(42)).await.unwrap_err();
+        assert_eq!(err, SubmissionError::Rejected("bad ordering hash".to_string()));
+
+        let url = serve_once("500 Internal Server Error", String::new(), Duration::ZERO).await;
+        let err = HttpBlockSink::new(url).submit_block(&test_block(42)).await.unwrap_err();
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_ack_for_wrong_slot_is_invalid() {
+        let err = parse_ack(7, StatusCode::OK, r#"{"status":"accepted","slot":8}"#).unwrap_err();
+        assert!(matches!(err, SubmissionError::InvalidAck(_)));
+        let err = parse_ack(7, StatusCode::OK, "accepted").unwrap_err();
+        assert!(matches!(err, SubmissionError::InvalidAck(_)));
+        let err = parse_ack(7, StatusCode::BAD_REQUEST, "").unwrap_err();
+        assert_eq!(err, SubmissionError::Rejected("HTTP 400 Bad Request".to_string()));
+        assert!(parse_ack(7, StatusCode::TOO_MANY_REQUESTS, "").unwrap_err().is_retryable());
+        assert!(parse_ack(7, StatusCode::REQUEST_TIMEOUT, "").unwrap_err().is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_http_sink_times_out() {
+        let url = serve_once("200 OK", r#"{"status":"accepted"}"#.to_string(), Duration::from_millis(500)).await;
+        let sink = HttpBlockSink::new(url).with_timeout(Duration::from_millis(50));
+        let err = sink.submit_block(&test_block(1)).await.unwrap_err();
+        assert_eq!(err, SubmissionError::Timeout(Duration::from_millis(50)));
+    }
+
+    #[tokio::test]
+    async fn test_retrying_sink_recovers_from_transient_failures() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakySink {
+            failures: 2,
+            error: SubmissionError::Transport("connection refused".to_string()),
+            calls: Arc::clone(&calls),
+        };
+        let sink = RetryingBlockSink::new(Box::new(inner), fast_retries(3));
+
+        assert!(sink.submit_block(&test_block(5)).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_sink_does_not_retry_rejections() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakySink {
+            failures: 10,
+            error: SubmissionError::Rejected("stale parent".to_string()),
+            calls: Arc::clone(&calls),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let store = FailedBlockStore::new(dir.path());
+        let sink = RetryingBlockSink::new(Box::new(inner), fast_retries(3)).with_failed_block_store(store.clone());
+
+        let outcome = SubmissionOutcome::from(sink.submit_block(&test_block(5)).await);
+        assert_eq!(outcome, SubmissionOutcome::Rejected { reason: "stale parent".to_string() });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(store.load_all().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_persist_block() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let inner = FlakySink {
+            failures: 10,
+            error: SubmissionError::Timeout(Duration::from_millis(10)),
+            calls: Arc::clone(&calls),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let store = FailedBlockStore::new(dir.path().join("failed"));
+        let sink = RetryingBlockSink::new(Box::new(inner), fast_retries(4)).with_failed_block_store(store.clone());
+
+        let block = test_block(9);
+        let outcome = SubmissionOutcome::from(sink.submit_block(&block).await);
+
+        assert!(matches!(outcome, SubmissionOutcome::Failed { .. }));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        let saved = store.load_all().unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].ordered_hash, block.ordered_hash);
+    }
+
+    #[tokio::test]
+    async fn test_leader_sink_targets_current_and_next_leader() {
+        let staked: Vec<StakedValidator> = (0..4).map(|_| StakedValidator::new(Pubkey::new_unique(), 1)).collect();
+        let schedule = LeaderSchedule::new(staked.clone());
+        let targets = schedule.target_leaders(6);
+        let build = |non_target_failures: u32| {
+            let mut sink = LeaderTargetedSink::new(schedule.clone());
+            let mut calls = Vec::new();
+            for validator in &staked {
+                let counter = Arc::new(AtomicU32::new(0));
+                calls.push(Arc::clone(&counter));
+                let failures = if targets.contains(&validator.identity) { 0 } else { non_target_failures };
+                let error = SubmissionError::Rejected("parent mismatch".to_string());
+                sink = sink.with_leader(validator.identity, Box::new(FlakySink { failures, error, calls: counter }));
+            }
+            (sink, calls)
+        };
+
+        // Every validator gets the block once; rejections from non-leaders do not fail it
+        let (sink, calls) = build(10);
+        sink.submit_block(&test_block(6)).await.unwrap();
+        assert!(calls.iter().all(|counter| counter.load(Ordering::SeqCst) == 1));
+
+        // Non-leaders accepting cannot stand in for the leaders
+        let (mut sink, _) = build(0);
+        for leader in &targets {
+            let error = SubmissionError::Rejected("parent mismatch".to_string());
+            let calls = Arc::new(AtomicU32::new(0));
+            sink = sink.with_leader(*leader, Box::new(FlakySink { failures: 10, error, calls }));
+        }
+        let error = sink.submit_block(&test_block(6)).await.unwrap_err();
+        assert!(!error.is_retryable());
+
+        let unreachable = LeaderTargetedSink::new(schedule);
+        let error = unreachable.submit_block(&test_block(6)).await.unwrap_err();
+        assert!(error.is_retryable() && error.to_string().contains("no endpoint"));
+    }
+
+    /// Never answers
+    struct StalledSink;
+
+    #[async_trait]
+    impl BlockSink for StalledSink {
+        async fn submit_block(&self, _block: &OrderedBlock) -> Result<BlockAck, SubmissionError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrying_sink_gives_up_at_its_deadline() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FailedBlockStore::new(dir.path());
+        let sink = RetryingBlockSink::new(Box::new(StalledSink), fast_retries(3))
+            .with_deadline(Duration::from_millis(50))
+            .with_failed_block_store(store.clone());
+
+        let started = std::time::Instant::now();
+        let error = sink.submit_block(&test_block(4)).await.unwrap_err();
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(matches!(error, SubmissionError::Timeout(_)));
+        assert_eq!(store.load_all().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_does_not_wait_for_slow_sinks() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let fast = FlakySink { failures: 0, error: SubmissionError::Transport(String::new()), calls };
+        let sink = FanOutBlockSink::new(vec![Box::new(StalledSink), Box::new(fast)]);
+
+        let ack = tokio::time::timeout(Duration::from_secs(1), sink.submit_block(&test_block(3))).await;
+        assert_eq!(ack.unwrap().unwrap(), BlockAck::accepted(3));
+    }
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(8), Duration::from_millis(500));
+    }
+}
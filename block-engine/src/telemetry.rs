@@ -0,0 +1,206 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{config, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use serde::{Deserialize, Serialize};
+use tracing::{warn, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+use anyhow::Result;
+
+/// Service name reported to the OpenTelemetry collector
+pub const SERVICE_NAME: &str = "block-engine";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per event, including the fields of every enclosing span
+    Json,
+}
+
+/// Flushes buffered spans to the collector when dropped; keep it alive for the life of the process
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            for result in provider.force_flush() {
+                if let Err(e) = result {
+                    warn!("Failed to flush spans: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Batch-export spans over OTLP/HTTP to `{endpoint}/v1/traces`. Must be called inside a Tokio runtime.
+pub fn otlp_tracer_provider(endpoint: &str) -> Result<TracerProvider> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(endpoint.trim_end_matches('/'))
+        .build_span_exporter()?;
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_config(config().with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            SERVICE_NAME,
+        )])))
+        .build())
+}
+
+/// Log formatting layer writing to `writer`
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
+/// Install the global subscriber: logs to stdout in `format`, plus OTLP export when an endpoint is given
+pub fn init_telemetry(level: Level, format: LogFormat, otlp_endpoint: Option<&str>) -> Result<TelemetryGuard> {
+    let provider = otlp_endpoint.map(otlp_tracer_provider).transpose()?;
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+
+    tracing_subscriber::registry()
+        .with(fmt_layer(format, std::io::stdout))
+        .with(otel_layer)
+        .with(LevelFilter::from_level(level))
+        .try_init()?;
+
+    Ok(TelemetryGuard { provider })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tracing::info_span;
+
+    /// Request lines and raw bodies received by the collector stand-in
+    type Captured = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    /// Minimal OTLP/HTTP collector: records every export request and answers 200
+    async fn collector_stand_in() -> (String, Captured) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let captured: Captured = Arc::default();
+
+        let requests = Arc::clone(&captured);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut data = Vec::new();
+                let mut buf = [0u8; 8192];
+                let (header_end, content_length) = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..n]);
+                    if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let headers = String::from_utf8_lossy(&data[..pos]).to_lowercase();
+                        let length = headers
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .and_then(|v| v.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        break (pos + 4, length);
+                    }
+                };
+                while data.len() < header_end + content_length {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..n]);
+                }
+
+                let request_line = String::from_utf8_lossy(&data[..header_end]).lines().next().unwrap_or_default().to_string();
+                let body = data[header_end..header_end + content_length].to_vec();
+                requests.lock().unwrap().push((request_line, body));
+
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+
+        (endpoint, captured)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_spans_exported_to_otlp_collector() {
+        let (endpoint, captured) = collector_stand_in().await;
+        let provider = otlp_tracer_provider(&endpoint).unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let window = info_span!("process_window", window_id = 42u64, slot = 42u64);
+            let _window = window.enter();
+            let bundle = info_span!("bundle", bundle_id = "b-1234", searcher = "searcher_a");
+            let _bundle = bundle.enter();
+        });
+
+        tokio::task::spawn_blocking(move || provider.force_flush()).await.unwrap();
+
+        let requests = captured.lock().unwrap().clone();
+        assert!(!requests.is_empty());
+        assert!(requests.iter().all(|(line, _)| line.starts_with("POST /v1/traces")));
+        let body: Vec<u8> = requests.into_iter().flat_map(|(_, body)| body).collect();
+        let contains = |needle: &str| body.windows(needle.len()).any(|w| w == needle.as_bytes());
+        assert!(contains("process_window"));
+        assert!(contains("bundle_id"));
+        assert!(contains("b-1234"));
+        assert!(contains(SERVICE_NAME));
+    }
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_logs_carry_span_fields() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(fmt_layer(LogFormat::Json, move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("bundle", bundle_id = "b-5678", window_id = 7u64);
+            let _entered = span.enter();
+            tracing::info!("bundle admitted to pool");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(line["fields"]["message"], "bundle admitted to pool");
+        assert_eq!(line["span"]["bundle_id"], "b-5678");
+        assert_eq!(line["spans"][0]["window_id"], 7);
+    }
+}
@@ -40,7 +40,7 @@ impl TransactionPool {
         }
 
         // Validate bundle before adding
-        bundle.validate().map_err(|e| PoolError::InvalidBundle(e.to_string()))?;
+        bundle.validate().map_err(PoolError::InvalidBundle)?;
 
         let bundle_id = bundle.id;
         bundles.insert(bundle_id, bundle);
@@ -85,6 +85,23 @@ impl TransactionPool {
             .collect()
     }
 
+    /// Remove and return every pending bundle in arrival order
+    pub fn drain_pending(&self) -> Vec<Bundle> {
+        let mut bundles = self.bundles.write().unwrap();
+        let mut queue = self.pending_queue.write().unwrap();
+
+        let drained: Vec<Bundle> = queue
+            .drain(..)
+            .filter_map(|id| bundles.remove(&id))
+            .collect();
+
+        for bundle in &drained {
+            let _ = self.event_sender.send(PoolEvent::BundleRemoved(bundle.id));
+        }
+
+        drained
+    }
+
     pub fn get_bundles_by_tip_range(&self, min_tip: u64, max_tip: u64) -> Vec<Bundle> {
         let bundles = self.bundles.read().unwrap();
         
@@ -142,11 +159,22 @@ pub enum PoolError {
     #[error("Transaction pool is full")]
     PoolFull,
     #[error("Invalid bundle: {0}")]
-    InvalidBundle(String),
+    InvalidBundle(crate::bundle::BundleError),
     #[error("Bundle not found")]
     BundleNotFound,
 }
 
+impl PoolError {
+    /// Stable variant name, used as a metrics label; invalid bundles report the `BundleError` variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PoolError::PoolFull => "pool_full",
+            PoolError::InvalidBundle(e) => e.kind(),
+            PoolError::BundleNotFound => "bundle_not_found",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +237,21 @@ mod tests {
         assert_eq!(pending.len(), 3);
     }
 
+    #[test]
+    fn test_drain_pending() {
+        let pool = TransactionPool::new(10);
+        let first = create_test_bundle(1000);
+        let first_id = first.id;
+        pool.add_bundle(first).unwrap();
+        pool.add_bundle(create_test_bundle(2000)).unwrap();
+
+        let drained = pool.drain_pending();
+        assert_eq!(drained.len(), 2);
+        assert_eq!(drained[0].id, first_id);
+        assert_eq!(pool.get_stats().total_bundles, 0);
+        assert!(pool.drain_pending().is_empty());
+    }
+
     #[test]
     fn test_get_bundles_by_tip_range() {
         let pool = TransactionPool::new(10);
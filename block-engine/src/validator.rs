@@ -1,35 +1,112 @@
-use crate::block_assembler::{Block, BlockValidationError};
-use solana_sdk::signature::Signature;
-use std::sync::{Arc, RwLock};
-use tokio::time::{sleep, Duration};
+use crate::block_assembler::{validate_bundle_layout, Block, BlockValidationError};
+use crate::block_tree::{BlockTree, Reorg, TreeUpdate};
+use crate::leader_schedule::{LeaderSchedule, StakedValidator};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout, Duration, Instant};
 use tracing::{info, warn, error};
 use anyhow::Result;
 use uuid::Uuid;
 
-/// Mock validator that simulates block verification and inclusion
+/// Misbehaviour a `MockValidator` can be configured with on top of its `failure_rate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "fault", rename_all = "snake_case")]
+pub enum FaultModel {
+    /// Reject the `n`th block received, counting from 1
+    FailNthBlock { n: u64 },
+    /// Reject every block for these slots
+    FailSlots { slots: Vec<u64> },
+    /// With this probability, never answer; callers are expected to time out
+    Timeout { probability: f64 },
+    /// With this probability, take `extra_delay_ms` longer to answer
+    SlowResponse { probability: f64, extra_delay_ms: u64 },
+    /// Accept blocks even when they fail validation
+    Byzantine,
+}
+
+/// Seeded randomness and the block counter it is drawn against
+#[derive(Debug)]
+struct FaultState {
+    rng: StdRng,
+    blocks_received: u64,
+}
+
+/// Everything decided about one submission before it is processed
+struct SubmissionPlan {
+    extra_delay_ms: u64,
+    hang: bool,
+    injected_failure: Option<String>,
+    signature: Signature,
+}
+
+/// Mock validator that simulates block verification and inclusion. Every random decision is drawn
+/// from an RNG seeded with `seed()`, so a run can be replayed by seeding the same way.
 #[derive(Debug, Clone)]
 pub struct MockValidator {
     pub validator_id: String,
+    /// Identity the leader schedule refers to
+    pub identity: Pubkey,
     pub accepted_blocks: Arc<RwLock<Vec<Block>>>,
+    /// Accepted blocks linked by parent hash, rooted at `Hash::default()`
+    pub block_tree: Arc<RwLock<BlockTree>>,
+    /// Canonical chain switches, oldest first
+    pub reorgs: Arc<RwLock<Vec<Reorg>>>,
     pub rejected_blocks: Arc<RwLock<Vec<(Block, String)>>>,
     pub verification_delay_ms: u64,
     pub failure_rate: f64, // 0.0 = never fail, 1.0 = always fail
     pub max_transactions_per_block: usize,
     pub max_compute_units_per_block: u64,
+    /// Voting weight in the network's quorum
+    pub stake: u64,
+    pub faults: Vec<FaultModel>,
+    seed: u64,
+    fault_state: Arc<Mutex<FaultState>>,
 }
 
 impl MockValidator {
     /// Create a new mock validator with default settings
     pub fn new() -> Self {
         Self {
-            validator_id: format!("validator_{}", Uuid::new_v4().to_string()[..8].to_string()),
+            validator_id: format!("validator_{}", &Uuid::new_v4().to_string()[..8]),
+            identity: Pubkey::new_unique(),
             accepted_blocks: Arc::new(RwLock::new(Vec::new())),
+            block_tree: Arc::new(RwLock::new(BlockTree::new(Hash::default()))),
+            reorgs: Arc::new(RwLock::new(Vec::new())),
             rejected_blocks: Arc::new(RwLock::new(Vec::new())),
             verification_delay_ms: 100,
             failure_rate: 0.0,
             max_transactions_per_block: 100,
             max_compute_units_per_block: 1_000_000,
+            stake: 1,
+            faults: Vec::new(),
+            seed: 0,
+            fault_state: Arc::new(Mutex::new(FaultState { rng: StdRng::seed_from_u64(0), blocks_received: 0 })),
         }
+        .seeded(rand::random())
+    }
+
+    /// Restart this validator's randomness and block count from `seed`
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.fault_state = Arc::new(Mutex::new(FaultState { rng: StdRng::seed_from_u64(seed), blocks_received: 0 }));
+        self
+    }
+
+    pub fn with_fault(mut self, fault: FaultModel) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
     /// Create a validator with custom failure rate
@@ -39,6 +116,13 @@ impl MockValidator {
         validator
     }
 
+    /// Create a validator with the given voting and leader-schedule weight
+    pub fn with_stake(stake: u64) -> Self {
+        let mut validator = Self::new();
+        validator.stake = stake;
+        validator
+    }
+
     /// Create a validator with custom verification delay
     pub fn with_verification_delay(delay_ms: u64) -> Self {
         let mut validator = Self::new();
@@ -64,21 +148,30 @@ impl MockValidator {
             block.bundles.len()
         );
 
+        let plan = self.plan_submission(block.slot);
+
         // Simulate verification delay
-        sleep(Duration::from_millis(self.verification_delay_ms)).await;
+        sleep(Duration::from_millis(self.verification_delay_ms + plan.extra_delay_ms)).await;
+        if plan.hang {
+            warn!("Validator {} dropped block for slot {} without answering", self.validator_id, block.slot);
+            std::future::pending::<()>().await;
+        }
 
         // Perform validation checks
         match self.validate_block(&block).await {
-            Ok(_) => {
-                // Simulate random failure based on failure rate
-                if self.should_fail() {
-                    let reason = "Random validation failure".to_string();
+            Ok(_) => match plan.injected_failure {
+                Some(reason) => {
                     self.reject_block(block, reason.clone()).await;
                     Ok(BlockSubmissionResult::Rejected { reason })
-                } else {
-                    let signature = self.accept_block(block).await;
-                    Ok(BlockSubmissionResult::Accepted { signature })
                 }
+                None => Ok(BlockSubmissionResult::Accepted { signature: self.accept_block(block, plan.signature).await }),
+            },
+            Err(validation_error) if self.faults.contains(&FaultModel::Byzantine) => {
+                warn!(
+                    "Byzantine validator {} accepting invalid block for slot {}: {}",
+                    self.validator_id, block.slot, validation_error
+                );
+                Ok(BlockSubmissionResult::Accepted { signature: self.accept_block(block, plan.signature).await })
             }
             Err(validation_error) => {
                 let reason = validation_error.to_string();
@@ -88,6 +181,40 @@ impl MockValidator {
         }
     }
 
+    /// Draw this submission's faults. The same number of values is drawn for every block, so the
+    /// sequence of decisions depends only on the seed and the order blocks arrive in.
+    fn plan_submission(&self, slot: u64) -> SubmissionPlan {
+        let mut state = self.fault_state.lock().unwrap();
+        state.blocks_received += 1;
+        let block_number = state.blocks_received;
+        let (failure_roll, timeout_roll, slow_roll): (f64, f64, f64) = (state.rng.gen(), state.rng.gen(), state.rng.gen());
+        let mut signature = [0u8; 64];
+        state.rng.fill_bytes(&mut signature);
+
+        let mut plan = SubmissionPlan {
+            extra_delay_ms: 0,
+            hang: false,
+            injected_failure: (failure_roll < self.failure_rate).then(|| "Random validation failure".to_string()),
+            signature: Signature::from(signature),
+        };
+        for fault in &self.faults {
+            match fault {
+                FaultModel::FailNthBlock { n } if *n == block_number => {
+                    plan.injected_failure = Some(format!("Injected failure on block #{}", block_number));
+                }
+                FaultModel::FailSlots { slots } if slots.contains(&slot) => {
+                    plan.injected_failure = Some(format!("Injected failure for slot {}", slot));
+                }
+                FaultModel::Timeout { probability } if timeout_roll < *probability => plan.hang = true,
+                FaultModel::SlowResponse { probability, extra_delay_ms } if slow_roll < *probability => {
+                    plan.extra_delay_ms += extra_delay_ms;
+                }
+                _ => {}
+            }
+        }
+        plan
+    }
+
     /// Validate the block structure and contents
     async fn validate_block(&self, block: &Block) -> Result<(), BlockValidationError> {
         info!(
@@ -118,16 +245,22 @@ impl MockValidator {
             return Err(BlockValidationError::TooManyComputeUnits);
         }
 
-        // Validate that all bundle transactions are included in the block
-        for bundle in &block.bundles {
-            for bundle_tx in &bundle.transactions {
-                if !block.transactions.contains(bundle_tx) {
-                    warn!(
-                        "❌ Block validation failed: missing transaction from bundle {}",
-                        bundle.id
-                    );
-                    return Err(BlockValidationError::MissingBundleTransaction);
-                }
+        // Bundles must land whole, in order and ranked, with tips that add up
+        if let Err(e) = validate_bundle_layout(block) {
+            warn!("Block validation failed: {}", e);
+            return Err(e);
+        }
+
+        // The block must build on one this validator already holds
+        {
+            let tree = self.block_tree.read().unwrap();
+            if tree.contains(&block.blockhash) {
+                warn!("Block validation failed: block {} already accepted", block.blockhash);
+                return Err(BlockValidationError::DuplicateBlock(block.blockhash));
+            }
+            if !tree.contains(&block.parent_hash) {
+                warn!("Block validation failed: unknown parent {}", block.parent_hash);
+                return Err(BlockValidationError::UnknownParent(block.parent_hash));
             }
         }
 
@@ -142,9 +275,8 @@ impl MockValidator {
     }
 
     /// Accept a block and record it
-    async fn accept_block(&self, block: Block) -> Signature {
-        let signature = Signature::new_unique();
-        
+    async fn accept_block(&self, block: Block, signature: Signature) -> Signature {
+
         info!(
             "🎉 Validator {} ACCEPTED block for slot {} with signature {}",
             self.validator_id,
@@ -172,6 +304,8 @@ impl MockValidator {
             );
         }
 
+        self.record_in_tree(&block);
+
         // Store accepted block
         let mut accepted = self.accepted_blocks.write().unwrap();
         accepted.push(block);
@@ -179,6 +313,48 @@ impl MockValidator {
         signature
     }
 
+    /// Link an accepted block into the tree, recording any reorg it causes
+    fn record_in_tree(&self, block: &Block) -> Option<Reorg> {
+        let update = self.block_tree.write().unwrap().insert(block.clone());
+        match update {
+            Ok(TreeUpdate::Reorg(reorg)) => {
+                warn!(
+                    "Validator {} reorged from {} to {}, orphaning {} blocks",
+                    self.validator_id,
+                    reorg.old_head,
+                    reorg.new_head,
+                    reorg.orphaned.len()
+                );
+                self.reorgs.write().unwrap().push(reorg.clone());
+                Some(reorg)
+            }
+            Ok(_) => None,
+            // Only a Byzantine validator gets here, having accepted a block it could not link
+            Err(e) => {
+                warn!("Validator {} could not link block for slot {}: {}", self.validator_id, block.slot, e);
+                None
+            }
+        }
+    }
+
+    /// Take a block another leader produced and the network already voted on, e.g. a competing
+    /// fork. It is validated like any other block but without the simulated delay or faults.
+    pub async fn import_block(&self, block: Block) -> Result<Option<Reorg>, BlockValidationError> {
+        self.validate_block(&block).await?;
+        let reorg = self.record_in_tree(&block);
+        self.accepted_blocks.write().unwrap().push(block);
+        Ok(reorg)
+    }
+
+    /// Tip of this validator's canonical chain
+    pub fn head(&self) -> Hash {
+        self.block_tree.read().unwrap().head()
+    }
+
+    pub fn get_reorgs(&self) -> Vec<Reorg> {
+        self.reorgs.read().unwrap().clone()
+    }
+
     /// Reject a block and record the reason
     async fn reject_block(&self, block: Block, reason: String) {
         error!(
@@ -200,26 +376,6 @@ impl MockValidator {
         rejected.push((block, reason));
     }
 
-    /// Determine if this submission should fail based on failure rate
-    fn should_fail(&self) -> bool {
-        if self.failure_rate <= 0.0 {
-            return false;
-        }
-        if self.failure_rate >= 1.0 {
-            return true;
-        }
-        
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
-        let mut hasher = DefaultHasher::new();
-        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos().hash(&mut hasher);
-        let random_value = (hasher.finish() % 1000) as f64 / 1000.0;
-        
-        random_value < self.failure_rate
-    }
-
     /// Get statistics about this validator's performance
     pub fn get_stats(&self) -> ValidatorStats {
         let accepted = self.accepted_blocks.read().unwrap();
@@ -254,6 +410,8 @@ impl MockValidator {
         let mut rejected = self.rejected_blocks.write().unwrap();
         accepted.clear();
         rejected.clear();
+        *self.block_tree.write().unwrap() = BlockTree::new(Hash::default());
+        self.reorgs.write().unwrap().clear();
         
         info!("🧹 Validator {} cleared all block history", self.validator_id);
     }
@@ -297,74 +455,407 @@ pub struct ValidatorStats {
     pub total_transactions_processed: u64,
 }
 
+/// Fraction of total stake that must accept a block, e.g. 2/3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumThreshold {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl QuorumThreshold {
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        assert!(denominator > 0 && numerator <= denominator, "quorum must be a fraction between 0 and 1");
+        Self { numerator, denominator }
+    }
+
+    pub fn two_thirds() -> Self {
+        Self::new(2, 3)
+    }
+
+    pub fn majority() -> Self {
+        Self::new(1, 2)
+    }
+
+    /// Whether `stake` out of `total_stake` meets the threshold; nothing is met without stake
+    pub fn is_met(&self, stake: u64, total_stake: u64) -> bool {
+        total_stake > 0 && stake as u128 * self.denominator as u128 >= total_stake as u128 * self.numerator as u128
+    }
+}
+
+impl Default for QuorumThreshold {
+    fn default() -> Self {
+        Self::two_thirds()
+    }
+}
+
+/// What one validator said about a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorResponse {
+    Accepted { signature: Signature },
+    Rejected { reason: String },
+    /// No answer within the network's per-validator timeout
+    TimedOut,
+    /// The submission itself failed
+    Errored { error: String },
+    /// Still outstanding when the outcome was decided
+    Pending,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorVote {
+    pub validator_id: String,
+    pub stake: u64,
+    pub response: ValidatorResponse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsensusStatus {
+    /// Accepting stake reached the quorum
+    Confirmed,
+    /// Too much stake rejected, failed or timed out for the quorum to be reachable
+    QuorumUnreachable,
+}
+
+/// Result of submitting a block to the network
+#[derive(Debug, Clone)]
+pub struct ConsensusOutcome {
+    pub slot: u64,
+    pub status: ConsensusStatus,
+    pub quorum: QuorumThreshold,
+    pub accepted_stake: u64,
+    /// Stake that rejected, errored or timed out
+    pub rejected_stake: u64,
+    pub total_stake: u64,
+    /// One vote per validator, in network order
+    pub votes: Vec<ValidatorVote>,
+    pub elapsed: Duration,
+}
+
+impl ConsensusOutcome {
+    pub fn is_confirmed(&self) -> bool {
+        self.status == ConsensusStatus::Confirmed
+    }
+
+    /// Stake that had not answered when the outcome was decided
+    pub fn pending_stake(&self) -> u64 {
+        self.total_stake - self.accepted_stake - self.rejected_stake
+    }
+
+    pub fn accepted_count(&self) -> usize {
+        self.votes
+            .iter()
+            .filter(|v| matches!(v.response, ValidatorResponse::Accepted { .. }))
+            .count()
+    }
+}
+
+/// Result of delivering a competing branch to the network
+#[derive(Debug, Clone)]
+pub struct ForkOutcome {
+    /// The rival blocks, oldest first
+    pub branch: Vec<Block>,
+    /// Validators whose canonical chain switched to the branch
+    pub reorgs: Vec<(String, Reorg)>,
+}
+
+/// How many recently confirmed blocks the network keeps to repair validators that missed them
+const REPAIR_HISTORY: usize = 64;
+
 /// A network of multiple validators for more realistic simulation
 #[derive(Debug)]
 pub struct ValidatorNetwork {
     pub validators: Vec<MockValidator>,
+    /// Recently confirmed blocks, oldest first
+    confirmed_blocks: Mutex<VecDeque<Block>>,
+    /// Seed every validator's randomness and identity was derived from, if the network seeded them
+    seed: Option<u64>,
+    /// Chance that a rival leader builds a longer fork around each confirmed block
+    fork_rate: f64,
+    fork_rng: Mutex<StdRng>,
+    reorg_sender: broadcast::Sender<Reorg>,
+    pub quorum: QuorumThreshold,
+    /// How long each validator gets to answer
+    pub submit_timeout: Duration,
 }
 
 impl ValidatorNetwork {
-    /// Create a network with multiple validators
+    /// Create a network of `count` equally staked validators
     pub fn new(count: usize) -> Self {
-        let mut validators = Vec::new();
-        
-        for i in 0..count {
-            let mut validator = MockValidator::new();
-            validator.validator_id = format!("validator_{}", i);
-            
-            // Add some variety to the validators
-            match i % 3 {
-                0 => validator.failure_rate = 0.05, // 5% failure rate
-                1 => validator.failure_rate = 0.10, // 10% failure rate
-                _ => validator.failure_rate = 0.02, // 2% failure rate
-            }
-            
-            validators.push(validator);
+        Self::with_stakes(&vec![1; count])
+    }
+
+    /// Create one validator per entry of `stakes`
+    pub fn with_stakes(stakes: &[u64]) -> Self {
+        let validators = stakes
+            .iter()
+            .enumerate()
+            .map(|(i, &stake)| {
+                let mut validator = MockValidator::with_stake(stake);
+                validator.validator_id = format!("validator_{}", i);
+                validator
+            })
+            .collect();
+        let seed = rand::random();
+        info!("Validator network seed {}", seed);
+        Self::from_validators(validators).with_seed(seed)
+    }
+
+    /// Keep each validator's own seed and identity
+    pub fn from_validators(validators: Vec<MockValidator>) -> Self {
+        Self {
+            validators,
+            confirmed_blocks: Mutex::new(VecDeque::new()),
+            seed: None,
+            fork_rate: 0.0,
+            fork_rng: Mutex::new(StdRng::seed_from_u64(rand::random())),
+            reorg_sender: broadcast::channel(64).0,
+            quorum: QuorumThreshold::default(),
+            submit_timeout: Duration::from_secs(2),
         }
-        
-        Self { validators }
     }
 
-    /// Submit a block to all validators and return results
-    pub async fn submit_block_to_network(&self, block: Block) -> Vec<(String, BlockSubmissionResult)> {
-        info!(
-            "🌐 Submitting block for slot {} to network of {} validators",
-            block.slot,
-            self.validators.len()
-        );
+    /// Re-derive every validator's RNG seed and identity from `seed`, so the network's faults and
+    /// leader schedule replay exactly
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        for (index, validator) in self.validators.iter_mut().enumerate() {
+            let mut hasher = Sha256::new();
+            hasher.update(seed.to_le_bytes());
+            hasher.update((index as u64).to_le_bytes());
+            let derived: [u8; 32] = hasher.finalize().into();
+            *validator = validator.clone().seeded(u64::from_le_bytes(derived[..8].try_into().unwrap()));
+            validator.identity = Pubkey::new_from_array(derived);
+        }
+        self.fork_rng = Mutex::new(StdRng::seed_from_u64(seed ^ u64::MAX));
+        self.seed = Some(seed);
+        self
+    }
 
-        let mut results = Vec::new();
-        
-        // Submit to all validators concurrently
-        let futures: Vec<_> = self.validators.iter().map(|validator| {
-            let block_clone = block.clone();
-            let validator_id = validator.validator_id.clone();
-            async move {
-                let result = validator.submit_block(block_clone).await.unwrap();
-                (validator_id, result)
+    pub fn with_fork_rate(mut self, fork_rate: f64) -> Self {
+        self.fork_rate = fork_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Reorgs the network goes through, one event per distinct chain switch
+    pub fn subscribe_reorgs(&self) -> broadcast::Receiver<Reorg> {
+        self.reorg_sender.subscribe()
+    }
+
+    /// Have a rival leader build `length` empty blocks on `parent` from `slot` onwards and deliver
+    /// them to every validator. A branch longer than a validator's canonical chain reorgs it.
+    pub async fn simulate_fork(&self, parent: Hash, slot: u64, length: usize) -> ForkOutcome {
+        let schedule = self.leader_schedule();
+        let mut timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs().max(1);
+        let mut branch: Vec<Block> = Vec::with_capacity(length);
+        for offset in 0..length as u64 {
+            let mut block = Block {
+                slot: slot + offset,
+                parent_hash: branch.last().map_or(parent, |b| b.blockhash),
+                blockhash: Hash::default(),
+                transactions: vec![],
+                bundles: vec![],
+                timestamp,
+                leader_pubkey: schedule.leader_for_slot(slot + offset).unwrap_or_default(),
+                total_fees: 0,
+                total_tips: 0,
+            };
+            block.blockhash = block.compute_blockhash();
+            // An identical rival block may already exist from an earlier fork at the same point
+            while self.validators.iter().any(|v| v.block_tree.read().unwrap().contains(&block.blockhash)) {
+                timestamp += 1;
+                block.timestamp = timestamp;
+                block.blockhash = block.compute_blockhash();
             }
-        }).collect();
+            branch.push(block);
+        }
+        info!("Simulating a {}-block fork from {} at slot {}", length, parent, slot);
 
-        // Wait for all results
-        for future in futures {
-            results.push(future.await);
+        let mut reorgs: Vec<(String, Reorg)> = Vec::new();
+        for validator in &self.validators {
+            for block in &branch {
+                match validator.import_block(block.clone()).await {
+                    Ok(Some(reorg)) => reorgs.push((validator.validator_id.clone(), reorg)),
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!("Validator {} refused fork block for slot {}: {}", validator.validator_id, block.slot, e);
+                        break;
+                    }
+                }
+            }
         }
 
-        // Log network consensus
-        let accepted_count = results.iter().filter(|(_, result)| {
-            matches!(result, BlockSubmissionResult::Accepted { .. })
-        }).count();
-        
-        let consensus_rate = accepted_count as f64 / self.validators.len() as f64;
-        
+        let mut published: Vec<(Hash, Hash)> = Vec::new();
+        for (_, reorg) in &reorgs {
+            if !published.contains(&(reorg.old_head, reorg.new_head)) {
+                published.push((reorg.old_head, reorg.new_head));
+                let _ = self.reorg_sender.send(reorg.clone());
+            }
+        }
+        ForkOutcome { branch, reorgs }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn with_quorum(mut self, quorum: QuorumThreshold) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    pub fn with_submit_timeout(mut self, submit_timeout: Duration) -> Self {
+        self.submit_timeout = submit_timeout;
+        self
+    }
+
+    pub fn total_stake(&self) -> u64 {
+        self.validators.iter().map(|v| v.stake).sum()
+    }
+
+    /// Stake-weighted rotation over the network's validators
+    pub fn leader_schedule(&self) -> LeaderSchedule {
+        LeaderSchedule::new(self.validators.iter().map(|v| StakedValidator::new(v.identity, v.stake)))
+    }
+
+    /// Validator scheduled to lead `slot`
+    pub fn leader_for_slot(&self, slot: u64) -> Option<&MockValidator> {
+        let leader = self.leader_schedule().leader_for_slot(slot)?;
+        self.validators.iter().find(|v| v.identity == leader)
+    }
+
+    /// Submit a block to every validator at once and return as soon as the quorum is reached or
+    /// can no longer be reached. Validators still working carry on in the background.
+    pub async fn submit_block_to_network(&self, block: Block) -> ConsensusOutcome {
+        let started = Instant::now();
+        let slot = block.slot;
+        let total_stake = self.total_stake();
         info!(
-            "🗳️ Network consensus: {}/{} validators accepted block ({}% acceptance)",
-            accepted_count,
+            "Submitting block for slot {} to network of {} validators ({} stake, quorum {}/{})",
+            slot,
             self.validators.len(),
-            (consensus_rate * 100.0) as u32
+            total_stake,
+            self.quorum.numerator,
+            self.quorum.denominator
         );
 
-        results
+        for validator in &self.validators {
+            self.repair(validator, block.parent_hash).await;
+        }
+
+        let mut tasks = JoinSet::new();
+        let mut task_validators = HashMap::new();
+        for (index, validator) in self.validators.iter().enumerate() {
+            let validator = validator.clone();
+            let block = block.clone();
+            let submit_timeout = self.submit_timeout;
+            let handle = tasks.spawn(async move {
+                match timeout(submit_timeout, validator.submit_block(block)).await {
+                    Ok(Ok(BlockSubmissionResult::Accepted { signature })) => ValidatorResponse::Accepted { signature },
+                    Ok(Ok(BlockSubmissionResult::Rejected { reason })) => ValidatorResponse::Rejected { reason },
+                    Ok(Err(e)) => ValidatorResponse::Errored { error: e.to_string() },
+                    Err(_) => ValidatorResponse::TimedOut,
+                }
+            });
+            task_validators.insert(handle.id(), index);
+        }
+
+        let mut votes: Vec<ValidatorVote> = self
+            .validators
+            .iter()
+            .map(|v| ValidatorVote { validator_id: v.validator_id.clone(), stake: v.stake, response: ValidatorResponse::Pending })
+            .collect();
+        let (mut accepted_stake, mut rejected_stake) = (0, 0);
+        let mut status = None;
+        while let Some(joined) = tasks.join_next_with_id().await {
+            let (id, response) = match joined {
+                Ok(result) => result,
+                Err(e) => (e.id(), ValidatorResponse::Errored { error: e.to_string() }),
+            };
+            let index = task_validators[&id];
+            match &response {
+                ValidatorResponse::Accepted { .. } => accepted_stake += votes[index].stake,
+                _ => rejected_stake += votes[index].stake,
+            }
+            votes[index].response = response;
+
+            if self.quorum.is_met(accepted_stake, total_stake) {
+                status = Some(ConsensusStatus::Confirmed);
+                break;
+            }
+            if !self.quorum.is_met(total_stake - rejected_stake, total_stake) {
+                status = Some(ConsensusStatus::QuorumUnreachable);
+                break;
+            }
+        }
+        tasks.detach_all();
+
+        if status == Some(ConsensusStatus::Confirmed) {
+            let mut confirmed = self.confirmed_blocks.lock().unwrap();
+            confirmed.push_back(block.clone());
+            while confirmed.len() > REPAIR_HISTORY {
+                confirmed.pop_front();
+            }
+        }
+
+        if status == Some(ConsensusStatus::Confirmed) && self.fork_rate > 0.0 {
+            let roll: f64 = self.fork_rng.lock().unwrap().gen();
+            if roll < self.fork_rate {
+                self.simulate_fork(block.parent_hash, slot, 2).await;
+            }
+        }
+
+        let outcome = ConsensusOutcome {
+            slot,
+            // Only an empty network runs out of votes undecided; it cannot confirm anything
+            status: status.unwrap_or(ConsensusStatus::QuorumUnreachable),
+            quorum: self.quorum,
+            accepted_stake,
+            rejected_stake,
+            total_stake,
+            votes,
+            elapsed: started.elapsed(),
+        };
+
+        match outcome.status {
+            ConsensusStatus::Confirmed => info!(
+                "Slot {} confirmed with {}/{} stake after {:?}",
+                slot, accepted_stake, total_stake, outcome.elapsed
+            ),
+            ConsensusStatus::QuorumUnreachable => warn!(
+                "Slot {} cannot reach quorum: {}/{} stake rejected or unresponsive after {:?}",
+                slot, rejected_stake, total_stake, outcome.elapsed
+            ),
+        }
+        outcome
+    }
+
+    /// Import the confirmed blocks `validator` is missing below `parent`, so a block it rejected or
+    /// timed out on does not leave it unable to link every block built after it
+    async fn repair(&self, validator: &MockValidator, parent: Hash) {
+        let missing: Vec<Block> = {
+            let tree = validator.block_tree.read().unwrap();
+            let confirmed = self.confirmed_blocks.lock().unwrap();
+            let mut missing = Vec::new();
+            let mut next = parent;
+            while !tree.contains(&next) {
+                let Some(block) = confirmed.iter().find(|b| b.blockhash == next) else {
+                    break;
+                };
+                next = block.parent_hash;
+                missing.push(block.clone());
+            }
+            missing
+        };
+
+        for block in missing.into_iter().rev() {
+            let slot = block.slot;
+            match validator.import_block(block).await {
+                Ok(_) => info!("Validator {} caught up on confirmed block for slot {}", validator.validator_id, slot),
+                Err(e) => {
+                    warn!("Validator {} could not catch up on slot {}: {}", validator.validator_id, slot, e);
+                    break;
+                }
+            }
+        }
     }
 
     /// Get aggregate statistics for the entire network
@@ -388,7 +879,7 @@ mod tests {
 
         Block {
             slot: 12345,
-            parent_hash: Hash::new_unique(),
+            parent_hash: Hash::default(),
             blockhash: Hash::new_unique(),
             transactions: vec![],
             bundles: vec![bundle],
@@ -448,6 +939,32 @@ mod tests {
         assert_eq!(stats.blocks_rejected, 1);
     }
 
+    #[tokio::test]
+    async fn test_validator_rejects_misranked_bundles() {
+        use crate::auction::{AuctionPolicy, PriorityFeePolicy};
+        use solana_sdk::{instruction::Instruction, message::Message, transaction::Transaction, signature::Signature};
+        let bundle = |tip| {
+            let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+            let message = Message::new(&[instruction], Some(&Pubkey::new_unique()));
+            Bundle::new(vec![Transaction { signatures: vec![Signature::default()], message }], tip, "searcher".to_string())
+        };
+        let ranked = PriorityFeePolicy.rank(1, vec![bundle(100), bundle(300), bundle(200)]);
+
+        let validator = MockValidator::new().seeded(7);
+        let mut block = create_test_block();
+        block.transactions = ranked.iter().flat_map(|b| b.transactions.clone()).collect();
+        block.total_tips = 600;
+        block.bundles = ranked;
+        assert!(validator.validate_block(&block).await.is_ok());
+
+        block.bundles.swap(0, 1);
+        block.transactions.swap(0, 1);
+        match validator.submit_block(block).await.unwrap() {
+            BlockSubmissionResult::Rejected { reason } => assert!(reason.contains("outranks"), "{}", reason),
+            BlockSubmissionResult::Accepted { .. } => panic!("Expected misranked block to be rejected"),
+        }
+    }
+
     #[tokio::test]
     async fn test_validator_with_failure_rate() {
         let validator = MockValidator::with_failure_rate(1.0); // Always fail
@@ -465,20 +982,256 @@ mod tests {
         }
     }
 
+    fn validator(delay_ms: u64, failure_rate: f64, stake: u64) -> MockValidator {
+        let mut validator = MockValidator::with_failure_rate(failure_rate);
+        validator.verification_delay_ms = delay_ms;
+        validator.stake = stake;
+        validator
+    }
+
+    #[test]
+    fn test_quorum_threshold_is_stake_fraction() {
+        let quorum = QuorumThreshold::two_thirds();
+        assert!(quorum.is_met(2, 3));
+        assert!(!quorum.is_met(66, 100));
+        assert!(quorum.is_met(67, 100));
+        assert!(QuorumThreshold::majority().is_met(1, 2));
+        assert!(!quorum.is_met(0, 0));
+        assert!(!QuorumThreshold::new(0, 1).is_met(0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_validator_network_submits_concurrently() {
+        let validators = (0..5).map(|_| validator(200, 0.0, 1)).collect();
+        let network = ValidatorNetwork::from_validators(validators).with_quorum(QuorumThreshold::new(1, 1));
+
+        let outcome = network.submit_block_to_network(create_test_block()).await;
+
+        assert!(outcome.is_confirmed());
+        assert_eq!(outcome.accepted_count(), 5);
+        assert_eq!(outcome.accepted_stake, 5);
+        // Five 200ms validators in sequence would take a second
+        assert!(outcome.elapsed < Duration::from_millis(600), "took {:?}", outcome.elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_returns_once_stake_weighted_quorum_is_reached() {
+        // The heavy validator alone holds 2/3 of the stake; the slow ones are not waited for
+        let network = ValidatorNetwork::from_validators(vec![
+            validator(10, 0.0, 4),
+            validator(5_000, 0.0, 1),
+            validator(5_000, 0.0, 1),
+        ])
+        .with_submit_timeout(Duration::from_secs(10));
+
+        let outcome = network.submit_block_to_network(create_test_block()).await;
+
+        assert_eq!(outcome.status, ConsensusStatus::Confirmed);
+        assert_eq!((outcome.accepted_stake, outcome.pending_stake()), (4, 2));
+        assert_eq!(outcome.votes[1].response, ValidatorResponse::Pending);
+        assert!(outcome.elapsed < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_returns_once_quorum_is_unreachable() {
+        let network = ValidatorNetwork::from_validators(vec![
+            validator(10, 1.0, 1),
+            validator(10, 1.0, 1),
+            validator(5_000, 0.0, 1),
+        ])
+        .with_submit_timeout(Duration::from_secs(10));
+
+        let outcome = network.submit_block_to_network(create_test_block()).await;
+
+        assert_eq!(outcome.status, ConsensusStatus::QuorumUnreachable);
+        assert_eq!(outcome.rejected_stake, 2);
+        assert!(matches!(outcome.votes[0].response, ValidatorResponse::Rejected { .. }));
+        assert!(outcome.elapsed < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_network_schedules_leaders_by_stake() {
+        let network = ValidatorNetwork::with_stakes(&[0, 5, 5]);
+        assert_eq!(network.total_stake(), 10);
+
+        let schedule = network.leader_schedule();
+        for slot in 0..200 {
+            let leader = network.leader_for_slot(slot).unwrap();
+            assert_eq!(Some(leader.identity), schedule.leader_for_slot(slot));
+            assert_ne!(leader.validator_id, "validator_0", "unstaked validators never lead");
+        }
+    }
+
+    fn block_for_slot(slot: u64) -> Block {
+        Block { slot, ..create_test_block() }
+    }
+
+    async fn decisions(validator: &MockValidator, slots: std::ops::Range<u64>) -> Vec<bool> {
+        let mut accepted = Vec::new();
+        for slot in slots {
+            let result = validator.submit_block(block_for_slot(slot)).await.unwrap();
+            accepted.push(matches!(result, BlockSubmissionResult::Accepted { .. }));
+        }
+        accepted
+    }
+
+    #[tokio::test]
+    async fn test_failures_replay_from_seed() {
+        let flaky = |seed| {
+            let mut validator = MockValidator::with_failure_rate(0.5).seeded(seed);
+            validator.verification_delay_ms = 0;
+            validator
+        };
+
+        let first = decisions(&flaky(42), 0..40).await;
+        assert_eq!(decisions(&flaky(42), 0..40).await, first);
+        assert_ne!(decisions(&flaky(43), 0..40).await, first);
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+
+    #[tokio::test]
+    async fn test_injected_block_and_slot_failures() {
+        let mut validator = MockValidator::new()
+            .with_fault(FaultModel::FailNthBlock { n: 2 })
+            .with_fault(FaultModel::FailSlots { slots: vec![14] });
+        validator.verification_delay_ms = 0;
+
+        assert_eq!(decisions(&validator, 10..16).await, vec![true, false, true, true, false, true]);
+        let reasons: Vec<String> = validator.get_rejected_blocks().into_iter().map(|(_, reason)| reason).collect();
+        assert_eq!(reasons, vec!["Injected failure on block #2", "Injected failure for slot 14"]);
+    }
+
+    #[tokio::test]
+    async fn test_byzantine_validator_accepts_invalid_blocks() {
+        let invalid = Block { timestamp: 0, ..create_test_block() };
+        let honest = MockValidator::with_verification_delay(0);
+        let byzantine = MockValidator::with_verification_delay(0).with_fault(FaultModel::Byzantine);
+
+        assert!(matches!(honest.submit_block(invalid.clone()).await.unwrap(), BlockSubmissionResult::Rejected { .. }));
+        assert!(matches!(byzantine.submit_block(invalid).await.unwrap(), BlockSubmissionResult::Accepted { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_slow_faults_surface_in_network_votes() {
+        let network = ValidatorNetwork::from_validators(vec![
+            validator(10, 0.0, 1),
+            validator(10, 0.0, 1).with_fault(FaultModel::Timeout { probability: 1.0 }),
+            validator(10, 0.0, 1).with_fault(FaultModel::SlowResponse { probability: 1.0, extra_delay_ms: 1_000 }),
+        ])
+        .with_submit_timeout(Duration::from_millis(200));
+
+        let outcome = network.submit_block_to_network(create_test_block()).await;
+
+        assert_eq!(outcome.status, ConsensusStatus::QuorumUnreachable);
+        assert_eq!(outcome.votes[1].response, ValidatorResponse::TimedOut);
+        assert_eq!(outcome.votes[2].response, ValidatorResponse::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_network_replays_from_seed() {
+        // Returns each slot's leader and, per validator, the slots it accepted
+        async fn run(seed: u64) -> (Vec<Pubkey>, Vec<Vec<u64>>) {
+            let mut network = ValidatorNetwork::with_stakes(&[3, 2, 1]).with_seed(seed);
+            for validator in &mut network.validators {
+                validator.verification_delay_ms = 0;
+                validator.failure_rate = 0.4;
+            }
+            let mut leaders = Vec::new();
+            for slot in 0..10 {
+                leaders.push(network.leader_for_slot(slot).unwrap().identity);
+                network.submit_block_to_network(block_for_slot(slot)).await;
+            }
+            // Validators outvoted before answering finish in the background
+            sleep(Duration::from_millis(50)).await;
+            let accepted = network
+                .validators
+                .iter()
+                .map(|v| v.get_accepted_blocks().iter().map(|b| b.slot).collect())
+                .collect();
+            (leaders, accepted)
+        }
+
+        let (leaders, accepted) = run(7).await;
+        assert_eq!(run(7).await, (leaders.clone(), accepted.clone()));
+        assert_ne!(run(8).await, (leaders, accepted));
+        assert_eq!(ValidatorNetwork::new(3).with_seed(7).seed(), Some(7));
+    }
+
     #[tokio::test]
-    async fn test_validator_network() {
-        let network = ValidatorNetwork::new(3);
+    async fn test_blocks_must_link_to_a_known_parent() {
+        let validator = MockValidator::with_verification_delay(0);
+        let first = create_test_block();
+        let orphan = Block { parent_hash: Hash::new_unique(), blockhash: Hash::new_unique(), ..create_test_block() };
+        let child = Block { parent_hash: first.blockhash, blockhash: Hash::new_unique(), ..create_test_block() };
+
+        assert!(matches!(validator.submit_block(first.clone()).await.unwrap(), BlockSubmissionResult::Accepted { .. }));
+        assert!(matches!(validator.submit_block(orphan).await.unwrap(), BlockSubmissionResult::Rejected { .. }));
+        assert!(matches!(validator.submit_block(first).await.unwrap(), BlockSubmissionResult::Rejected { .. }));
+        assert!(matches!(validator.submit_block(child.clone()).await.unwrap(), BlockSubmissionResult::Accepted { .. }));
+
+        let reasons: Vec<String> = validator.get_rejected_blocks().into_iter().map(|(_, r)| r).collect();
+        assert!(reasons[0].starts_with("Parent block") && reasons[1].ends_with("already accepted"));
+        assert_eq!(validator.head(), child.blockhash);
+    }
+
+    #[tokio::test]
+    async fn test_competing_fork_reorgs_every_validator_once() {
+        let mut network = ValidatorNetwork::new(3).with_quorum(QuorumThreshold::new(1, 1));
+        for validator in &mut network.validators {
+            validator.verification_delay_ms = 0;
+        }
+        let mut reorgs = network.subscribe_reorgs();
         let block = create_test_block();
+        assert!(network.submit_block_to_network(block.clone()).await.is_confirmed());
 
-        let results = network.submit_block_to_network(block).await;
-        
-        assert_eq!(results.len(), 3);
-        
-        // At least some should be accepted (given low failure rates)
-        let accepted_count = results.iter().filter(|(_, result)| {
-            matches!(result, BlockSubmissionResult::Accepted { .. })
-        }).count();
-        
-        assert!(accepted_count > 0, "At least one validator should accept the block");
+        // A one-block rival is only a side fork; a two-block one wins
+        let short = network.simulate_fork(block.parent_hash, block.slot, 1).await;
+        assert!(short.reorgs.is_empty());
+        let long = network.simulate_fork(block.parent_hash, block.slot, 2).await;
+
+        assert_eq!(long.reorgs.len(), 3);
+        for validator in &network.validators {
+            assert_eq!(validator.head(), long.branch[1].blockhash);
+            assert!(!validator.block_tree.read().unwrap().is_canonical(&block.blockhash));
+        }
+        let reorg = reorgs.try_recv().unwrap();
+        assert_eq!(reorg.orphaned.iter().map(|b| b.blockhash).collect::<Vec<_>>(), vec![block.blockhash]);
+        assert!(reorgs.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slow_validators_time_out() {
+        let network = ValidatorNetwork::from_validators(vec![validator(10, 0.0, 1), validator(1_000, 0.0, 1)])
+            .with_submit_timeout(Duration::from_millis(100));
+
+        let outcome = network.submit_block_to_network(create_test_block()).await;
+
+        assert_eq!(outcome.status, ConsensusStatus::QuorumUnreachable);
+        assert_eq!(outcome.votes[1].response, ValidatorResponse::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_validator_catches_up_on_a_confirmed_block_it_rejected() {
+        let first = create_test_block();
+        let second = Block {
+            slot: first.slot + 1,
+            parent_hash: first.blockhash,
+            blockhash: Hash::new_unique(),
+            ..create_test_block()
+        };
+        let lagging = validator(0, 0.0, 1).with_fault(FaultModel::FailSlots { slots: vec![first.slot] });
+        let network = ValidatorNetwork::from_validators(vec![lagging, validator(0, 0.0, 1), validator(0, 0.0, 1)]);
+
+        let outcome = network.submit_block_to_network(first.clone()).await;
+        assert!(outcome.is_confirmed());
+        // Let the lagging validator finish rejecting before the next block arrives
+        sleep(Duration::from_millis(50)).await;
+        assert!(!network.validators[0].block_tree.read().unwrap().contains(&first.blockhash));
+
+        let outcome = network.submit_block_to_network(second.clone()).await;
+        sleep(Duration::from_millis(50)).await;
+        assert!(outcome.is_confirmed());
+        assert_eq!(network.validators[0].head(), second.blockhash);
+        assert_eq!(network.validators[0].get_stats().blocks_rejected, 1);
     }
 }
@@ -0,0 +1,340 @@
+use crate::bundle_source::{ListStore, RedisListStore};
+use crate::ordered_block::{OrderedBlock, WireBundle};
+use crate::sink::SubmissionOutcome;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+use anyhow::{anyhow, Result};
+
+/// Redis list holding one JSON `WindowRecord` per processed window
+pub const WINDOW_LOG_KEY: &str = "engine:window_log";
+
+/// Records kept in the Redis window log before the oldest are trimmed
+pub const DEFAULT_WINDOW_LOG_MAX_LEN: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedBundle {
+    pub bundle_id: String,
+    pub searcher_pubkey: String,
+    pub tip: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedBundle {
+    pub bundle_id: String,
+    pub reason: String,
+}
+
+/// Everything the engine decided for one window: what came in, how it ranked, and what was built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowRecord {
+    pub window_id: u64,
+    /// Milliseconds since the Unix epoch when the record was written
+    pub recorded_at: u64,
+    pub inputs: Vec<WireBundle>,
+    /// Simulated candidates from best to worst
+    pub ranking: Vec<RankedBundle>,
+    /// Bundle IDs included in the block, in block order
+    pub winners: Vec<String>,
+    pub rejected: Vec<RejectedBundle>,
+    pub block: Option<OrderedBlock>,
+    pub submission: Option<SubmissionOutcome>,
+}
+
+/// Append-only log of processed windows
+#[async_trait]
+pub trait WindowStore: Send + Sync {
+    async fn append(&self, record: &WindowRecord) -> Result<()>;
+
+    /// Every record in the order it was appended
+    async fn load_all(&self) -> Result<Vec<WindowRecord>>;
+
+    async fn last_record(&self) -> Result<Option<WindowRecord>> {
+        Ok(self.load_all().await?.pop())
+    }
+}
+
+/// JSON-lines file log; every append is flushed to disk before returning.
+/// File I/O runs on the blocking thread pool so it never stalls the auction loop.
+pub struct FileWindowStore {
+    path: PathBuf,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl FileWindowStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn blocking<T: Send + 'static>(&self, f: impl FnOnce(PathBuf) -> Result<T> + Send + 'static) -> Result<T> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || f(path)).await?
+    }
+}
+
+/// The last `count` non-empty lines of `path`, read backwards from the end of the file
+fn tail_lines(path: &Path, count: usize) -> std::io::Result<Vec<String>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let len = file.metadata()?.len();
+    let mut chunk = 8 * 1024;
+    loop {
+        let start = len.saturating_sub(chunk);
+        let mut buf = Vec::with_capacity((len - start) as usize);
+        file.seek(SeekFrom::Start(start))?;
+        (&file).take(len - start).read_to_end(&mut buf)?;
+
+        let text = String::from_utf8_lossy(&buf);
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        if start > 0 {
+            // The first piece may start mid-line
+            lines.remove(0);
+        }
+        let lines: Vec<&str> = lines.into_iter().filter(|l| !l.trim().is_empty()).collect();
+        if lines.len() >= count || start == 0 {
+            return Ok(lines[lines.len().saturating_sub(count)..].iter().map(|l| l.to_string()).collect());
+        }
+        chunk *= 2;
+    }
+}
+
+#[async_trait]
+impl WindowStore for FileWindowStore {
+    async fn append(&self, record: &WindowRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let write_lock = Arc::clone(&self.write_lock);
+        self.blocking(move |path| {
+            let _guard = write_lock.lock().unwrap();
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            file.write_all(&line)?;
+            file.sync_data()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_all(&self) -> Result<Vec<WindowRecord>> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+        let mut records = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str::<WindowRecord>(line) {
+                Ok(record) => records.push(record),
+                // A crash mid-append can only tear the final line
+                Err(e) if i == lines.len() - 1 => {
+                    warn!("Ignoring truncated last record in {}: {}", self.path.display(), e);
+                }
+                Err(e) => return Err(anyhow!("corrupt record {} in {}: {}", i + 1, self.path.display(), e)),
+            }
+        }
+        Ok(records)
+    }
+
+    async fn last_record(&self) -> Result<Option<WindowRecord>> {
+        // The line before the last is needed only when the last one was torn by a crash
+        let mut lines = self.blocking(|path| Ok(tail_lines(&path, 2)?)).await?;
+        let Some(last) = lines.pop() else {
+            return Ok(None);
+        };
+        match serde_json::from_str(&last) {
+            Ok(record) => Ok(Some(record)),
+            Err(e) => {
+                warn!("Ignoring truncated last record in {}: {}", self.path.display(), e);
+                lines.pop().map(|line| serde_json::from_str(&line)).transpose().map_err(Into::into)
+            }
+        }
+    }
+}
+
+/// Redis list log under `engine:window_log`, readable by the API server.
+/// Only the newest `max_len` records are kept.
+pub struct RedisWindowStore<S: ListStore = RedisListStore> {
+    store: tokio::sync::Mutex<S>,
+    key: String,
+    max_len: usize,
+}
+
+impl RedisWindowStore<RedisListStore> {
+    pub async fn connect(redis_url: &str) -> Result<Self> {
+        Ok(Self::with_store(RedisListStore::connect(redis_url).await?))
+    }
+}
+
+impl<S: ListStore> RedisWindowStore<S> {
+    pub fn with_store(store: S) -> Self {
+        Self {
+            store: tokio::sync::Mutex::new(store),
+            key: WINDOW_LOG_KEY.to_string(),
+            max_len: DEFAULT_WINDOW_LOG_MAX_LEN,
+        }
+    }
+
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+}
+
+#[async_trait]
+impl<S: ListStore> WindowStore for RedisWindowStore<S> {
+    async fn append(&self, record: &WindowRecord) -> Result<()> {
+        let json = serde_json::to_string(record)?;
+        let mut store = self.store.lock().await;
+        store.push(&self.key, json).await?;
+        store.trim(&self.key, self.max_len).await
+    }
+
+    async fn load_all(&self) -> Result<Vec<WindowRecord>> {
+        let entries = self.store.lock().await.read_all(&self.key).await?;
+        entries
+            .iter()
+            .map(|entry| serde_json::from_str(entry).map_err(Into::into))
+            .collect()
+    }
+
+    async fn last_record(&self) -> Result<Option<WindowRecord>> {
+        let entry = self.store.lock().await.last(&self.key).await?;
+        entry.map(|entry| serde_json::from_str(&entry)).transpose().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle_source::MemoryListStore;
+    use crate::sink::BlockAck;
+
+    fn record(window_id: u64) -> WindowRecord {
+        WindowRecord {
+            window_id,
+            recorded_at: 1_700_000_000_000,
+            inputs: vec![],
+            ranking: vec![RankedBundle {
+                bundle_id: format!("bundle-{}", window_id),
+                searcher_pubkey: "searcher".to_string(),
+                tip: 5000,
+            }],
+            winners: vec![format!("bundle-{}", window_id)],
+            rejected: vec![RejectedBundle {
+                bundle_id: "loser".to_string(),
+                reason: "Simulation failed".to_string(),
+            }],
+            block: None,
+            submission: Some(SubmissionOutcome::Delivered(BlockAck::accepted(window_id))),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_store_appends_and_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("engine").join("windows.jsonl");
+
+        let store = FileWindowStore::new(&path);
+        store.append(&record(1)).await.unwrap();
+        store.append(&record(2)).await.unwrap();
+
+        // A fresh handle sees everything written before
+        let reopened = FileWindowStore::new(&path);
+        let records = reopened.load_all().await.unwrap();
+        assert_eq!(records.iter().map(|r| r.window_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(records[1].winners, vec!["bundle-2".to_string()]);
+        assert_eq!(records[1].submission, Some(SubmissionOutcome::Delivered(BlockAck::accepted(2))));
+        assert_eq!(reopened.last_record().await.unwrap().unwrap().window_id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_ignores_torn_final_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("windows.jsonl");
+        let store = FileWindowStore::new(&path);
+        store.append(&record(1)).await.unwrap();
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"window_id\": 2, \"recor").unwrap();
+
+        let records = store.load_all().await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(store.last_record().await.unwrap().unwrap().window_id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_last_record_reads_only_the_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("windows.jsonl");
+        let store = FileWindowStore::new(&path);
+        for window_id in 0..200 {
+            store.append(&record(window_id)).await.unwrap();
+        }
+
+        // A corrupt record early in the log would fail `load_all`, but not the tail read
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        contents.replace_range(0..1, "x");
+        std::fs::write(&path, contents).unwrap();
+
+        assert!(store.load_all().await.is_err());
+        assert_eq!(store.last_record().await.unwrap().unwrap().window_id, 199);
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_is_empty_log() {
+        let store = FileWindowStore::new("/nonexistent/windows.jsonl");
+        assert!(store.load_all().await.unwrap().is_empty());
+        assert!(store.last_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_appends_to_list() {
+        let lists = MemoryListStore::new();
+        let store = RedisWindowStore::with_store(lists.clone());
+
+        store.append(&record(3)).await.unwrap();
+        store.append(&record(4)).await.unwrap();
+
+        assert_eq!(lists.list(WINDOW_LOG_KEY).len(), 2);
+        let records = store.load_all().await.unwrap();
+        assert_eq!(records.iter().map(|r| r.window_id).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(store.last_record().await.unwrap().unwrap().window_id, 4);
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_is_capped() {
+        let lists = MemoryListStore::new();
+        let store = RedisWindowStore::with_store(lists.clone()).with_max_len(3);
+
+        for window_id in 0..5 {
+            store.append(&record(window_id)).await.unwrap();
+        }
+
+        let records = store.load_all().await.unwrap();
+        assert_eq!(records.iter().map(|r| r.window_id).collect::<Vec<_>>(), vec![2, 3, 4]);
+    }
+}
@@ -112,6 +112,22 @@ start_api_server() {
     fi
 }
 
+# Function to start the validator that receives blocks on port 4000
+start_validator() {
+    print_info "Starting validator client..."
+
+    cargo run --bin validator-client -- --listen 0.0.0.0:4000 &
+    VALIDATOR_PID=$!
+
+    sleep 3
+
+    if check_service 4000 "Validator"; then
+        print_status "Validator started successfully (PID: $VALIDATOR_PID)"
+    else
+        print_warning "Validator may still be building..."
+    fi
+}
+
 # Function to start block engine
 start_block_engine() {
     print_info "Starting Rust block engine..."
@@ -189,7 +205,7 @@ show_status() {
     
     check_service 6379 "Redis Server" || true
     check_service 3001 "API Server" || true
-    check_service 4000 "Mock Validator" || true
+    check_service 4000 "Validator" || true
     check_service 3000 "Dashboard" || true
     
     echo
@@ -198,7 +214,7 @@ show_status() {
     echo -e "${BLUE}  • Bundle Submission: ${NC}http://localhost:3001/api/bundles"
     echo -e "${BLUE}  • Metrics:          ${NC}http://localhost:3001/api/metrics"
     echo -e "${BLUE}  • Treasury:         ${NC}http://localhost:3001/api/metrics/treasury"
-    echo -e "${BLUE}  • Mock Validator:   ${NC}http://localhost:4000/submit_block"
+    echo -e "${BLUE}  • Validator:        ${NC}http://localhost:4000/submit_block"
     if check_service 3000 "Dashboard" 2>/dev/null; then
         echo -e "${BLUE}  • Dashboard:        ${NC}http://localhost:3000"
     fi
@@ -221,6 +237,7 @@ cleanup() {
     
     # Kill background processes
     [ ! -z "$API_PID" ] && kill $API_PID 2>/dev/null || true
+    [ ! -z "$VALIDATOR_PID" ] && kill $VALIDATOR_PID 2>/dev/null || true
     [ ! -z "$BLOCK_ENGINE_PID" ] && kill $BLOCK_ENGINE_PID 2>/dev/null || true
     [ ! -z "$DASHBOARD_PID" ] && kill $DASHBOARD_PID 2>/dev/null || true
     
@@ -285,6 +302,7 @@ main() {
     # Start services in order
     start_redis
     start_api_server
+    start_validator
     start_block_engine
     start_dashboard
     
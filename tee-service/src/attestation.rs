@@ -0,0 +1,111 @@
+use block_engine::{BundleEnvelope, WireBundle};
+use block_engine::ordered_block::compute_ordered_hash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use std::str::FromStr;
+
+/// Statement signed by the enclave: for this window, these sealed inputs produced this ordering
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderingAttestation {
+    pub window_id: u64,
+    /// `compute_ordered_hash` over the winning bundles in block order
+    pub ordered_hash: String,
+    /// Commitment to every envelope the enclave received, see [`input_hash`]
+    pub input_hash: String,
+    pub bundle_count: usize,
+    /// Hash identifying the enclave code that produced the ordering
+    pub measurement: String,
+    /// `simulated` for the software backend, which offers no hardware guarantees
+    pub backend: String,
+    pub enclave: String,
+    pub signature: String,
+}
+
+impl OrderingAttestation {
+    #[allow(clippy::too_many_arguments)]
+    pub fn sign(
+        identity: &Keypair,
+        window_id: u64,
+        ordered_hash: String,
+        input_hash: String,
+        bundle_count: usize,
+        measurement: String,
+        backend: &str,
+    ) -> Self {
+        let mut attestation = Self {
+            window_id,
+            ordered_hash,
+            input_hash,
+            bundle_count,
+            measurement,
+            backend: backend.to_string(),
+            enclave: identity.pubkey().to_string(),
+            signature: String::new(),
+        };
+        attestation.signature = identity.sign_message(&attestation.message()).to_string();
+        attestation
+    }
+
+    /// Check the signature against the enclave key named in the attestation
+    pub fn verify(&self) -> bool {
+        let (Ok(enclave), Ok(signature)) = (Pubkey::from_str(&self.enclave), Signature::from_str(&self.signature))
+        else {
+            return false;
+        };
+        signature.verify(enclave.as_ref(), &self.message())
+    }
+
+    /// Check the signature and that `ordered_bundles` is the ordering that was attested
+    pub fn verify_ordering(&self, ordered_bundles: &[WireBundle]) -> bool {
+        self.verify()
+            && ordered_bundles.len() == self.bundle_count
+            && compute_ordered_hash(ordered_bundles) == self.ordered_hash
+    }
+
+    /// Bytes the enclave signs
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = b"tee-ordering".to_vec();
+        message.extend_from_slice(&self.window_id.to_le_bytes());
+        message.extend_from_slice(&(self.bundle_count as u64).to_le_bytes());
+        for field in [&self.ordered_hash, &self.input_hash, &self.measurement, &self.backend] {
+            message.extend_from_slice(&(field.len() as u64).to_le_bytes());
+            message.extend_from_slice(field.as_bytes());
+        }
+        message
+    }
+}
+
+/// Order-independent commitment to a window's envelopes, so a searcher can check theirs was included
+pub fn input_hash(sealed: &[BundleEnvelope]) -> String {
+    let mut digests: Vec<_> = sealed.iter().map(BundleEnvelope::digest).collect();
+    digests.sort_unstable();
+    let mut hasher = Sha256::new();
+    for digest in digests {
+        hasher.update(digest);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attestation_verifies_only_untampered() {
+        let identity = Keypair::new();
+        let attestation =
+            OrderingAttestation::sign(&identity, 4, compute_ordered_hash(&[]), input_hash(&[]), 0, "m".into(), "simulated");
+        assert!(attestation.verify());
+        assert!(attestation.verify_ordering(&[]));
+
+        let mut tampered = attestation.clone();
+        tampered.measurement = "other".to_string();
+        assert!(!tampered.verify());
+
+        let mut tampered = attestation;
+        tampered.enclave = Keypair::new().pubkey().to_string();
+        assert!(!tampered.verify());
+    }
+}
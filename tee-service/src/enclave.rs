@@ -0,0 +1,206 @@
+use crate::attestation::{input_hash, OrderingAttestation};
+use block_engine::ordered_block::compute_ordered_hash;
+use block_engine::{AuctionPolicy, BundleEnvelope, EnvelopeError, EnvelopeKey, PriorityFeePolicy, WireBundle};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// Result of ordering one window inside the enclave. Bundle contents leave the boundary only
+/// here, after the ordering has been fixed and signed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowOrdering {
+    pub attestation: OrderingAttestation,
+    pub ordered_bundles: Vec<WireBundle>,
+    pub rejected: Vec<RejectedEnvelope>,
+}
+
+/// Envelope that did not take part in the auction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedEnvelope {
+    /// Position in the submitted list
+    pub index: usize,
+    pub kind: String,
+    pub reason: String,
+}
+
+impl RejectedEnvelope {
+    fn new(index: usize, kind: &str, reason: String) -> Self {
+        Self { index, kind: kind.to_string(), reason }
+    }
+}
+
+/// Boundary holding the sealing key and the attestation key. Implementations decrypt bundles and
+/// run the auction without exposing plaintext to the host.
+pub trait EnclaveBackend: Send + Sync {
+    /// Short backend name carried in every attestation
+    fn backend(&self) -> &'static str;
+
+    /// X25519 key searchers seal bundles to
+    fn sealing_key(&self) -> [u8; 32];
+
+    /// Key that signs attestations
+    fn identity(&self) -> Pubkey;
+
+    /// Hash identifying the enclave code
+    fn measurement(&self) -> String;
+
+    /// Open the window's envelopes, rank them and attest to the winning order
+    fn order_window(&self, window_id: u64, sealed: &[BundleEnvelope], max_bundles: usize) -> WindowOrdering;
+}
+
+/// Software stand-in for a hardware enclave, so the service runs and is testable on ordinary Linux.
+/// Keys live in process memory; attestations are labelled `simulated`.
+pub struct SimulatedEnclave {
+    sealing: EnvelopeKey,
+    identity: Keypair,
+    policy: PriorityFeePolicy,
+}
+
+impl SimulatedEnclave {
+    pub const BACKEND: &'static str = "simulated";
+
+    pub fn new(sealing: EnvelopeKey, identity: Keypair) -> Self {
+        Self { sealing, identity, policy: PriorityFeePolicy }
+    }
+}
+
+impl EnclaveBackend for SimulatedEnclave {
+    fn backend(&self) -> &'static str {
+        Self::BACKEND
+    }
+
+    fn sealing_key(&self) -> [u8; 32] {
+        self.sealing.public_key()
+    }
+
+    fn identity(&self) -> Pubkey {
+        self.identity.pubkey()
+    }
+
+    fn measurement(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"tee-service simulated enclave");
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn order_window(&self, window_id: u64, sealed: &[BundleEnvelope], max_bundles: usize) -> WindowOrdering {
+        let mut rejected = Vec::new();
+        let mut opened = HashMap::new();
+        let mut candidates = Vec::new();
+        for (index, envelope) in sealed.iter().enumerate() {
+            if envelope.window_id != window_id {
+                let reason = format!("sealed for window {}, not window {}", envelope.window_id, window_id);
+                rejected.push(RejectedEnvelope::new(index, "window_mismatch", reason));
+                continue;
+            }
+            let decoded = self.sealing.open(envelope, window_id).and_then(|wire| {
+                let bundle = wire.to_bundle().map_err(|e| EnvelopeError::InvalidPayload(e.to_string()))?;
+                bundle.validate().map_err(|e| EnvelopeError::InvalidPayload(e.to_string()))?;
+                Ok((wire, bundle))
+            });
+            match decoded {
+                Ok((wire, bundle)) if opened.contains_key(&bundle.id) => {
+                    rejected.push(RejectedEnvelope::new(index, "duplicate", format!("duplicate bundle {}", wire.id)));
+                }
+                Ok((wire, bundle)) => {
+                    opened.insert(bundle.id, wire);
+                    candidates.push(bundle);
+                }
+                Err(e) => {
+                    debug!(index, error = %e, "Envelope rejected");
+                    rejected.push(RejectedEnvelope::new(index, e.kind(), e.to_string()));
+                }
+            }
+        }
+
+        let mut ranked = self.policy.rank(window_id, candidates);
+        ranked.truncate(max_bundles);
+        let ordered_bundles: Vec<WireBundle> = ranked.iter().filter_map(|b| opened.remove(&b.id)).collect();
+
+        let attestation = OrderingAttestation::sign(
+            &self.identity,
+            window_id,
+            compute_ordered_hash(&ordered_bundles),
+            input_hash(sealed),
+            ordered_bundles.len(),
+            self.measurement(),
+            Self::BACKEND,
+        );
+        info!(
+            "Window {} ordered inside the enclave: {} of {} envelopes won, {} rejected",
+            window_id,
+            ordered_bundles.len(),
+            sealed.len(),
+            rejected.len()
+        );
+        WindowOrdering { attestation, ordered_bundles, rejected }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use block_engine::Bundle;
+    use solana_sdk::{hash::Hash, system_instruction, transaction::Transaction};
+
+    pub(crate) fn wire_bundle(tip: u64) -> WireBundle {
+        let payer = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        WireBundle::from_bundle(&Bundle::new(vec![tx], tip, payer.pubkey().to_string())).unwrap()
+    }
+
+    fn enclave() -> SimulatedEnclave {
+        SimulatedEnclave::new(EnvelopeKey::generate(), Keypair::new())
+    }
+
+    #[test]
+    fn test_orders_sealed_bundles_by_tip_and_attests() {
+        let enclave = enclave();
+        let bundles: Vec<_> = [1000, 3000, 2000].into_iter().map(wire_bundle).collect();
+        let sealed: Vec<_> = bundles
+            .iter()
+            .map(|b| BundleEnvelope::seal(&enclave.sealing_key(), 9, b).unwrap())
+            .collect();
+
+        let ordering = enclave.order_window(9, &sealed, 2);
+        let tips: Vec<_> = ordering.ordered_bundles.iter().map(|b| b.tip).collect();
+        assert_eq!(tips, vec![3000, 2000]);
+        assert!(ordering.rejected.is_empty());
+        assert!(ordering.attestation.verify_ordering(&ordering.ordered_bundles));
+        assert_eq!(ordering.attestation.enclave, enclave.identity().to_string());
+        assert_eq!(ordering.attestation.input_hash, input_hash(&sealed));
+
+        // The same inputs in any order give the same attested ordering
+        let mut reversed = sealed.clone();
+        reversed.reverse();
+        let again = enclave.order_window(9, &reversed, 2);
+        assert_eq!(again.attestation, ordering.attestation);
+    }
+
+    #[test]
+    fn test_bad_envelopes_are_reported_not_fatal() {
+        let enclave = enclave();
+        let good = BundleEnvelope::seal(&enclave.sealing_key(), 9, &wire_bundle(500)).unwrap();
+        let wrong_key = BundleEnvelope::seal(&EnvelopeKey::generate().public_key(), 9, &wire_bundle(900)).unwrap();
+        let wrong_window = BundleEnvelope::seal(&enclave.sealing_key(), 8, &wire_bundle(900)).unwrap();
+        let empty = WireBundle::from_bundle(&Bundle::new(vec![], 900, "s".to_string())).unwrap();
+        let empty = BundleEnvelope::seal(&enclave.sealing_key(), 9, &empty).unwrap();
+
+        let ordering = enclave.order_window(9, &[wrong_key, good.clone(), wrong_window, empty, good], 5);
+        assert_eq!(ordering.ordered_bundles.len(), 1);
+        let kinds: Vec<_> = ordering.rejected.iter().map(|r| (r.index, r.kind.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![(0, "unknown_key"), (2, "window_mismatch"), (3, "invalid_payload"), (4, "duplicate")]
+        );
+    }
+}
@@ -0,0 +1,7 @@
+pub mod attestation;
+pub mod enclave;
+pub mod server;
+
+pub use attestation::{input_hash, OrderingAttestation};
+pub use enclave::{EnclaveBackend, RejectedEnvelope, SimulatedEnclave, WindowOrdering};
+pub use server::{serve, EnclaveInfo, OrderWindowRequest, TeeService};
@@ -1,3 +1,63 @@
-fn main() {
-    println!("Hello, world!");
+use block_engine::EnvelopeKey;
+use clap::Parser;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tee_service::{serve, EnclaveBackend, SimulatedEnclave, TeeService};
+use tracing::{info, warn, Level};
+
+/// Decrypts sealed bundles inside an enclave boundary, orders them and attests to the ordering
+#[derive(Debug, Parser)]
+#[command(name = "tee-service")]
+struct Args {
+    #[arg(long, default_value = "0.0.0.0:4100")]
+    listen: SocketAddr,
+    /// Solana JSON keypair used to sign attestations; a fresh key is generated when omitted
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+    /// File holding the base64 X25519 sealing secret; a fresh key is generated when omitted
+    #[arg(long)]
+    sealing_key: Option<PathBuf>,
+    #[arg(long)]
+    max_bundles: Option<usize>,
+    #[arg(long, default_value = "info")]
+    log_level: Level,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    tracing_subscriber::fmt().with_max_level(args.log_level).init();
+
+    let identity = match &args.keypair {
+        Some(path) => read_keypair_file(path)
+            .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {}", path.display(), e))?,
+        None => {
+            warn!("No --keypair given, attesting with a throwaway identity");
+            Keypair::new()
+        }
+    };
+    let sealing = match &args.sealing_key {
+        Some(path) => EnvelopeKey::from_file(path)
+            .map_err(|e| anyhow::anyhow!("failed to read sealing key {}: {}", path.display(), e))?,
+        None => {
+            warn!("No --sealing-key given, bundles sealed before a restart cannot be opened");
+            EnvelopeKey::generate()
+        }
+    };
+    info!("Enclave identity {}", identity.pubkey());
+
+    let enclave = Arc::new(SimulatedEnclave::new(sealing, identity));
+    info!("Running the {} enclave backend, measurement {}", enclave.backend(), enclave.measurement());
+
+    let mut service = TeeService::new(enclave);
+    if let Some(max_bundles) = args.max_bundles {
+        service = service.with_max_bundles(max_bundles);
+    }
+
+    serve(args.listen, Arc::new(service), async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+    .await
 }
@@ -0,0 +1,160 @@
+use crate::enclave::{EnclaveBackend, WindowOrdering};
+use anyhow::Result;
+use block_engine::{BundleEnvelope, EnvelopePublicKey};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::info;
+
+/// Public description of the enclave, fetched by searchers before sealing bundles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnclaveInfo {
+    pub backend: String,
+    /// Key to seal bundles to
+    pub envelope_key: EnvelopePublicKey,
+    pub identity: String,
+    pub measurement: String,
+}
+
+/// Body of `POST /order_window`: every envelope collected for a closed window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderWindowRequest {
+    pub window_id: u64,
+    pub bundles: Vec<BundleEnvelope>,
+}
+
+/// Serves an enclave backend over HTTP
+pub struct TeeService {
+    enclave: Arc<dyn EnclaveBackend>,
+    max_bundles: usize,
+}
+
+impl TeeService {
+    pub fn new(enclave: Arc<dyn EnclaveBackend>) -> Self {
+        Self {
+            enclave,
+            max_bundles: block_engine::EngineConfig::default().max_bundles_per_block,
+        }
+    }
+
+    pub fn with_max_bundles(mut self, max_bundles: usize) -> Self {
+        self.max_bundles = max_bundles;
+        self
+    }
+
+    pub fn info(&self) -> EnclaveInfo {
+        EnclaveInfo {
+            backend: self.enclave.backend().to_string(),
+            envelope_key: EnvelopePublicKey::new(&self.enclave.sealing_key()),
+            identity: self.enclave.identity().to_string(),
+            measurement: self.enclave.measurement(),
+        }
+    }
+
+    pub fn order_window(&self, request: &OrderWindowRequest) -> WindowOrdering {
+        self.enclave.order_window(request.window_id, &request.bundles, self.max_bundles)
+    }
+}
+
+fn json<T: Serialize>(status: StatusCode, body: &T) -> hyper::http::Result<Response<Body>> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_vec(body).unwrap_or_default()))
+}
+
+async fn handle(service: Arc<TeeService>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/enclave") => json(StatusCode::OK, &service.info()),
+        (&Method::POST, "/order_window") => match hyper::body::to_bytes(request.into_body()).await {
+            Ok(body) => match serde_json::from_slice::<OrderWindowRequest>(&body) {
+                Ok(order) => json(StatusCode::OK, &service.order_window(&order)),
+                Err(e) => Response::builder().status(StatusCode::BAD_REQUEST).body(Body::from(e.to_string())),
+            },
+            Err(_) => Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()),
+        },
+        (&Method::GET, "/healthz") => Response::builder().body(Body::from("ok")),
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+/// Serve `GET /enclave`, `POST /order_window` and `GET /healthz` on `addr` until `shutdown` resolves
+pub async fn serve<F: Future<Output = ()>>(addr: SocketAddr, service: Arc<TeeService>, shutdown: F) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let service = Arc::clone(&service);
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle(Arc::clone(&service), request))) }
+    });
+
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    info!("TEE service listening on http://{}/order_window", server.local_addr());
+    server.with_graceful_shutdown(shutdown).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enclave::SimulatedEnclave;
+    use crate::enclave::tests::wire_bundle;
+    use block_engine::EnvelopeKey;
+    use solana_sdk::signature::Keypair;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_searcher_seals_to_published_key_and_gets_attested_order() {
+        let enclave = Arc::new(SimulatedEnclave::new(EnvelopeKey::generate(), Keypair::new()));
+        let service = Arc::new(TeeService::new(enclave).with_max_bundles(1));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(serve(addr, service, async {
+            let _ = stopped.await;
+        }));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = reqwest::Client::new();
+        let info: EnclaveInfo = client
+            .get(format!("http://{}/enclave", addr))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(info.backend, "simulated");
+        let sealing_key = info.envelope_key.to_bytes().unwrap();
+
+        let winner = wire_bundle(5000);
+        let request = OrderWindowRequest {
+            window_id: 2,
+            bundles: vec![
+                BundleEnvelope::seal(&sealing_key, 2, &wire_bundle(10)).unwrap(),
+                BundleEnvelope::seal(&sealing_key, 2, &winner).unwrap(),
+            ],
+        };
+        let ordering: WindowOrdering = client
+            .post(format!("http://{}/order_window", addr))
+            .json(&request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(ordering.ordered_bundles, vec![winner]);
+        assert!(ordering.attestation.verify_ordering(&ordering.ordered_bundles));
+        assert_eq!(ordering.attestation.enclave, info.identity);
+
+        let malformed = client.post(format!("http://{}/order_window", addr)).body("{}").send().await.unwrap();
+        assert_eq!(malformed.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        stop.send(()).unwrap();
+    }
+}
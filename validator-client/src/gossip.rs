@@ -0,0 +1,506 @@
+use crate::server::{SignedAck, ValidatorService};
+use block_engine::OrderedBlock;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use anyhow::Result;
+
+/// Messages queued per peer before new ones are dropped
+const PEER_QUEUE_CAPACITY: usize = 1024;
+
+/// Pause between attempts to reach a peer that is down or not started yet
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+/// Longest gossip line accepted; a peer that sends more without a newline is disconnected
+const MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Slots behind the newest one seen for which blocks and votes are still tracked
+const RETAINED_SLOTS: u64 = 512;
+
+/// Outgoing lines for one peer, waiting for its writer to start
+type PeerQueue = (SocketAddr, mpsc::Receiver<Arc<str>>);
+
+/// One line of the gossip stream, JSON encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GossipMessage {
+    /// A block accepted by `origin`, forwarded unchanged by every node that accepts it too
+    Block { origin: String, sent_at_ms: u64, block: Box<OrderedBlock> },
+    /// A validator's signed acceptance of a block
+    Vote { sent_at_ms: u64, ack: SignedAck },
+}
+
+impl GossipMessage {
+    fn slot(&self) -> u64 {
+        match self {
+            GossipMessage::Block { block, .. } => block.block.slot,
+            GossipMessage::Vote { ack, .. } => ack.slot,
+        }
+    }
+}
+
+/// What this node has seen of one block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockPropagation {
+    pub slot: u64,
+    pub blockhash: String,
+    /// Validator that first accepted the block; unknown while only votes have arrived
+    pub origin: Option<String>,
+    /// Time from the origin sending the block to it arriving here; 0 at the origin
+    pub delay_ms: Option<u64>,
+    /// Distinct validators whose vote for the block arrived here, this one included
+    pub votes: usize,
+}
+
+/// Body of a `GET /gossip` response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GossipStats {
+    pub validator: String,
+    pub peers: usize,
+    /// Oldest slot first
+    pub blocks: Vec<BlockPropagation>,
+}
+
+#[derive(Debug, Default)]
+struct BlockRecord {
+    slot: u64,
+    origin: Option<String>,
+    delay_ms: Option<u64>,
+    voters: HashSet<String>,
+}
+
+/// Blocks by blockhash, limited to the last `RETAINED_SLOTS` slots
+#[derive(Debug, Default)]
+struct Records {
+    blocks: HashMap<String, BlockRecord>,
+    newest_slot: u64,
+}
+
+/// Floods accepted blocks and votes to peers over TCP. Every node forwards what it has not seen
+/// before, so messages reach validators that are not direct peers. Delays come from the sender's
+/// wall clock and are only meaningful between processes on one machine.
+pub struct Gossip {
+    identity: String,
+    peers: Vec<SocketAddr>,
+    outbound: Vec<mpsc::Sender<Arc<str>>>,
+    /// Taken by `serve_gossip` to start one writer per peer
+    pending_writers: Mutex<Option<Vec<PeerQueue>>>,
+    records: Mutex<Records>,
+    /// Validators whose votes are counted; any validator's when unset
+    validators: Option<HashSet<String>>,
+}
+
+impl Gossip {
+    pub fn new(identity: Pubkey, peers: Vec<SocketAddr>) -> Self {
+        let (outbound, writers) = peers
+            .iter()
+            .map(|peer| {
+                let (sender, receiver) = mpsc::channel(PEER_QUEUE_CAPACITY);
+                (sender, (*peer, receiver))
+            })
+            .unzip();
+        Self {
+            identity: identity.to_string(),
+            peers,
+            outbound,
+            pending_writers: Mutex::new(Some(writers)),
+            records: Mutex::new(Records::default()),
+            validators: None,
+        }
+    }
+
+    /// Count only votes from these validators (and this one); others are dropped, not forwarded
+    pub fn with_validators(mut self, validators: impl IntoIterator<Item = Pubkey>) -> Self {
+        let mut known: HashSet<String> = validators.into_iter().map(|v| v.to_string()).collect();
+        known.insert(self.identity.clone());
+        self.validators = Some(known);
+        self
+    }
+
+    pub fn peers(&self) -> &[SocketAddr] {
+        &self.peers
+    }
+
+    /// Announce a block this validator accepted from the engine, along with its own vote
+    pub fn publish_block(&self, block: &OrderedBlock, ack: &SignedAck) {
+        let message = GossipMessage::Block {
+            origin: self.identity.clone(),
+            sent_at_ms: now_ms(),
+            block: Box::new(block.clone()),
+        };
+        if self.observe(&message) {
+            self.broadcast(&message);
+        }
+        self.publish_vote(ack);
+    }
+
+    pub fn publish_vote(&self, ack: &SignedAck) {
+        let message = GossipMessage::Vote { sent_at_ms: now_ms(), ack: ack.clone() };
+        if self.observe(&message) {
+            self.broadcast(&message);
+        }
+    }
+
+    /// Votes received for `blockhash`, this validator's own included
+    pub fn votes(&self, blockhash: &str) -> usize {
+        self.records.lock().unwrap().blocks.get(blockhash).map_or(0, |r| r.voters.len())
+    }
+
+    pub fn stats(&self) -> GossipStats {
+        let records = self.records.lock().unwrap();
+        let mut blocks: Vec<BlockPropagation> = records
+            .blocks
+            .iter()
+            .map(|(blockhash, record)| BlockPropagation {
+                slot: record.slot,
+                blockhash: blockhash.clone(),
+                origin: record.origin.clone(),
+                delay_ms: record.delay_ms,
+                votes: record.voters.len(),
+            })
+            .collect();
+        blocks.sort_by(|a, b| (a.slot, &a.blockhash).cmp(&(b.slot, &b.blockhash)));
+        GossipStats { validator: self.identity.clone(), peers: self.peers.len(), blocks }
+    }
+
+    fn knows_block(&self, blockhash: &str) -> bool {
+        self.records.lock().unwrap().blocks.get(blockhash).is_some_and(|r| r.origin.is_some())
+    }
+
+    fn is_known_validator(&self, validator: &str) -> bool {
+        self.validators.as_ref().is_none_or(|known| known.contains(validator))
+    }
+
+    /// Whether `slot` is more than `RETAINED_SLOTS` past both the ledger tip and the newest slot seen,
+    /// so a vote for it would prune everything tracked so far
+    fn is_too_far_ahead(&self, slot: u64, ledger_tip: Option<u64>) -> bool {
+        let newest = self.records.lock().unwrap().newest_slot.max(ledger_tip.unwrap_or(0));
+        slot > newest.saturating_add(RETAINED_SLOTS)
+    }
+
+    /// Record a message, returning whether it was new. Messages for slots no longer tracked are not.
+    fn observe(&self, message: &GossipMessage) -> bool {
+        let mut records = self.records.lock().unwrap();
+        let slot = message.slot();
+        if slot.saturating_add(RETAINED_SLOTS) < records.newest_slot {
+            return false;
+        }
+        if slot > records.newest_slot {
+            records.newest_slot = slot;
+            let oldest = slot.saturating_sub(RETAINED_SLOTS);
+            records.blocks.retain(|_, record| record.slot >= oldest);
+        }
+        match message {
+            GossipMessage::Block { origin, sent_at_ms, block } => {
+                let record = records.blocks.entry(block.block.blockhash.to_string()).or_default();
+                if record.origin.is_some() {
+                    return false;
+                }
+                record.slot = block.block.slot;
+                record.origin = Some(origin.clone());
+                record.delay_ms = Some(now_ms().saturating_sub(*sent_at_ms));
+                true
+            }
+            GossipMessage::Vote { ack, .. } => {
+                let record = records.blocks.entry(ack.blockhash.clone()).or_default();
+                record.slot = ack.slot;
+                record.voters.insert(ack.validator.clone())
+            }
+        }
+    }
+
+    fn broadcast(&self, message: &GossipMessage) {
+        let line: Arc<str> = match serde_json::to_string(message) {
+            Ok(line) => line.into(),
+            Err(e) => {
+                warn!("Failed to encode gossip message: {}", e);
+                return;
+            }
+        };
+        for (peer, sender) in self.peers.iter().zip(&self.outbound) {
+            if sender.try_send(Arc::clone(&line)).is_err() {
+                warn!("Gossip queue to {} is full, dropping message", peer);
+            }
+        }
+    }
+
+    /// Apply a block or vote from a peer and pass it on if it is new
+    fn handle(&self, message: GossipMessage, service: &ValidatorService) {
+        match &message {
+            GossipMessage::Block { origin, block, .. } => {
+                if self.knows_block(&block.block.blockhash.to_string()) {
+                    return;
+                }
+                // Only accepted blocks are recorded, so a rejected one is not mistaken for seen
+                match service.apply(block) {
+                    Ok(ack) => {
+                        info!("Accepted gossiped block for slot {} from {}", block.window_id, origin);
+                        if self.observe(&message) {
+                            self.broadcast(&message);
+                        }
+                        self.publish_vote(&ack);
+                    }
+                    Err(rejection) => warn!("Rejected gossiped block for slot {}: {}", block.window_id, rejection),
+                }
+            }
+            GossipMessage::Vote { ack, .. } => {
+                if !self.is_known_validator(&ack.validator) {
+                    debug!("Dropping vote from unknown validator {}", ack.validator);
+                    return;
+                }
+                if !ack.verify() {
+                    warn!("Dropping vote with a bad signature from {}", ack.validator);
+                    return;
+                }
+                if self.is_too_far_ahead(ack.slot, service.ledger_tip().map(|(slot, _)| slot)) {
+                    debug!("Dropping vote from {} for slot {}, too far ahead", ack.validator, ack.slot);
+                    return;
+                }
+                if self.observe(&message) {
+                    debug!("Vote from {} for slot {}", ack.validator, ack.slot);
+                    self.broadcast(&message);
+                }
+            }
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Keep a connection to `peer` open and write queued messages to it, reconnecting as needed
+async fn write_to_peer(peer: SocketAddr, mut queue: mpsc::Receiver<Arc<str>>) {
+    let mut stream: Option<TcpStream> = None;
+    while let Some(line) = queue.recv().await {
+        loop {
+            let connection = match stream.as_mut() {
+                Some(connection) => connection,
+                None => match TcpStream::connect(peer).await {
+                    Ok(connection) => {
+                        debug!("Connected to gossip peer {}", peer);
+                        stream.insert(connection)
+                    }
+                    Err(_) => {
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                        continue;
+                    }
+                },
+            };
+            let written = connection.write_all(line.as_bytes()).await.and(connection.write_all(b"\n").await);
+            match written {
+                Ok(()) => break,
+                Err(e) => {
+                    warn!("Lost gossip peer {}: {}", peer, e);
+                    stream = None;
+                }
+            }
+        }
+    }
+}
+
+/// Handle each line a peer sends until it disconnects or sends a line over `MAX_MESSAGE_BYTES`
+async fn read_from_peer<S: AsyncRead + Unpin>(stream: S, gossip: Arc<Gossip>, service: Arc<ValidatorService>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match (&mut reader).take(MAX_MESSAGE_BYTES as u64 + 1).read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.len() > MAX_MESSAGE_BYTES {
+            warn!("Gossip peer sent a message over {} bytes, disconnecting", MAX_MESSAGE_BYTES);
+            return;
+        }
+        match serde_json::from_slice::<GossipMessage>(&line) {
+            Ok(message) => gossip.handle(message, &service),
+            Err(e) => warn!("Dropping malformed gossip message: {}", e),
+        }
+    }
+}
+
+/// Exchange gossip on `addr` for the service's `Gossip` until `shutdown` resolves
+pub async fn serve_gossip<F: Future<Output = ()>>(
+    addr: SocketAddr,
+    service: Arc<ValidatorService>,
+    shutdown: F,
+) -> Result<()> {
+    let gossip = Arc::clone(service.gossip().ok_or_else(|| anyhow::anyhow!("validator has no gossip configured"))?);
+    let writers = gossip
+        .pending_writers
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("gossip is already being served"))?;
+    for (peer, queue) in writers {
+        tokio::spawn(write_to_peer(peer, queue));
+    }
+
+    let listener = TcpListener::bind(addr).await?;
+    info!("Gossiping on {} with {} peers", listener.local_addr()?, gossip.peers().len());
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => return Ok(()),
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(read_from_peer(stream, Arc::clone(&gossip), Arc::clone(&service)));
+                }
+                Err(e) => warn!("Failed to accept gossip connection: {}", e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::Ledger;
+    use crate::verify::tests::{ordered_block, signed_bundle};
+    use solana_sdk::hash::Hash;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    fn node(peers: Vec<SocketAddr>) -> Arc<ValidatorService> {
+        let identity = Keypair::new();
+        let gossip = Arc::new(Gossip::new(identity.pubkey(), peers));
+        Arc::new(ValidatorService::new(identity, Ledger::default()).with_gossip(gossip))
+    }
+
+    fn free_addr() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_blocks_and_votes_flood_past_direct_peers() {
+        // a <-> b <-> c, so a's block reaches c only through b
+        let (a_addr, b_addr, c_addr) = (free_addr(), free_addr(), free_addr());
+        let nodes = [node(vec![b_addr]), node(vec![a_addr, c_addr]), node(vec![b_addr])];
+        let (stop, _) = tokio::sync::broadcast::channel::<()>(1);
+        for (addr, service) in [a_addr, b_addr, c_addr].into_iter().zip(&nodes) {
+            let mut stopped = stop.subscribe();
+            tokio::spawn(serve_gossip(addr, Arc::clone(service), async move {
+                let _ = stopped.recv().await;
+            }));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let block = ordered_block(7, Hash::default(), vec![signed_bundle(&Keypair::new(), 10, 500)]).await;
+        let body = serde_json::to_vec(&block).unwrap();
+        assert!(matches!(nodes[0].submit(&body).1, crate::SubmitBlockResponse::Accepted(_)));
+
+        let blockhash = block.block.blockhash.to_string();
+        let all_voted = || nodes.iter().all(|n| n.gossip().unwrap().votes(&blockhash) == 3);
+        for _ in 0..100 {
+            if all_voted() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(all_voted());
+
+        let seen_at_c = nodes[2].gossip().unwrap().stats();
+        assert_eq!(seen_at_c.blocks[0].origin, Some(nodes[0].pubkey().to_string()));
+        assert!(seen_at_c.blocks[0].delay_ms.is_some());
+        assert_eq!(nodes[2].ledger_tip(), Some((7, block.block.blockhash)));
+        let _ = stop.send(());
+    }
+
+    fn vote(identity: &Keypair, slot: u64, blockhash: &Hash) -> GossipMessage {
+        GossipMessage::Vote { sent_at_ms: now_ms(), ack: SignedAck::sign(identity, slot, blockhash, "ordered") }
+    }
+
+    #[tokio::test]
+    async fn test_rejected_blocks_are_not_remembered() {
+        let service = node(vec![]);
+        let gossip = service.gossip().unwrap();
+        let block = ordered_block(3, Hash::default(), vec![signed_bundle(&Keypair::new(), 10, 500)]).await;
+        let message = |block: &OrderedBlock| GossipMessage::Block {
+            origin: "peer".to_string(),
+            sent_at_ms: now_ms(),
+            block: Box::new(block.clone()),
+        };
+
+        let mut tampered = block.clone();
+        tampered.ordered_hash = "0".repeat(64);
+        gossip.handle(message(&tampered), &service);
+        assert!(gossip.stats().blocks.is_empty());
+
+        gossip.handle(message(&block), &service);
+        assert_eq!(gossip.stats().blocks[0].origin.as_deref(), Some("peer"));
+        assert_eq!(service.ledger_tip(), Some((3, block.block.blockhash)));
+    }
+
+    #[test]
+    fn test_votes_only_from_known_validators() {
+        let (known, stranger) = (Keypair::new(), Keypair::new());
+        let gossip = Gossip::new(Pubkey::new_unique(), vec![]).with_validators([known.pubkey()]);
+        let service = ValidatorService::new(Keypair::new(), Ledger::default());
+        let blockhash = Hash::new_unique();
+
+        gossip.handle(vote(&stranger, 1, &blockhash), &service);
+        assert_eq!(gossip.votes(&blockhash.to_string()), 0);
+        gossip.handle(vote(&known, 1, &blockhash), &service);
+        assert_eq!(gossip.votes(&blockhash.to_string()), 1);
+    }
+
+    #[test]
+    fn test_records_are_pruned_by_slot() {
+        let gossip = Gossip::new(Pubkey::new_unique(), vec![]);
+        let voter = Keypair::new();
+        let (old, new) = (Hash::new_unique(), Hash::new_unique());
+
+        assert!(gossip.observe(&vote(&voter, 1, &old)));
+        assert!(gossip.observe(&vote(&voter, RETAINED_SLOTS + 10, &new)));
+        assert_eq!(gossip.stats().blocks.len(), 1);
+        assert_eq!(gossip.votes(&old.to_string()), 0);
+
+        // A late message for a pruned slot is not tracked or forwarded again
+        assert!(!gossip.observe(&vote(&Keypair::new(), 1, &old)));
+    }
+
+    #[test]
+    fn test_votes_far_ahead_are_ignored() {
+        let gossip = Gossip::new(Pubkey::new_unique(), vec![]);
+        let service = ValidatorService::new(Keypair::new(), Ledger::default());
+        let voter = Keypair::new();
+        let (tracked, far) = (Hash::new_unique(), Hash::new_unique());
+
+        gossip.handle(vote(&voter, 5, &tracked), &service);
+        gossip.handle(vote(&voter, u64::MAX, &far), &service);
+        gossip.handle(vote(&voter, 5 + RETAINED_SLOTS + 1, &far), &service);
+        assert_eq!(gossip.votes(&far.to_string()), 0);
+        assert_eq!(gossip.votes(&tracked.to_string()), 1);
+
+        // Within reach of the newest slot, a vote still counts
+        gossip.handle(vote(&voter, 5 + RETAINED_SLOTS, &far), &service);
+        assert_eq!(gossip.votes(&far.to_string()), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_lines_disconnect_the_peer() {
+        let service = node(vec![]);
+        let gossip = Arc::clone(service.gossip().unwrap());
+        let (mut peer, stream) = tokio::io::duplex(64 * 1024);
+        let reader = tokio::spawn(read_from_peer(stream, gossip, service));
+
+        let chunk = vec![b'x'; 64 * 1024];
+        for _ in 0..=MAX_MESSAGE_BYTES / chunk.len() {
+            if peer.write_all(&chunk).await.is_err() {
+                break;
+            }
+        }
+        tokio::time::timeout(Duration::from_secs(5), reader).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+use crate::verify::BlockRejection;
+use block_engine::Block;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction::SystemInstruction,
+    system_program,
+    transaction::Transaction,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Fee charged to the payer of every transaction, matching the engine's block accounting
+pub const FEE_PER_TRANSACTION: u64 = 5000;
+
+/// Lamports credited to an account the first time the ledger sees it, standing in for a faucet
+pub const DEFAULT_INITIAL_BALANCE: u64 = 1_000_000_000_000;
+
+/// Result of applying a block to the ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedBlock {
+    pub slot: u64,
+    pub blockhash: Hash,
+    pub transactions: usize,
+    pub fees: u64,
+}
+
+/// Account balances and chain tip as seen by this validator. System transfers move lamports,
+/// every transaction pays a flat fee, and other instructions are accepted without execution.
+#[derive(Debug)]
+pub struct Ledger {
+    balances: HashMap<Pubkey, u64>,
+    initial_balance: u64,
+    processed: HashSet<Signature>,
+    tip: Option<(u64, Hash)>,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self::new(DEFAULT_INITIAL_BALANCE)
+    }
+}
+
+impl Ledger {
+    pub fn new(initial_balance: u64) -> Self {
+        Self {
+            balances: HashMap::new(),
+            initial_balance,
+            processed: HashSet::new(),
+            tip: None,
+        }
+    }
+
+    pub fn balance(&self, pubkey: &Pubkey) -> u64 {
+        self.balances.get(pubkey).copied().unwrap_or(self.initial_balance)
+    }
+
+    /// Slot and hash of the last accepted block
+    pub fn tip(&self) -> Option<(u64, Hash)> {
+        self.tip
+    }
+
+    /// Execute the block's transactions in order. Either every transaction applies or the ledger is left unchanged.
+    ///
+    /// A default parent hash marks an engine that has just (re)started its chain and is accepted after any tip.
+    pub fn apply(&mut self, block: &Block) -> Result<AppliedBlock, BlockRejection> {
+        if let Some((last_slot, last_hash)) = self.tip {
+            if block.slot <= last_slot {
+                return Err(BlockRejection::StaleSlot { slot: block.slot, last: last_slot });
+            }
+            if block.parent_hash != Hash::default() && block.parent_hash != last_hash {
+                return Err(BlockRejection::ParentMismatch {
+                    parent: block.parent_hash.to_string(),
+                    expected: last_hash.to_string(),
+                });
+            }
+        }
+
+        // Stage balance changes and signatures so a failing transaction leaves the ledger untouched
+        let mut staged = Staged { ledger: self, balances: HashMap::new() };
+        let mut signatures = HashSet::new();
+        let mut fees = 0;
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let signature = tx.signatures.first().copied().unwrap_or_default();
+            if self.processed.contains(&signature) || !signatures.insert(signature) {
+                return Err(BlockRejection::DuplicateTransaction { index });
+            }
+            fees += staged
+                .execute(tx)
+                .map_err(|reason| BlockRejection::TransactionFailed { index, reason })?;
+        }
+
+        let balances = staged.balances;
+        self.balances.extend(balances);
+        self.processed.extend(signatures);
+        self.tip = Some((block.slot, block.blockhash));
+        Ok(AppliedBlock {
+            slot: block.slot,
+            blockhash: block.blockhash,
+            transactions: block.transactions.len(),
+            fees,
+        })
+    }
+}
+
+/// Balances changed by the block being applied, layered over the committed ledger
+struct Staged<'a> {
+    ledger: &'a Ledger,
+    balances: HashMap<Pubkey, u64>,
+}
+
+impl Staged<'_> {
+    fn balance(&mut self, pubkey: &Pubkey) -> &mut u64 {
+        let committed = self.ledger.balance(pubkey);
+        self.balances.entry(*pubkey).or_insert(committed)
+    }
+
+    fn execute(&mut self, tx: &Transaction) -> Result<u64, String> {
+        let keys = &tx.message.account_keys;
+        let payer = keys.first().ok_or("transaction has no fee payer")?;
+        self.debit(payer, FEE_PER_TRANSACTION)
+            .map_err(|balance| format!("fee payer {} has {} lamports, fee is {}", payer, balance, FEE_PER_TRANSACTION))?;
+
+        for instruction in &tx.message.instructions {
+            let program = keys
+                .get(instruction.program_id_index as usize)
+                .ok_or("instruction references a missing program account")?;
+            if *program != system_program::id() {
+                continue;
+            }
+            if let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&instruction.data) {
+                let account = |position: usize| {
+                    instruction
+                        .accounts
+                        .get(position)
+                        .and_then(|index| keys.get(*index as usize))
+                        .ok_or("transfer is missing an account")
+                };
+                let (from, to) = (account(0)?, account(1)?);
+                self.debit(from, lamports)
+                    .map_err(|balance| format!("{} has {} lamports, cannot send {}", from, balance, lamports))?;
+                *self.balance(to) += lamports;
+            }
+        }
+        Ok(FEE_PER_TRANSACTION)
+    }
+
+    /// Take `lamports` from `pubkey`, or return its balance if it cannot cover them
+    fn debit(&mut self, pubkey: &Pubkey, lamports: u64) -> Result<(), u64> {
+        let balance = self.balance(pubkey);
+        *balance = balance.checked_sub(lamports).ok_or(*balance)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::tests::{ordered_block, signed_bundle};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[tokio::test]
+    async fn test_apply_moves_lamports_and_advances_tip() {
+        let payer = Keypair::new();
+        let block = ordered_block(1, Hash::default(), vec![signed_bundle(&payer, 1_000, 2000)]).await.block;
+        let mut ledger = Ledger::new(10_000);
+
+        let applied = ledger.apply(&block).unwrap();
+        assert_eq!(applied.fees, FEE_PER_TRANSACTION);
+        assert_eq!(ledger.balance(&payer.pubkey()), 10_000 - 1_000 - FEE_PER_TRANSACTION);
+        assert_eq!(ledger.tip(), Some((1, block.blockhash)));
+
+        // Replaying the same transactions in a later block is refused
+        let mut replay = block.clone();
+        replay.slot = 2;
+        replay.parent_hash = block.blockhash;
+        assert_eq!(ledger.apply(&replay), Err(BlockRejection::DuplicateTransaction { index: 0 }));
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_leaves_ledger_unchanged() {
+        let rich = Keypair::new();
+        let poor = Keypair::new();
+        let mut ledger = Ledger::new(10_000);
+        let block = ordered_block(
+            1,
+            Hash::default(),
+            vec![signed_bundle(&rich, 100, 2000), signed_bundle(&poor, 50_000, 1000)],
+        )
+        .await
+        .block;
+
+        let err = ledger.apply(&block).unwrap_err();
+        assert!(matches!(err, BlockRejection::TransactionFailed { index: 1, .. }));
+        assert_eq!(ledger.balance(&rich.pubkey()), 10_000);
+        assert_eq!(ledger.tip(), None);
+    }
+
+    #[tokio::test]
+    async fn test_chain_continuity_enforced() {
+        let mut ledger = Ledger::default();
+        let first = ordered_block(5, Hash::default(), vec![signed_bundle(&Keypair::new(), 1, 1)]).await.block;
+        ledger.apply(&first).unwrap();
+
+        let stale = ordered_block(5, first.blockhash, vec![signed_bundle(&Keypair::new(), 1, 1)]).await.block;
+        assert!(matches!(ledger.apply(&stale), Err(BlockRejection::StaleSlot { .. })));
+
+        let orphan = ordered_block(6, Hash::new_unique(), vec![signed_bundle(&Keypair::new(), 1, 1)]).await.block;
+        assert!(matches!(ledger.apply(&orphan), Err(BlockRejection::ParentMismatch { .. })));
+
+        let child = ordered_block(6, first.blockhash, vec![signed_bundle(&Keypair::new(), 1, 1)]).await.block;
+        assert!(ledger.apply(&child).is_ok());
+    }
+}
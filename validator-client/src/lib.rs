@@ -0,0 +1,9 @@
+pub mod verify;
+pub mod ledger;
+pub mod server;
+pub mod gossip;
+
+pub use verify::{verify_attestation, verify_block, BlockRejection, RejectionCode};
+pub use ledger::{AppliedBlock, Ledger, DEFAULT_INITIAL_BALANCE, FEE_PER_TRANSACTION};
+pub use server::{ack_message, serve, SignedAck, SubmitBlockResponse, ValidatorService};
+pub use gossip::{serve_gossip, BlockPropagation, Gossip, GossipMessage, GossipStats};
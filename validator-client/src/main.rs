@@ -1,3 +1,95 @@
-fn main() {
-    println!("Hello, world!");
+use clap::Parser;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn, Level};
+use validator_client::{serve, serve_gossip, Gossip, Ledger, ValidatorService, DEFAULT_INITIAL_BALANCE};
+
+/// Receives ordered blocks from the block engine, re-verifies them and applies them to a local ledger
+#[derive(Debug, Parser)]
+#[command(name = "validator-client")]
+struct Args {
+    #[arg(long, default_value = "0.0.0.0:4000")]
+    listen: SocketAddr,
+    /// Solana JSON keypair used to sign acknowledgments; a fresh key is generated when omitted
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+    /// Lamports every account starts with the first time it is seen
+    #[arg(long, default_value_t = DEFAULT_INITIAL_BALANCE)]
+    initial_balance: u64,
+    #[arg(long)]
+    max_transactions: Option<usize>,
+    /// Only accept blocks attested by this engine identity
+    #[arg(long)]
+    engine_pubkey: Option<Pubkey>,
+    /// Address to exchange block and vote gossip on; gossip is off when omitted
+    #[arg(long)]
+    gossip_listen: Option<SocketAddr>,
+    /// Gossip address of another validator; repeat for each peer
+    #[arg(long = "peer")]
+    peers: Vec<SocketAddr>,
+    /// Identity of a validator whose gossiped votes count; repeat for each. Votes from any
+    /// validator count when omitted
+    #[arg(long = "validator")]
+    validators: Vec<Pubkey>,
+    #[arg(long, default_value = "info")]
+    log_level: Level,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    tracing_subscriber::fmt().with_max_level(args.log_level).init();
+
+    let identity = match &args.keypair {
+        Some(path) => read_keypair_file(path)
+            .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {}", path.display(), e))?,
+        None => {
+            warn!("No --keypair given, signing with a throwaway identity");
+            Keypair::new()
+        }
+    };
+    let identity_pubkey = identity.pubkey();
+    info!("Validator identity {}", identity_pubkey);
+
+    let mut service = ValidatorService::new(identity, Ledger::new(args.initial_balance));
+    if let Some(max_transactions) = args.max_transactions {
+        service = service.with_max_transactions(max_transactions);
+    }
+    match args.engine_pubkey {
+        Some(engine) => {
+            info!("Accepting only blocks attested by engine {}", engine);
+            service = service.with_trusted_engine(engine);
+        }
+        None => warn!("No --engine-pubkey given, attestations are checked only when present"),
+    }
+
+    let Some(gossip_listen) = args.gossip_listen else {
+        if !args.peers.is_empty() {
+            warn!("--peer has no effect without --gossip-listen");
+        }
+        return serve(args.listen, Arc::new(service), async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await;
+    };
+
+    let mut gossip = Gossip::new(identity_pubkey, args.peers);
+    if args.validators.is_empty() {
+        warn!("No --validator given, gossiped votes from any validator are counted");
+    } else {
+        gossip = gossip.with_validators(args.validators);
+    }
+    let service = Arc::new(service.with_gossip(Arc::new(gossip)));
+    tokio::try_join!(
+        serve(args.listen, Arc::clone(&service), async {
+            let _ = tokio::signal::ctrl_c().await;
+        }),
+        serve_gossip(gossip_listen, service, async {
+            let _ = tokio::signal::ctrl_c().await;
+        }),
+    )?;
+    Ok(())
 }
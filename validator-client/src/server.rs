@@ -0,0 +1,301 @@
+use crate::gossip::Gossip;
+use crate::ledger::Ledger;
+use crate::verify::{verify_attestation, verify_block, BlockRejection, RejectionCode};
+use block_engine::OrderedBlock;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use anyhow::Result;
+
+/// Body of a `/submit_block` response. `status`, `slot` and `reason` are what the engine's `BlockAck` reads.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SubmitBlockResponse {
+    Accepted(SignedAck),
+    Rejected {
+        slot: Option<u64>,
+        code: RejectionCode,
+        reason: String,
+        validator: String,
+    },
+}
+
+/// Acceptance signed with the validator's identity key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedAck {
+    pub slot: u64,
+    pub blockhash: String,
+    pub ordered_hash: String,
+    pub validator: String,
+    pub signature: String,
+}
+
+impl SignedAck {
+    pub fn sign(identity: &Keypair, slot: u64, blockhash: &Hash, ordered_hash: &str) -> Self {
+        let signature = identity.sign_message(&ack_message(slot, blockhash, ordered_hash));
+        Self {
+            slot,
+            blockhash: blockhash.to_string(),
+            ordered_hash: ordered_hash.to_string(),
+            validator: identity.pubkey().to_string(),
+            signature: signature.to_string(),
+        }
+    }
+
+    /// Check the signature against the validator key named in the acknowledgment
+    pub fn verify(&self) -> bool {
+        let (Ok(validator), Ok(signature), Ok(blockhash)) = (
+            Pubkey::from_str(&self.validator),
+            Signature::from_str(&self.signature),
+            Hash::from_str(&self.blockhash),
+        ) else {
+            return false;
+        };
+        signature.verify(validator.as_ref(), &ack_message(self.slot, &blockhash, &self.ordered_hash))
+    }
+}
+
+/// Bytes a validator signs to acknowledge a block
+pub fn ack_message(slot: u64, blockhash: &Hash, ordered_hash: &str) -> Vec<u8> {
+    let mut message = b"block-ack".to_vec();
+    message.extend_from_slice(&slot.to_le_bytes());
+    message.extend_from_slice(blockhash.as_ref());
+    message.extend_from_slice(ordered_hash.as_bytes());
+    message
+}
+
+/// Verifies and applies blocks posted by the engine
+pub struct ValidatorService {
+    identity: Keypair,
+    ledger: Mutex<Ledger>,
+    max_transactions: usize,
+    /// When set, only blocks attested by this engine are accepted
+    engine: Option<Pubkey>,
+    /// Peers told about every block accepted from the engine
+    gossip: Option<Arc<Gossip>>,
+}
+
+impl ValidatorService {
+    pub fn new(identity: Keypair, ledger: Ledger) -> Self {
+        Self {
+            identity,
+            ledger: Mutex::new(ledger),
+            max_transactions: block_engine::EngineConfig::default().max_transactions_per_block,
+            engine: None,
+            gossip: None,
+        }
+    }
+
+    /// Announce accepted blocks and votes to peers; start the transport with `serve_gossip`
+    pub fn with_gossip(mut self, gossip: Arc<Gossip>) -> Self {
+        self.gossip = Some(gossip);
+        self
+    }
+
+    pub fn gossip(&self) -> Option<&Arc<Gossip>> {
+        self.gossip.as_ref()
+    }
+
+    /// Require every block to carry a valid attestation from `engine`
+    pub fn with_trusted_engine(mut self, engine: Pubkey) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    pub fn with_max_transactions(mut self, max_transactions: usize) -> Self {
+        self.max_transactions = max_transactions;
+        self
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.identity.pubkey()
+    }
+
+    pub fn ledger_tip(&self) -> Option<(u64, Hash)> {
+        self.ledger.lock().unwrap().tip()
+    }
+
+    /// Decode, verify and apply a submitted block, returning the HTTP status and response body
+    pub fn submit(&self, body: &[u8]) -> (StatusCode, SubmitBlockResponse) {
+        let block: OrderedBlock = match serde_json::from_slice(body) {
+            Ok(block) => block,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    self.rejection(None, BlockRejection::Malformed(e.to_string())),
+                )
+            }
+        };
+        let slot = block.window_id;
+
+        match self.apply(&block) {
+            Ok(ack) => {
+                if let Some(gossip) = &self.gossip {
+                    gossip.publish_block(&block, &ack);
+                }
+                (StatusCode::OK, SubmitBlockResponse::Accepted(ack))
+            }
+            Err(rejection) => {
+                warn!("Rejected block for slot {}: {}", slot, rejection);
+                (StatusCode::UNPROCESSABLE_ENTITY, self.rejection(Some(slot), rejection))
+            }
+        }
+    }
+
+    /// Verify and apply a decoded block, signing an acknowledgment once it is accepted
+    pub fn apply(&self, block: &OrderedBlock) -> Result<SignedAck, BlockRejection> {
+        let slot = block.window_id;
+        let applied = verify_block(block, self.max_transactions)
+            .and_then(|()| verify_attestation(block, self.engine.as_ref()))
+            .and_then(|()| self.ledger.lock().unwrap().apply(&block.block))?;
+        info!(
+            "Accepted block for slot {} with {} transactions ({} lamports in fees)",
+            slot, applied.transactions, applied.fees
+        );
+        Ok(SignedAck::sign(&self.identity, slot, &applied.blockhash, &block.ordered_hash))
+    }
+
+    fn rejection(&self, slot: Option<u64>, rejection: BlockRejection) -> SubmitBlockResponse {
+        SubmitBlockResponse::Rejected {
+            slot,
+            code: rejection.code(),
+            reason: rejection.to_string(),
+            validator: self.identity.pubkey().to_string(),
+        }
+    }
+}
+
+async fn handle(service: Arc<ValidatorService>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::POST, "/submit_block") => match hyper::body::to_bytes(request.into_body()).await {
+            Ok(body) => {
+                let (status, reply) = service.submit(&body);
+                Response::builder()
+                    .status(status)
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&reply).unwrap_or_default()))
+            }
+            Err(_) => Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()),
+        },
+        (&Method::GET, "/gossip") => match service.gossip() {
+            Some(gossip) => Response::builder()
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&gossip.stats()).unwrap_or_default())),
+            None => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+        },
+        (&Method::GET, "/healthz") => Response::builder().body(Body::from("ok")),
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+/// Serve `POST /submit_block`, `GET /gossip` and `GET /healthz` on `addr` until `shutdown` resolves
+pub async fn serve<F: Future<Output = ()>>(
+    addr: SocketAddr,
+    service: Arc<ValidatorService>,
+    shutdown: F,
+) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let service = Arc::clone(&service);
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle(Arc::clone(&service), request))) }
+    });
+
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    info!("Validator listening on http://{}/submit_block", server.local_addr());
+    server.with_graceful_shutdown(shutdown).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::tests::{ordered_block, signed_bundle};
+    use block_engine::{BlockSink, HttpBlockSink, SubmissionError};
+    use std::time::Duration;
+
+    async fn start(service: Arc<ValidatorService>) -> (String, tokio::sync::oneshot::Sender<()>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(serve(addr, service, async {
+            let _ = stopped.await;
+        }));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        (format!("http://{}/submit_block", addr), stop)
+    }
+
+    #[test]
+    fn test_signed_ack_verifies_only_untampered() {
+        let identity = Keypair::new();
+        let mut ack = SignedAck::sign(&identity, 9, &Hash::new_unique(), "abc");
+        assert!(ack.verify());
+
+        ack.slot = 10;
+        assert!(!ack.verify());
+    }
+
+    #[tokio::test]
+    async fn test_engine_sink_gets_signed_acceptance() {
+        let service = Arc::new(ValidatorService::new(Keypair::new(), Ledger::default()));
+        let (url, stop) = start(Arc::clone(&service)).await;
+
+        let block = ordered_block(3, Hash::default(), vec![signed_bundle(&Keypair::new(), 10, 500)]).await;
+        let ack = HttpBlockSink::new(url.clone()).submit_block(&block).await.unwrap();
+        assert_eq!(ack.status, "accepted");
+        assert_eq!(ack.slot, Some(3));
+        assert_eq!(ack.validator, Some(service.pubkey().to_string()));
+        assert_eq!(service.ledger_tip(), Some((3, block.block.blockhash)));
+
+        let reply: SubmitBlockResponse = reqwest::Client::new()
+            .post(&url)
+            .json(&ordered_block(4, block.block.blockhash, vec![signed_bundle(&Keypair::new(), 10, 500)]).await)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        match reply {
+            SubmitBlockResponse::Accepted(ack) => assert!(ack.verify()),
+            other => panic!("expected acceptance, got {:?}", other),
+        }
+
+        stop.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejections_are_structured() {
+        let service = Arc::new(ValidatorService::new(Keypair::new(), Ledger::default()));
+        let (url, stop) = start(service).await;
+
+        let mut block = ordered_block(3, Hash::default(), vec![signed_bundle(&Keypair::new(), 10, 500)]).await;
+        block.ordered_hash = "0".repeat(64);
+        let response = reqwest::Client::new().post(&url).json(&block).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+        let reply: SubmitBlockResponse = response.json().await.unwrap();
+        assert!(matches!(
+            reply,
+            SubmitBlockResponse::Rejected { slot: Some(3), code: RejectionCode::OrderedHashMismatch, .. }
+        ));
+
+        // The engine treats a rejection as final rather than retrying
+        let err = HttpBlockSink::new(url.clone()).submit_block(&block).await.unwrap_err();
+        assert!(matches!(err, SubmissionError::Rejected(reason) if reason.contains("ordered hash mismatch")));
+
+        let malformed = reqwest::Client::new().post(&url).body("{}").send().await.unwrap();
+        assert_eq!(malformed.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        stop.send(()).unwrap();
+    }
+}
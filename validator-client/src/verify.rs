@@ -0,0 +1,282 @@
+use block_engine::ordered_block::compute_ordered_hash;
+use block_engine::{verify_block_attestation, AttestationError, Bundle, OrderedBlock};
+use solana_sdk::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Why a block was refused. Every variant maps to a stable `code` returned to the engine.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BlockRejection {
+    #[error("malformed block: {0}")]
+    Malformed(String),
+    #[error("window {window_id} does not match block slot {slot}")]
+    SlotMismatch { window_id: u64, slot: u64 },
+    #[error("ordered hash mismatch: advertised {advertised}, computed {computed}")]
+    OrderedHashMismatch { advertised: String, computed: String },
+    #[error("ordered bundle {position} does not match the block: {reason}")]
+    BundleMismatch { position: usize, reason: String },
+    #[error("bundle {position} tips {tip}, more than bundle {previous} ahead of it")]
+    OrderingViolation { position: usize, previous: usize, tip: u64 },
+    #[error("block transactions are not the bundle transactions in order")]
+    TransactionMismatch,
+    #[error("block advertises {advertised} lamports of tips, bundles total {computed}")]
+    TipMismatch { advertised: u64, computed: u64 },
+    #[error("blockhash mismatch: advertised {advertised}, computed {computed}")]
+    BlockhashMismatch { advertised: String, computed: String },
+    #[error("block has {count} transactions, limit is {limit}")]
+    TooManyTransactions { count: usize, limit: usize },
+    #[error("transaction {index} has an invalid signature")]
+    InvalidSignature { index: usize },
+    #[error("transaction {index} was already included in an earlier block")]
+    DuplicateTransaction { index: usize },
+    #[error("transaction {index} failed: {reason}")]
+    TransactionFailed { index: usize, reason: String },
+    #[error("slot {slot} is not after the last accepted slot {last}")]
+    StaleSlot { slot: u64, last: u64 },
+    #[error("parent {parent} is not the last accepted block {expected}")]
+    ParentMismatch { parent: String, expected: String },
+    #[error("invalid engine attestation: {0}")]
+    InvalidAttestation(AttestationError),
+}
+
+impl BlockRejection {
+    /// Machine-readable rejection code
+    pub fn code(&self) -> RejectionCode {
+        match self {
+            BlockRejection::Malformed(_) => RejectionCode::MalformedBlock,
+            BlockRejection::SlotMismatch { .. } => RejectionCode::SlotMismatch,
+            BlockRejection::OrderedHashMismatch { .. } => RejectionCode::OrderedHashMismatch,
+            BlockRejection::BundleMismatch { .. } => RejectionCode::BundleMismatch,
+            BlockRejection::OrderingViolation { .. } => RejectionCode::OrderingViolation,
+            BlockRejection::TransactionMismatch => RejectionCode::TransactionMismatch,
+            BlockRejection::TipMismatch { .. } => RejectionCode::TipMismatch,
+            BlockRejection::BlockhashMismatch { .. } => RejectionCode::BlockhashMismatch,
+            BlockRejection::TooManyTransactions { .. } => RejectionCode::TooManyTransactions,
+            BlockRejection::InvalidSignature { .. } => RejectionCode::InvalidSignature,
+            BlockRejection::DuplicateTransaction { .. } => RejectionCode::DuplicateTransaction,
+            BlockRejection::TransactionFailed { .. } => RejectionCode::TransactionFailed,
+            BlockRejection::StaleSlot { .. } => RejectionCode::StaleSlot,
+            BlockRejection::ParentMismatch { .. } => RejectionCode::ParentMismatch,
+            BlockRejection::InvalidAttestation(_) => RejectionCode::InvalidAttestation,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionCode {
+    MalformedBlock,
+    SlotMismatch,
+    OrderedHashMismatch,
+    BundleMismatch,
+    OrderingViolation,
+    TransactionMismatch,
+    TipMismatch,
+    BlockhashMismatch,
+    TooManyTransactions,
+    InvalidSignature,
+    DuplicateTransaction,
+    TransactionFailed,
+    StaleSlot,
+    ParentMismatch,
+    InvalidAttestation,
+}
+
+/// Check everything about a block that does not depend on ledger state: the advertised ordering
+/// and hashes are recomputed from the contents rather than trusted.
+pub fn verify_block(block: &OrderedBlock, max_transactions: usize) -> Result<(), BlockRejection> {
+    let inner = &block.block;
+    if block.window_id != inner.slot {
+        return Err(BlockRejection::SlotMismatch { window_id: block.window_id, slot: inner.slot });
+    }
+
+    let computed = compute_ordered_hash(&block.ordered_bundles);
+    if computed != block.ordered_hash {
+        return Err(BlockRejection::OrderedHashMismatch { advertised: block.ordered_hash.clone(), computed });
+    }
+
+    if block.ordered_bundles.len() != inner.bundles.len() {
+        return Err(BlockRejection::BundleMismatch {
+            position: block.ordered_bundles.len().min(inner.bundles.len()),
+            reason: format!(
+                "{} ordered bundles but {} in the block",
+                block.ordered_bundles.len(),
+                inner.bundles.len()
+            ),
+        });
+    }
+    for (position, (wire, bundle)) in block.ordered_bundles.iter().zip(&inner.bundles).enumerate() {
+        let decoded = wire
+            .to_bundle()
+            .map_err(|e| BlockRejection::BundleMismatch { position, reason: e.to_string() })?;
+        if decoded.id != bundle.id || decoded.tip_lamports != bundle.tip_lamports {
+            return Err(BlockRejection::BundleMismatch {
+                position,
+                reason: format!("advertised {} but the block holds {}", wire.id, bundle.id),
+            });
+        }
+        if decoded.transactions != bundle.transactions {
+            return Err(BlockRejection::BundleMismatch {
+                position,
+                reason: "transactions differ from the advertised bundle".to_string(),
+            });
+        }
+    }
+
+    verify_priority_order(&inner.bundles)?;
+
+    let expected: Vec<_> = inner.bundles.iter().flat_map(|b| b.transactions.iter()).collect();
+    if expected.len() != inner.transactions.len() || expected.iter().zip(&inner.transactions).any(|(a, b)| *a != b) {
+        return Err(BlockRejection::TransactionMismatch);
+    }
+    if inner.transactions.len() > max_transactions {
+        return Err(BlockRejection::TooManyTransactions { count: inner.transactions.len(), limit: max_transactions });
+    }
+
+    let tips: u64 = inner.bundles.iter().map(|b| b.tip_lamports).sum();
+    if tips != inner.total_tips {
+        return Err(BlockRejection::TipMismatch { advertised: inner.total_tips, computed: tips });
+    }
+
+    let blockhash = inner.compute_blockhash();
+    if blockhash != inner.blockhash {
+        return Err(BlockRejection::BlockhashMismatch {
+            advertised: inner.blockhash.to_string(),
+            computed: blockhash.to_string(),
+        });
+    }
+
+    for (index, tx) in inner.transactions.iter().enumerate() {
+        if tx.verify().is_err() {
+            return Err(BlockRejection::InvalidSignature { index });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the engine's signature over the block. With a trusted `engine`, the block must be attested
+/// by exactly that key; otherwise an attestation is only checked when present.
+pub fn verify_attestation(block: &OrderedBlock, engine: Option<&Pubkey>) -> Result<(), BlockRejection> {
+    if block.attestation.is_none() && engine.is_none() {
+        return Ok(());
+    }
+    verify_block_attestation(block, engine)
+        .map(|_| ())
+        .map_err(BlockRejection::InvalidAttestation)
+}
+
+/// Bundles must appear from highest to lowest tip, as the priority fee auction ranks them
+fn verify_priority_order(bundles: &[Bundle]) -> Result<(), BlockRejection> {
+    for (position, pair) in bundles.windows(2).enumerate() {
+        if pair[1].tip_lamports > pair[0].tip_lamports {
+            return Err(BlockRejection::OrderingViolation {
+                position: position + 1,
+                previous: position,
+                tip: pair[1].tip_lamports,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use block_engine::BlockAssembler;
+    use solana_sdk::{
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+
+    pub(crate) fn signed_bundle(payer: &Keypair, lamports: u64, tip: u64) -> Bundle {
+        let tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), lamports)],
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        );
+        Bundle::new(vec![tx], tip, payer.pubkey().to_string())
+    }
+
+    /// Build a block exactly as the engine does
+    pub(crate) async fn ordered_block(slot: u64, parent_hash: Hash, bundles: Vec<Bundle>) -> OrderedBlock {
+        let assembler = BlockAssembler::new(Pubkey::new_unique(), 100, 1_000_000);
+        let template = assembler.create_block_template(slot, parent_hash);
+        let block = assembler.assemble_block(template, bundles).await.unwrap();
+        OrderedBlock::new(slot, block).unwrap()
+    }
+
+    async fn sample_block() -> OrderedBlock {
+        let bundles = vec![
+            signed_bundle(&Keypair::new(), 100, 3000),
+            signed_bundle(&Keypair::new(), 100, 1000),
+        ];
+        ordered_block(5, Hash::default(), bundles).await
+    }
+
+    #[tokio::test]
+    async fn test_engine_block_verifies() {
+        assert_eq!(verify_block(&sample_block().await, 100), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_tampering_is_detected() {
+        let mut block = sample_block().await;
+        block.ordered_bundles.swap(0, 1);
+        assert_eq!(verify_block(&block, 100).unwrap_err().code(), RejectionCode::OrderedHashMismatch);
+
+        // Reordering consistently everywhere still breaks the tip ordering and the blockhash
+        let mut block = sample_block().await;
+        block.ordered_bundles.swap(0, 1);
+        block.block.bundles.swap(0, 1);
+        block.ordered_hash = compute_ordered_hash(&block.ordered_bundles);
+        assert_eq!(verify_block(&block, 100).unwrap_err().code(), RejectionCode::OrderingViolation);
+
+        let mut block = sample_block().await;
+        block.block.total_tips += 1;
+        assert_eq!(verify_block(&block, 100).unwrap_err().code(), RejectionCode::TipMismatch);
+
+        let mut block = sample_block().await;
+        block.block.timestamp += 1;
+        assert_eq!(verify_block(&block, 100).unwrap_err().code(), RejectionCode::BlockhashMismatch);
+
+        let block = sample_block().await;
+        assert_eq!(verify_block(&block, 1).unwrap_err().code(), RejectionCode::TooManyTransactions);
+    }
+
+    #[tokio::test]
+    async fn test_forged_signature_rejected() {
+        let mut block = sample_block().await;
+        let forged = Keypair::new().sign_message(b"not this transaction");
+        block.block.transactions[0].signatures[0] = forged;
+        block.block.bundles[0].transactions[0].signatures[0] = forged;
+        block.ordered_bundles[0] = block_engine::WireBundle::from_bundle(&block.block.bundles[0]).unwrap();
+        block.block.blockhash = block.block.compute_blockhash();
+
+        assert_eq!(verify_block(&block, 100), Err(BlockRejection::InvalidSignature { index: 0 }));
+    }
+
+    #[tokio::test]
+    async fn test_attestation_checked_against_trusted_engine() {
+        let engine = Keypair::new();
+        let block = sample_block().await;
+        let inputs = block.ordered_bundles.iter().map(|b| b.id.clone()).collect();
+        let attested = block.clone().with_window_inputs(inputs).signed(&engine);
+
+        assert_eq!(verify_attestation(&block, None), Ok(()));
+        assert_eq!(verify_attestation(&attested, Some(&engine.pubkey())), Ok(()));
+        assert_eq!(
+            verify_attestation(&block, Some(&engine.pubkey())),
+            Err(BlockRejection::InvalidAttestation(AttestationError::Missing))
+        );
+
+        let mut tampered = attested.clone();
+        tampered.block.parent_hash = Hash::new_unique();
+        assert_eq!(verify_attestation(&tampered, None).unwrap_err().code(), RejectionCode::InvalidAttestation);
+        assert!(verify_attestation(&attested, Some(&Keypair::new().pubkey())).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Runs validator-client processes on localhost and watches blocks and votes gossip between them
+
+use block_engine::{BlockAssembler, Bundle, OrderedBlock};
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use validator_client::GossipStats;
+
+/// Kills the validator process when the test ends, pass or fail
+struct Node {
+    process: Child,
+    http: SocketAddr,
+    gossip: SocketAddr,
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+impl Node {
+    fn spawn(http: SocketAddr, gossip: SocketAddr, peers: &[SocketAddr], keypair: &Path, validators: &[Pubkey]) -> Self {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_validator-client"));
+        command
+            .args(["--listen", &http.to_string(), "--gossip-listen", &gossip.to_string()])
+            .args(["--keypair", keypair.to_str().unwrap()])
+            .args(["--log-level", "warn"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        for peer in peers {
+            command.args(["--peer", &peer.to_string()]);
+        }
+        for validator in validators {
+            command.args(["--validator", &validator.to_string()]);
+        }
+        let process = command.spawn().expect("failed to start validator-client");
+        Self { process, http, gossip }
+    }
+
+    async fn stats(&self, client: &reqwest::Client) -> Option<GossipStats> {
+        let response = client.get(format!("http://{}/gossip", self.http)).send().await.ok()?;
+        response.json().await.ok()
+    }
+}
+
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+async fn block(slot: u64, parent_hash: Hash) -> OrderedBlock {
+    let payer = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 10)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        Hash::new_unique(),
+    );
+    let assembler = BlockAssembler::new(Pubkey::new_unique(), 100, 1_000_000);
+    let template = assembler.create_block_template(slot, parent_hash);
+    let block = assembler
+        .assemble_block(template, vec![Bundle::new(vec![tx], 500, payer.pubkey().to_string())])
+        .await
+        .unwrap();
+    OrderedBlock::new(slot, block).unwrap()
+}
+
+/// Poll every node until `done` holds for all of them, returning their last stats
+async fn wait_for(
+    client: &reqwest::Client,
+    nodes: &[Node],
+    done: impl Fn(&GossipStats) -> bool,
+) -> Vec<GossipStats> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let mut stats = Vec::new();
+        for node in nodes {
+            if let Some(s) = node.stats(client).await {
+                stats.push(s);
+            }
+        }
+        if stats.len() == nodes.len() && stats.iter().all(&done) {
+            return stats;
+        }
+        assert!(Instant::now() < deadline, "gossip did not settle: {:?}", stats);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_blocks_and_votes_propagate_between_processes() {
+    // a <-> b <-> c: blocks submitted at either end cross b to reach the other
+    let addrs: Vec<(SocketAddr, SocketAddr)> = (0..3).map(|_| (free_addr(), free_addr())).collect();
+    let gossip: Vec<SocketAddr> = addrs.iter().map(|(_, g)| *g).collect();
+    let peers = [vec![gossip[1]], vec![gossip[0], gossip[2]], vec![gossip[1]]];
+    let dir = tempfile::tempdir().unwrap();
+    let keypairs: Vec<_> = (0..3)
+        .map(|i| {
+            let path = dir.path().join(format!("validator-{}.json", i));
+            let keypair = Keypair::new();
+            solana_sdk::signature::write_keypair_file(&keypair, &path).unwrap();
+            (path, keypair.pubkey())
+        })
+        .collect();
+    let validators: Vec<Pubkey> = keypairs.iter().map(|(_, pubkey)| *pubkey).collect();
+    let nodes: Vec<Node> = addrs
+        .iter()
+        .zip(&peers)
+        .zip(&keypairs)
+        .map(|(((http, gossip), peers), (keypair, _))| Node::spawn(*http, *gossip, peers, keypair, &validators))
+        .collect();
+    let client = reqwest::Client::new();
+    let started = wait_for(&client, &nodes, |s| s.blocks.is_empty()).await;
+    assert_eq!(started.iter().map(|s| s.peers).collect::<Vec<_>>(), vec![1, 2, 1]);
+    assert_eq!(nodes[0].gossip, gossip[0]);
+
+    let first = block(1, Hash::default()).await;
+    let response = client
+        .post(format!("http://{}/submit_block", nodes[0].http))
+        .json(&first)
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let first_hash = first.block.blockhash.to_string();
+    let stats = wait_for(&client, &nodes, |s| s.blocks.iter().any(|b| b.blockhash == first_hash && b.votes == 3)).await;
+    for s in &stats {
+        let seen = &s.blocks[0];
+        assert_eq!(seen.origin.as_deref(), Some(started[0].validator.as_str()));
+        let delay = seen.delay_ms.expect("block arrived, not just its votes");
+        assert!(delay < 5_000, "{} took {}ms to see the block", s.validator, delay);
+    }
+
+    // The far end extends the chain and the origin hears about it two hops away
+    let second = block(2, first.block.blockhash).await;
+    client
+        .post(format!("http://{}/submit_block", nodes[2].http))
+        .json(&second)
+        .send()
+        .await
+        .unwrap();
+    let second_hash = second.block.blockhash.to_string();
+    let stats = wait_for(&client, &nodes, |s| s.blocks.iter().any(|b| b.blockhash == second_hash && b.votes == 3)).await;
+    assert!(stats.iter().all(|s| s.blocks.len() == 2 && s.blocks[1].slot == 2));
+    assert_eq!(stats[0].blocks[1].origin.as_deref(), Some(started[2].validator.as_str()));
+}
@@ -0,0 +1,121 @@
+//! Drives three validator-client processes through the engine's leader-targeted sink
+
+use block_engine::{
+    BlockAssembler, BlockSink, Bundle, HttpBlockSink, LeaderSchedule, LeaderTargetedSink, OrderedBlock, StakedValidator,
+};
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Kills the validator process when the test ends, pass or fail
+struct Node {
+    process: Child,
+    http: SocketAddr,
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+impl Node {
+    fn spawn(http: SocketAddr, keypair: &Path) -> Self {
+        let process = Command::new(env!("CARGO_BIN_EXE_validator-client"))
+            .args(["--listen", &http.to_string()])
+            .args(["--keypair", keypair.to_str().unwrap()])
+            .args(["--log-level", "warn"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start validator-client");
+        Self { process, http }
+    }
+
+    fn url(&self) -> String {
+        format!("http://{}/submit_block", self.http)
+    }
+
+    async fn wait_ready(&self, client: &reqwest::Client) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while client.get(format!("http://{}/healthz", self.http)).send().await.is_err() {
+            assert!(Instant::now() < deadline, "validator on {} did not start", self.http);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+async fn block(slot: u64, parent_hash: Hash) -> OrderedBlock {
+    let payer = Keypair::new();
+    let tx = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 10)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        Hash::new_unique(),
+    );
+    let assembler = BlockAssembler::new(Pubkey::new_unique(), 100, 1_000_000);
+    let template = assembler.create_block_template(slot, parent_hash);
+    let block = assembler
+        .assemble_block(template, vec![Bundle::new(vec![tx], 500, payer.pubkey().to_string())])
+        .await
+        .unwrap();
+    OrderedBlock::new(slot, block).unwrap()
+}
+
+#[tokio::test]
+async fn test_every_validator_ledger_follows_a_rotating_leader() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut nodes = Vec::new();
+    let mut identities = Vec::new();
+    for i in 0..3 {
+        let path = dir.path().join(format!("validator-{}.json", i));
+        let keypair = Keypair::new();
+        solana_sdk::signature::write_keypair_file(&keypair, &path).unwrap();
+        identities.push(keypair.pubkey());
+        nodes.push(Node::spawn(free_addr(), &path));
+    }
+    let client = reqwest::Client::new();
+    for node in &nodes {
+        node.wait_ready(&client).await;
+    }
+
+    let schedule = LeaderSchedule::new(identities.iter().map(|&identity| StakedValidator::new(identity, 1)))
+        .with_slots_per_leader(1);
+    let mut sink = LeaderTargetedSink::new(schedule.clone());
+    for (identity, node) in identities.iter().zip(&nodes) {
+        sink = sink.with_leader(*identity, Box::new(HttpBlockSink::new(node.url())));
+    }
+
+    // Enough slots for the lead to change hands; each block builds on the one before
+    let slots = 1..=12u64;
+    let leaders: std::collections::HashSet<Pubkey> = slots.clone().filter_map(|slot| schedule.leader_for_slot(slot)).collect();
+    assert!(leaders.len() >= 2, "schedule never rotated");
+    let mut parent = Hash::default();
+    for slot in slots {
+        let next = block(slot, parent).await;
+        let ack = sink.submit_block(&next).await.unwrap();
+        assert_eq!(ack.status, "accepted", "slot {}", slot);
+        parent = next.block.blockhash;
+    }
+
+    // Every ledger, leader or not, holds the whole chain and accepts the next block
+    let next = block(13, parent).await;
+    for node in &nodes {
+        let response = client.post(node.url()).json(&next).send().await.unwrap();
+        let status = response.status();
+        assert!(status.is_success(), "{} refused the next block: {}", node.http, response.text().await.unwrap());
+    }
+}